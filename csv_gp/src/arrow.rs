@@ -0,0 +1,362 @@
+use std::{collections::BTreeMap, io, path::Path, str::FromStr, sync::Arc};
+
+use arrow_array::{ArrayRef, RecordBatch, RecordBatchReader, StringArray};
+use arrow_cast::{cast_with_options, CastOptions};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+
+use crate::{
+    cell::Cell,
+    csv_details::CSVDetails,
+    error::{CSVError, InvalidOption},
+    parser::{read_header, Delimiter, ParseOptions},
+    valid_file::{read_valid_rows, ValidFileOptions},
+};
+
+/// Options changing how the valid rows are converted to Arrow
+#[derive(Debug, Clone)]
+pub struct ArrowOptions {
+    /// Schema with a field per column, the values are converted to the field types.
+    /// The columns are named after the header when not given.
+    pub schema: Option<SchemaRef>,
+    /// Types of the columns named after the header when no schema is given, by column name.
+    /// The columns that aren't listed are text.
+    pub column_types: BTreeMap<String, DataType>,
+    /// Number of rows per record batch
+    pub batch_size: usize,
+}
+
+impl Default for ArrowOptions {
+    fn default() -> Self {
+        Self {
+            schema: None,
+            column_types: BTreeMap::new(),
+            batch_size: 65536,
+        }
+    }
+}
+
+type ValidRows = Box<dyn Iterator<Item = io::Result<(usize, Vec<Cell>)>> + Send>;
+
+/// Reads the valid rows of a checked file as Arrow record batches, the header is used for the column names
+pub struct ValidRowsReader {
+    rows: ValidRows,
+    schema: SchemaRef,
+    batch_size: usize,
+    parse_options: ParseOptions,
+}
+
+impl ValidRowsReader {
+    /// Converts a batch of rows to arrays of the schema's types, empty cells are null unless the column is text
+    fn to_batch(&self, rows: Vec<Vec<Cell>>) -> Result<RecordBatch, ArrowError> {
+        let cast_options = CastOptions {
+            safe: false,
+            ..Default::default()
+        };
+
+        let columns = self
+            .schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let text = field.data_type() == &DataType::Utf8;
+                let values: StringArray = rows
+                    .iter()
+                    .map(|row| {
+                        let value = row[i].value(&self.parse_options);
+                        (text || !value.is_empty()).then_some(value)
+                    })
+                    .collect();
+
+                if text {
+                    Ok(Arc::new(values) as ArrayRef)
+                } else {
+                    cast_with_options(&values, field.data_type(), &cast_options)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        RecordBatch::try_new(self.schema.clone(), columns)
+    }
+}
+
+impl Iterator for ValidRowsReader {
+    type Item = Result<RecordBatch, ArrowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rows = match (&mut self.rows)
+            .take(self.batch_size)
+            .map(|row| row.map(|(_, cells)| cells))
+            .collect::<io::Result<Vec<_>>>()
+        {
+            Ok(rows) if rows.is_empty() => return None,
+            Ok(rows) => rows,
+            Err(e) => return Some(Err(e.into())),
+        };
+
+        Some(self.to_batch(rows))
+    }
+}
+
+impl RecordBatchReader for ValidRowsReader {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Reads the valid rows of the file at `path` checked into `csv_details` as Arrow record batches,
+/// with the fixes in `options` applied
+pub fn read_valid_rows_arrow(
+    path: impl AsRef<Path> + Send + 'static,
+    csv_details: &CSVDetails,
+    delimiter: impl Into<Delimiter>,
+    encoding: &str,
+    parse_options: &ParseOptions,
+    options: &ValidFileOptions,
+    arrow_options: &ArrowOptions,
+) -> Result<ValidRowsReader, CSVError> {
+    let delimiter = delimiter.into();
+    let delimiter = delimiter.as_str();
+    let schema = match arrow_options.schema.clone() {
+        Some(schema) if schema.fields().len() != csv_details.column_count => {
+            return Err(ArrowError::SchemaError(format!(
+                "the schema has {} fields, but the file has {} columns",
+                schema.fields().len(),
+                csv_details.column_count
+            ))
+            .into())
+        }
+        Some(schema) => schema,
+        None => {
+            let names = read_header(&path, delimiter, encoding, parse_options)?;
+            if let Some(missing) = arrow_options
+                .column_types
+                .keys()
+                .find(|&name| !names.contains(name))
+            {
+                return Err(ArrowError::SchemaError(format!(
+                    "there is no column {missing} in the header"
+                ))
+                .into());
+            }
+
+            let fields: Vec<_> = names
+                .into_iter()
+                .map(|name| match arrow_options.column_types.get(&name) {
+                    Some(data_type) => Field::new(name, data_type.clone(), true),
+                    None => Field::new(name, DataType::Utf8, false),
+                })
+                .collect();
+            Arc::new(Schema::new(fields))
+        }
+    };
+
+    let rows = read_valid_rows(
+        path,
+        csv_details,
+        delimiter,
+        encoding,
+        parse_options,
+        options,
+    )?
+    .filter(|row| !matches!(row, Ok((0, _))));
+
+    Ok(ValidRowsReader {
+        rows: Box::new(rows),
+        schema,
+        batch_size: arrow_options.batch_size.max(1),
+        parse_options: parse_options.clone(),
+    })
+}
+
+/// Parses column types given as comma separated `name:type` pairs, e.g. `id:Int64,amount:Float64`
+pub fn parse_column_types(s: &str) -> Result<BTreeMap<String, DataType>, InvalidOption> {
+    s.split(',')
+        .map(|pair| {
+            let invalid = || InvalidOption::Value {
+                option: "column types",
+                value: pair.into(),
+            };
+
+            let (name, data_type) = pair.rsplit_once(':').ok_or_else(invalid)?;
+            let data_type = DataType::from_str(data_type.trim()).map_err(|_| invalid())?;
+
+            Ok((name.trim().to_string(), data_type))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use arrow_array::{
+        cast::AsArray,
+        ffi_stream::{ArrowArrayStreamReader, FFI_ArrowArrayStream},
+        types::{Float64Type, Int64Type},
+        Array,
+    };
+    use tempfile::NamedTempFile;
+
+    use crate::checker::check_file;
+
+    use super::*;
+
+    fn checked_file(content: &str) -> (NamedTempFile, CSVDetails) {
+        let file = NamedTempFile::new().unwrap();
+        fs::write(file.path(), content).unwrap();
+        let csv_details = check_file(file.path(), ',', "utf-8", None::<&Path>).unwrap();
+        (file, csv_details)
+    }
+
+    fn read(
+        file: &NamedTempFile,
+        csv_details: &CSVDetails,
+        schema: Option<SchemaRef>,
+        batch_size: usize,
+    ) -> Result<ValidRowsReader, CSVError> {
+        read_valid_rows_arrow(
+            file.path().to_path_buf(),
+            csv_details,
+            ",",
+            "utf-8",
+            &ParseOptions::default(),
+            &ValidFileOptions::default(),
+            &ArrowOptions {
+                schema,
+                batch_size,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_text_columns() {
+        let (file, csv_details) = checked_file("name,\"a \"\"b\"\"\"\nx,\"1,2\"\ninvalid\ny,\n");
+
+        let batches: Vec<_> = read(
+            &file,
+            &csv_details,
+            None,
+            ArrowOptions::default().batch_size,
+        )
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.schema().field(1).name(), "a \"b\"");
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(batch.column(0).as_string::<i32>().value(1), "y");
+        assert_eq!(batch.column(1).as_string::<i32>().value(0), "1,2");
+        assert_eq!(batch.column(1).as_string::<i32>().value(1), "");
+    }
+
+    #[test]
+    fn test_schema() {
+        let (file, csv_details) = checked_file("name,amount\nx,1\ny,\nz,3\n");
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("name", DataType::Utf8, false),
+            Field::new("amount", DataType::Int64, true),
+        ]));
+
+        let batches: Vec<_> = read(&file, &csv_details, Some(schema), 2)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(batches.len(), 2);
+        let amounts = batches[0].column(1).as_primitive::<Int64Type>();
+        assert_eq!(amounts.value(0), 1);
+        assert!(amounts.is_null(1));
+        assert_eq!(batches[1].column(1).as_primitive::<Int64Type>().value(0), 3);
+    }
+
+    #[test]
+    fn test_schema_mismatch() {
+        let (file, csv_details) = checked_file("name,amount\nx,1\n");
+        let schema = Arc::new(Schema::new(vec![Field::new("name", DataType::Utf8, false)]));
+
+        let error = read(&file, &csv_details, Some(schema), 2).err().unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "Schema error: the schema has 1 fields, but the file has 2 columns"
+        );
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        let (file, csv_details) = checked_file("amount\nx\n");
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "amount",
+            DataType::Int64,
+            true,
+        )]));
+
+        let mut reader = read(&file, &csv_details, Some(schema), 2).unwrap();
+
+        assert!(reader.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_c_stream() {
+        let (file, csv_details) = checked_file("a,b\n1,2\n3,4\n");
+        let reader = read(
+            &file,
+            &csv_details,
+            None,
+            ArrowOptions::default().batch_size,
+        )
+        .unwrap();
+
+        let stream = FFI_ArrowArrayStream::new(Box::new(reader));
+        let imported = ArrowArrayStreamReader::try_new(stream).unwrap();
+
+        assert_eq!(imported.schema().field(0).name(), "a");
+        let rows: usize = imported.map(|batch| batch.unwrap().num_rows()).sum();
+        assert_eq!(rows, 2);
+    }
+
+    #[test]
+    fn test_column_types() {
+        let (file, csv_details) = checked_file("name,amount\nx,1.5\ny,\n");
+        let arrow_options = ArrowOptions {
+            column_types: parse_column_types("amount:Float64").unwrap(),
+            ..Default::default()
+        };
+
+        let mut reader = read_valid_rows_arrow(
+            file.path().to_path_buf(),
+            &csv_details,
+            ",",
+            "utf-8",
+            &ParseOptions::default(),
+            &ValidFileOptions::default(),
+            &arrow_options,
+        )
+        .unwrap();
+
+        assert_eq!(reader.schema().field(0).data_type(), &DataType::Utf8);
+        let batch = reader.next().unwrap().unwrap();
+        let amounts = batch.column(1).as_primitive::<Float64Type>();
+        assert_eq!(amounts.value(0), 1.5);
+        assert!(amounts.is_null(1));
+    }
+
+    #[test]
+    fn test_parse_column_types() {
+        assert_eq!(
+            parse_column_types("a:Int64, b:c:Utf8").unwrap(),
+            BTreeMap::from([
+                ("a".to_string(), DataType::Int64),
+                ("b:c".to_string(), DataType::Utf8)
+            ])
+        );
+        assert_eq!(
+            parse_column_types("a:Nope").unwrap_err().to_string(),
+            "invalid value a:Nope for column types"
+        );
+    }
+}
@@ -0,0 +1,410 @@
+use std::{collections::BTreeMap, fmt::Display, fs, io, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    csv_details::CSVDetails,
+    error::{CSVError, InvalidOption},
+};
+
+/// The issue counts of a check, saved to compare the checks of later versions of a file to
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub row_count: usize,
+    pub column_count: usize,
+    pub valid_row_count: usize,
+    /// Number of occurrences of each kind of issue, as in [`CSVDetails::issue_counts`]
+    pub issues: BTreeMap<String, usize>,
+    /// Whether only part of the file was checked, so the counts are only those of that part
+    #[serde(default)]
+    pub partial: bool,
+}
+
+impl Baseline {
+    pub fn from_details(csv_details: &CSVDetails) -> Self {
+        Self {
+            row_count: csv_details.row_count,
+            column_count: csv_details.column_count,
+            valid_row_count: csv_details.valid_rows.len(),
+            issues: csv_details
+                .issue_counts()
+                .into_iter()
+                .map(|(issue, count)| (issue.to_string(), count))
+                .collect(),
+            partial: csv_details.partial.is_some(),
+        }
+    }
+
+    /// Writes the baseline to `path` as JSON
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CSVError> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::from)?;
+        fs::write(path, json + "\n")?;
+
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CSVError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+/// How much an issue count can go up compared to the baseline before it is a regression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance {
+    /// A number of occurrences
+    Count(usize),
+    /// A percentage of the count in the baseline
+    Percent(f64),
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self::Count(0)
+    }
+}
+
+impl Tolerance {
+    /// The highest count that isn't a regression from `baseline`
+    pub fn allowed(&self, baseline: usize) -> usize {
+        match *self {
+            Self::Count(count) => baseline + count,
+            Self::Percent(percent) => {
+                baseline + (baseline as f64 * percent / 100.0).floor() as usize
+            }
+        }
+    }
+}
+
+impl FromStr for Tolerance {
+    type Err = InvalidOption;
+
+    /// A number of occurrences, e.g. `10`, or a percentage of the baseline count, e.g. `5%`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidOption::Value {
+            option: "tolerance",
+            value: s.into(),
+        };
+
+        match s.trim().strip_suffix('%') {
+            Some(percent) => match percent.trim().parse::<f64>() {
+                Ok(percent) if percent >= 0.0 && percent.is_finite() => Ok(Self::Percent(percent)),
+                _ => Err(invalid()),
+            },
+            None => s.trim().parse().map(Self::Count).map_err(|_| invalid()),
+        }
+    }
+}
+
+/// Tolerances of the comparison to a baseline, per issue
+#[derive(Debug, Clone, Default)]
+pub struct Tolerances {
+    /// Tolerance of the issues without their own
+    pub default: Tolerance,
+    /// Tolerances keyed by the name of the issue, as in [`CSVDetails::issue_counts`]
+    pub issues: BTreeMap<String, Tolerance>,
+}
+
+impl Tolerances {
+    fn validate(&self) -> Result<(), InvalidOption> {
+        let known = CSVDetails::new().issue_counts();
+
+        match self
+            .issues
+            .keys()
+            .find(|issue| !known.iter().any(|(name, _)| name == issue))
+        {
+            Some(issue) => Err(InvalidOption::Value {
+                option: "issue",
+                value: issue.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn get(&self, issue: &str) -> Tolerance {
+        self.issues.get(issue).copied().unwrap_or(self.default)
+    }
+}
+
+/// An issue found a different number of times than in the baseline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueDelta {
+    pub issue: String,
+    pub baseline: usize,
+    pub current: usize,
+    /// Highest count within the tolerance
+    pub allowed: usize,
+}
+
+impl IssueDelta {
+    pub fn is_regression(&self) -> bool {
+        self.current > self.allowed
+    }
+}
+
+impl Display for IssueDelta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "The {} count went from {} to {}",
+            self.issue.replace('_', " "),
+            self.baseline,
+            self.current
+        )?;
+
+        if self.is_regression() {
+            write!(f, ", more than the {} allowed.", self.allowed)
+        } else if self.current > self.baseline {
+            write!(f, ", within the {} allowed.", self.allowed)
+        } else {
+            write!(f, ".")
+        }
+    }
+}
+
+/// Differences between the issue counts of a check and a baseline
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub baseline: Baseline,
+    pub current: Baseline,
+    /// Issues found a different number of times, the issues missing from the baseline aren't compared
+    pub deltas: Vec<IssueDelta>,
+}
+
+impl Comparison {
+    /// The issues found more times than allowed by their tolerance
+    pub fn regressions(&self) -> impl Iterator<Item = &IssueDelta> {
+        self.deltas.iter().filter(|delta| delta.is_regression())
+    }
+
+    pub fn has_regressions(&self) -> bool {
+        self.regressions().next().is_some()
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.current.row_count != self.baseline.row_count {
+            writeln!(
+                f,
+                "There are {} rows, against {} in the baseline.",
+                self.current.row_count, self.baseline.row_count
+            )?;
+        }
+        if self.current.column_count != self.baseline.column_count {
+            writeln!(
+                f,
+                "There are {} columns, against {} in the baseline.",
+                self.current.column_count, self.baseline.column_count
+            )?;
+        }
+
+        for delta in &self.deltas {
+            writeln!(f, "{delta}")?;
+        }
+
+        match self.regressions().count() {
+            0 => writeln!(f, "There are no regressions compared to the baseline."),
+            regressions => writeln!(
+                f,
+                "There are {regressions} regressions compared to the baseline."
+            ),
+        }
+    }
+}
+
+/// Compares the issue counts of a check to a baseline, an issue found more times than its tolerance allows being a
+/// regression. The counts of a partial check can't be compared to those of a full check, or the other way around.
+pub fn compare_to_baseline(
+    csv_details: &CSVDetails,
+    baseline: &Baseline,
+    tolerances: &Tolerances,
+) -> Result<Comparison, InvalidOption> {
+    tolerances.validate()?;
+
+    let current = Baseline::from_details(csv_details);
+    match (current.partial, baseline.partial) {
+        (true, false) => {
+            return Err(InvalidOption::Conflict {
+                option: "a partial check",
+                other: "the baseline of a full check",
+            })
+        }
+        (false, true) => {
+            return Err(InvalidOption::Conflict {
+                option: "a full check",
+                other: "the baseline of a partial check",
+            })
+        }
+        _ => (),
+    }
+
+    let deltas = current
+        .issues
+        .iter()
+        .filter_map(|(issue, &count)| {
+            let &baseline_count = baseline.issues.get(issue)?;
+
+            (count != baseline_count).then(|| IssueDelta {
+                issue: issue.clone(),
+                baseline: baseline_count,
+                current: count,
+                allowed: tolerances.get(issue).allowed(baseline_count),
+            })
+        })
+        .collect();
+
+    Ok(Comparison {
+        baseline: baseline.clone(),
+        current,
+        deltas,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::partial::{PartialCheck, PartialReason};
+
+    use super::*;
+
+    fn details(too_many_columns: &[usize], blank_rows: &[usize]) -> CSVDetails {
+        CSVDetails {
+            row_count: 100,
+            column_count: 3,
+            too_many_columns: too_many_columns.iter().copied().collect(),
+            blank_rows: blank_rows.iter().copied().collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let baseline = Baseline::from_details(&details(&[1, 2], &[]));
+        let file = tempfile::NamedTempFile::new().unwrap();
+
+        baseline.save(file.path()).unwrap();
+
+        assert_eq!(Baseline::load(file.path()).unwrap(), baseline);
+        assert_eq!(baseline.issues["too_many_columns"], 2);
+    }
+
+    #[test]
+    fn test_invalid_baseline_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), "{}").unwrap();
+
+        assert!(Baseline::load(file.path())
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid baseline file"));
+    }
+
+    #[test]
+    fn test_tolerance() {
+        assert_eq!("10".parse::<Tolerance>().unwrap(), Tolerance::Count(10));
+        assert_eq!("5 %".parse::<Tolerance>().unwrap(), Tolerance::Percent(5.0));
+        assert!("-5%".parse::<Tolerance>().is_err());
+        assert!("x".parse::<Tolerance>().is_err());
+
+        assert_eq!(Tolerance::Count(10).allowed(5), 15);
+        assert_eq!(Tolerance::Percent(10.0).allowed(25), 27);
+        assert_eq!(Tolerance::Percent(10.0).allowed(0), 0);
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = Baseline::from_details(&details(&[1], &[5, 6, 7]));
+        let current = details(&[1, 2, 3], &[5]);
+
+        let comparison = compare_to_baseline(&current, &baseline, &Tolerances::default()).unwrap();
+        assert_eq!(
+            comparison.deltas,
+            vec![
+                IssueDelta {
+                    issue: "blank_rows".into(),
+                    baseline: 3,
+                    current: 1,
+                    allowed: 3
+                },
+                IssueDelta {
+                    issue: "too_many_columns".into(),
+                    baseline: 1,
+                    current: 3,
+                    allowed: 1
+                }
+            ]
+        );
+        assert!(comparison.has_regressions());
+        assert_eq!(
+            comparison.to_string(),
+            "The blank rows count went from 3 to 1.\n\
+             The too many columns count went from 1 to 3, more than the 1 allowed.\n\
+             There are 1 regressions compared to the baseline.\n"
+        );
+
+        let tolerances = Tolerances {
+            issues: BTreeMap::from([("too_many_columns".into(), Tolerance::Count(2))]),
+            ..Default::default()
+        };
+        let comparison = compare_to_baseline(&current, &baseline, &tolerances).unwrap();
+        assert!(!comparison.has_regressions());
+    }
+
+    #[test]
+    fn test_partial_check() {
+        let full = details(&[1], &[]);
+        let partial = CSVDetails {
+            partial: Some(PartialCheck {
+                reason: PartialReason::RowLimit,
+                rows_checked: 50,
+                rows_read: 50,
+            }),
+            ..details(&[1], &[])
+        };
+        let tolerances = Tolerances::default();
+
+        assert!(Baseline::from_details(&partial).partial);
+        assert_eq!(
+            compare_to_baseline(&partial, &Baseline::from_details(&full), &tolerances)
+                .unwrap_err()
+                .to_string(),
+            "a partial check can't be used together with the baseline of a full check"
+        );
+        assert_eq!(
+            compare_to_baseline(&full, &Baseline::from_details(&partial), &tolerances)
+                .unwrap_err()
+                .to_string(),
+            "a full check can't be used together with the baseline of a partial check"
+        );
+        assert!(
+            compare_to_baseline(&partial, &Baseline::from_details(&partial), &tolerances).is_ok()
+        );
+    }
+
+    #[test]
+    fn test_baseline_without_partial() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(
+            file.path(),
+            r#"{"row_count": 1, "column_count": 2, "valid_row_count": 1, "issues": {}}"#,
+        )
+        .unwrap();
+
+        assert!(!Baseline::load(file.path()).unwrap().partial);
+    }
+
+    #[test]
+    fn test_unknown_issue() {
+        let tolerances = Tolerances {
+            issues: BTreeMap::from([("bad_quotes".into(), Tolerance::Count(2))]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            compare_to_baseline(&CSVDetails::new(), &Baseline::default(), &tolerances)
+                .unwrap_err()
+                .to_string(),
+            "invalid value bad_quotes for issue"
+        );
+    }
+}
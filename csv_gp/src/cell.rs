@@ -1,6 +1,14 @@
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use regex::Regex;
+
+use crate::{
+    characters::InvisibleCharacter,
+    mojibake::{self, EncodingPair},
+    parser::ParseOptions,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Cell(String);
 
 impl Cell {
@@ -46,9 +54,175 @@ impl Cell {
         self.0.contains(pat)
     }
 
+    /// An unquoted cell with leading or trailing whitespace, e.g. ` abc `
+    pub fn has_whitespace_padding(&self) -> bool {
+        let trimmed = self.0.trim();
+        !trimmed.starts_with('"') && trimmed.len() != self.0.len()
+    }
+
+    /// A quoted cell with whitespace before the opening or after the closing quote, e.g. ` "abc" `
+    pub fn has_whitespace_outside_quotes(&self) -> bool {
+        let trimmed = self.0.trim();
+        is_quoted(trimmed) && trimmed.len() != self.0.len()
+    }
+
+    /// Removes any whitespace around the quotes of a quoted cell, unquoted cells are left untouched
+    pub fn trim_whitespace_outside_quotes(self) -> Self {
+        if self.has_whitespace_outside_quotes() {
+            Self(self.0.trim().to_string())
+        } else {
+            self
+        }
+    }
+
+    /// The cell's text as it is in the file, including any quotes
+    pub fn raw(&self) -> &str {
+        &self.0
+    }
+
+    /// The cell's text without the quotes around a quoted cell and with quoted-quotes unescaped
+    fn unquoted(&self) -> Cow<'_, str> {
+        if is_quoted(&self.0) {
+            Cow::Owned(self.0[1..self.0.len() - 1].replace("\"\"", "\""))
+        } else {
+            Cow::Borrowed(&self.0)
+        }
+    }
+
+    /// The cell's value as it was meant in a file read with `options`: without the quotes around a quoted cell and
+    /// with quoted-quotes unescaped if cells can be quoted, and with escaped characters unescaped
+    pub fn value(&self, options: &ParseOptions) -> Cow<'_, str> {
+        let value = if options.quoting {
+            self.unquoted()
+        } else {
+            Cow::Borrowed(self.0.as_str())
+        };
+
+        match options.escape {
+            // the NULL of MySQL and PostgreSQL, which has no value other than an empty string
+            Some(escape) if value.strip_prefix(escape) == Some("N") => Cow::Borrowed(""),
+            Some(escape) if value.contains(escape) => Cow::Owned(unescape(&value, escape)),
+            _ => value,
+        }
+    }
+
     pub fn invalid_character_count(&self) -> usize {
         self.0.matches('\u{FFFD}').count()
     }
+
+    /// The cell's text without the opening quote, if there is one
+    /// The cell's text without the quotes around it if cells can be `quoting`, to look for a formula in
+    fn formula_content(&self, quoting: bool) -> &str {
+        if !quoting {
+            return &self.0;
+        }
+
+        let content = self.0.strip_prefix('"').unwrap_or(&self.0);
+        content.strip_suffix('"').unwrap_or(content)
+    }
+
+    /// The cell starts with a character that makes spreadsheet software interpret it as a formula, ignoring the
+    /// invisible characters before it as some software drops them. Plain numbers such as `-12.5` and cells matching
+    /// `allowlist` aren't formulas. Quotes around the cell are only looked past when cells can be `quoting`.
+    pub fn is_formula(&self, quoting: bool, allowlist: Option<&Regex>) -> bool {
+        let content = self.formula_content(quoting);
+        let start = content.trim_start_matches(|c| InvisibleCharacter::categorise(c).is_some());
+
+        start.starts_with(['=', '+', '-', '@', '\t', '\r'])
+            && !is_number(content)
+            && !allowlist.is_some_and(|allowlist| allowlist.is_match(content))
+    }
+
+    /// Prefixes a cell that could be interpreted as a formula with `'`, so it is treated as text. The `'` goes inside
+    /// the quotes of a quoted cell when cells can be `quoting`.
+    pub fn neutralise_formula(self, quoting: bool, allowlist: Option<&Regex>) -> Self {
+        if !self.is_formula(quoting, allowlist) {
+            return self;
+        }
+
+        if quoting && self.0.starts_with('"') {
+            Self(format!("\"'{}", &self.0[1..]))
+        } else {
+            Self(format!("'{}", self.0))
+        }
+    }
+
+    /// The encodings the cell's text looks to have been mistakenly encoded and decoded with, if any
+    pub fn mojibake(&self) -> Option<EncodingPair> {
+        mojibake::detect(&self.0)
+    }
+
+    /// Replaces any text that looks like UTF-8 decoded with a single byte encoding with the correctly decoded text
+    pub fn repair_mojibake(self) -> Self {
+        if self.0.is_ascii() {
+            self
+        } else {
+            Self(mojibake::repair(&self.0))
+        }
+    }
+
+    /// The categories of all the invisible or control characters in the cell, one per character
+    pub fn invisible_characters(&self) -> impl Iterator<Item = InvisibleCharacter> + '_ {
+        self.0.chars().filter_map(InvisibleCharacter::categorise)
+    }
+
+    /// Replaces every invisible or control character with `with`
+    pub fn replace_invisible_characters(self, with: &str) -> Self {
+        if self.invisible_characters().next().is_none() {
+            return self;
+        }
+
+        let mut replaced = String::with_capacity(self.0.len());
+        for c in self.0.chars() {
+            if InvisibleCharacter::categorise(c).is_some() {
+                replaced.push_str(with);
+            } else {
+                replaced.push(c);
+            }
+        }
+
+        Self(replaced)
+    }
+}
+
+/// A number with an optional sign, and optionally `.` or `,` as decimal or thousands separators
+fn is_number(s: &str) -> bool {
+    let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+
+    digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.ends_with(|c: char| c.is_ascii_digit())
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+}
+
+/// Removes every escape character, decoding the escape sequences of MySQL and PostgreSQL such as `\0` or `\t` and
+/// keeping any other character it escapes
+fn unescape(s: &str, escape: char) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == escape {
+            result.extend(chars.next().map(|escaped| match escaped {
+                '0' => '\0',
+                'b' => '\u{8}',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                'Z' => '\u{1A}',
+                escaped => escaped,
+            }));
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+fn is_quoted(s: &str) -> bool {
+    s.len() >= 2 && s.starts_with('"') && s.ends_with('"')
 }
 
 impl AsRef<[u8]> for Cell {
@@ -87,6 +261,45 @@ mod tests {
         assert!(Cell::new("\"5\"\"379'319'026\"\",\"\"SINV-00110094\"").correctly_quoted())
     }
 
+    #[test]
+    fn test_value_in_dialect() {
+        let options = ParseOptions {
+            quoting: false,
+            escape: Some('\\'),
+            ..Default::default()
+        };
+
+        assert_eq!(Cell::new("\"a\\\tb\\\\").value(&options), "\"a\tb\\");
+        assert_eq!(Cell::new("\"a\"").value(&ParseOptions::default()), "a");
+    }
+
+    #[test]
+    fn test_value_escape_sequences() {
+        let options = ParseOptions {
+            quoting: false,
+            escape: Some('\\'),
+            ..Default::default()
+        };
+
+        assert_eq!(Cell::new("\\N").value(&options), "");
+        assert_eq!(Cell::new("a\\N").value(&options), "aN");
+        assert_eq!(Cell::new("a\\0b").value(&options), "a\0b");
+        assert_eq!(Cell::new("a\\tb\\nc\\rd").value(&options), "a\tb\nc\rd");
+        assert_eq!(Cell::new("\\b\\Z\\,").value(&options), "\u{8}\u{1A},");
+    }
+
+    #[test]
+    fn test_value() {
+        assert_eq!(Cell::new("\"a,b\"").raw(), "\"a,b\"");
+        assert_eq!(Cell::new("abc").value(&ParseOptions::default()), "abc");
+        assert_eq!(Cell::new("\"a,b\"").value(&ParseOptions::default()), "a,b");
+        assert_eq!(
+            Cell::new("\"say \"\"hi\"\"\"").value(&ParseOptions::default()),
+            "say \"hi\""
+        );
+        assert_eq!(Cell::new("\"\"").value(&ParseOptions::default()), "");
+    }
+
     #[test]
     fn test_no_quotes() {
         assert!(Cell::new("test").correctly_quoted())
@@ -106,4 +319,137 @@ mod tests {
     fn test_does_not_end() {
         assert!(!Cell::new("\"test").correctly_quoted())
     }
+
+    #[test]
+    fn test_whitespace_padding() {
+        assert!(Cell::new(" test").has_whitespace_padding());
+        assert!(Cell::new("test\t").has_whitespace_padding());
+        assert!(Cell::new("  ").has_whitespace_padding());
+        assert!(!Cell::new("te st").has_whitespace_padding());
+        assert!(!Cell::new(" \"test\" ").has_whitespace_padding());
+    }
+
+    #[test]
+    fn test_whitespace_outside_quotes() {
+        assert!(Cell::new(" \"test\" ").has_whitespace_outside_quotes());
+        assert!(Cell::new("\"test\" ").has_whitespace_outside_quotes());
+        assert!(!Cell::new("\" test \"").has_whitespace_outside_quotes());
+        assert!(!Cell::new(" test ").has_whitespace_outside_quotes());
+        assert!(!Cell::new(" \"").has_whitespace_outside_quotes());
+    }
+
+    #[test]
+    fn test_trim_whitespace_outside_quotes() {
+        assert_eq!(
+            Cell::new(" \"test\" ").trim_whitespace_outside_quotes(),
+            Cell::new("\"test\"")
+        );
+        assert_eq!(
+            Cell::new(" test ").trim_whitespace_outside_quotes(),
+            Cell::new(" test ")
+        );
+    }
+
+    #[test]
+    fn test_invisible_characters() {
+        assert_eq!(
+            Cell::new("a\0b\u{200B}\0")
+                .invisible_characters()
+                .collect::<Vec<_>>(),
+            vec![
+                InvisibleCharacter::Null,
+                InvisibleCharacter::ZeroWidth,
+                InvisibleCharacter::Null
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replace_invisible_characters() {
+        assert_eq!(
+            Cell::new("a\0b\u{A0}c").replace_invisible_characters(""),
+            Cell::new("abc")
+        );
+        assert_eq!(
+            Cell::new("a\u{A0}b").replace_invisible_characters(" "),
+            Cell::new("a b")
+        );
+    }
+
+    #[test]
+    fn test_is_formula() {
+        for formula in [
+            "=1+1",
+            "+1+1",
+            "-2+3",
+            "@SUM(A1)",
+            "\"=HYPERLINK(\"\"http://x\"\")\"",
+            "\t=1",
+            "\r=1",
+            "-",
+            "\u{200B}=HYPERLINK(1)",
+            "\0@SUM(A1)",
+        ] {
+            assert!(Cell::new(formula).is_formula(true, None), "{formula}");
+        }
+    }
+
+    #[test]
+    fn test_is_not_formula() {
+        for value in [
+            "1=1", "test", "-12", "-12.50", "+1,000.5", "\"-12\"", " =1", "",
+        ] {
+            assert!(!Cell::new(value).is_formula(true, None), "{value}");
+        }
+    }
+
+    #[test]
+    fn test_formula_allowlist() {
+        let allowlist = Regex::new(r"^(-|\+\d[\d ]+)$").unwrap();
+
+        for value in ["-", "+44 20 7946 0000", "\"-\""] {
+            assert!(Cell::new(value).is_formula(true, None), "{value}");
+            assert!(
+                !Cell::new(value).is_formula(true, Some(&allowlist)),
+                "{value}"
+            );
+        }
+        assert!(Cell::new("=1").is_formula(true, Some(&allowlist)));
+    }
+
+    #[test]
+    fn test_neutralise_formula() {
+        assert_eq!(
+            Cell::new("=1+1").neutralise_formula(true, None),
+            Cell::new("'=1+1")
+        );
+        assert_eq!(
+            Cell::new("\"@SUM(A1)\"").neutralise_formula(true, None),
+            Cell::new("\"'@SUM(A1)\"")
+        );
+        assert_eq!(
+            Cell::new("\u{200B}=1").neutralise_formula(true, None),
+            Cell::new("'\u{200B}=1")
+        );
+        assert_eq!(
+            Cell::new("-12").neutralise_formula(true, None),
+            Cell::new("-12")
+        );
+    }
+
+    #[test]
+    fn test_formula_without_quoting() {
+        assert!(!Cell::new("\"=1").is_formula(false, None));
+        assert!(!Cell::new("\"@SUM(A1)\"").is_formula(false, None));
+        assert!(Cell::new("=1").is_formula(false, None));
+
+        assert_eq!(
+            Cell::new("\"=1").neutralise_formula(false, None),
+            Cell::new("\"=1")
+        );
+        assert_eq!(
+            Cell::new("=\"x\"").neutralise_formula(false, None),
+            Cell::new("'=\"x\"")
+        );
+    }
 }
@@ -0,0 +1,100 @@
+/// List of (line number, column index) of cells, optionally only keeping a number of examples while still
+/// counting all of them
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CellLocations {
+    cells: Vec<(usize, usize)>,
+    limit: Option<usize>,
+    /// Number of cells added past the limit
+    overflow: usize,
+    /// Last cell added past the limit, so adding the same one twice in a row is only counted once
+    last_overflow: Option<(usize, usize)>,
+}
+
+impl CellLocations {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// A list only keeping the first `limit` cells added to it, while still counting all of them
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a cell, unless it's the same as the last cell added
+    pub fn push(&mut self, row: usize, column: usize) {
+        if self.last() == Some((row, column)) {
+            return;
+        }
+
+        if self.limit.is_none_or(|limit| self.cells.len() < limit) {
+            self.cells.push((row, column));
+        } else {
+            self.overflow += 1;
+            self.last_overflow = Some((row, column));
+        }
+    }
+
+    fn last(&self) -> Option<(usize, usize)> {
+        self.last_overflow.or(self.cells.last().copied())
+    }
+
+    /// Number of cells added, including any past the limit
+    pub fn len(&self) -> usize {
+        self.cells.len() + self.overflow
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether cells past the limit were left out of the list
+    pub fn is_truncated(&self) -> bool {
+        self.overflow > 0
+    }
+
+    /// Iterates over the cells kept in the list, in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.cells.iter().copied()
+    }
+}
+
+impl<const N: usize> From<[(usize, usize); N]> for CellLocations {
+    fn from(cells: [(usize, usize); N]) -> Self {
+        let mut locations = Self::new();
+        cells.into_iter().for_each(|(r, c)| locations.push(r, c));
+        locations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_cell_once() {
+        let locations = CellLocations::from([(1, 0), (1, 0), (1, 1), (2, 0)]);
+
+        assert_eq!(locations.len(), 3);
+        assert_eq!(
+            locations.iter().collect::<Vec<_>>(),
+            vec![(1, 0), (1, 1), (2, 0)]
+        );
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut locations = CellLocations::with_limit(1);
+
+        locations.push(1, 0);
+        locations.push(2, 3);
+        locations.push(2, 3);
+        locations.push(4, 1);
+
+        assert_eq!(locations.len(), 3);
+        assert!(locations.is_truncated());
+        assert_eq!(locations.iter().collect::<Vec<_>>(), vec![(1, 0)]);
+    }
+}
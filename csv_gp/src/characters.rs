@@ -0,0 +1,127 @@
+use std::fmt::Display;
+
+use crate::cell_locations::CellLocations;
+
+/// Categories of invisible or control characters that commonly break loading a file downstream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InvisibleCharacter {
+    /// NUL bytes (U+0000)
+    Null,
+    /// The SUB character (U+001A), used as an end of file marker by old DOS tools
+    EndOfFile,
+    /// Any other C0 or C1 control character, excluding tabs and line breaks
+    Control,
+    /// Zero-width spaces and joiners, and byte order marks in the middle of the text
+    ZeroWidth,
+    /// Bidirectional text overrides, embeddings, isolates and marks
+    Bidi,
+    /// Non-breaking spaces, which look like normal spaces
+    NonBreakingSpace,
+}
+
+impl InvisibleCharacter {
+    pub fn categorise(c: char) -> Option<Self> {
+        match c {
+            '\t' | '\n' | '\r' => None,
+            '\u{0}' => Some(Self::Null),
+            '\u{1A}' => Some(Self::EndOfFile),
+            c if c.is_control() => Some(Self::Control),
+            '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' => Some(Self::ZeroWidth),
+            '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}' => {
+                Some(Self::Bidi)
+            }
+            '\u{A0}' | '\u{2007}' | '\u{202F}' => Some(Self::NonBreakingSpace),
+            _ => None,
+        }
+    }
+
+    /// Short identifier of the category
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Null => "null",
+            Self::EndOfFile => "end_of_file",
+            Self::Control => "control",
+            Self::ZeroWidth => "zero_width",
+            Self::Bidi => "bidi",
+            Self::NonBreakingSpace => "non_breaking_space",
+        }
+    }
+}
+
+impl Display for InvisibleCharacter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let description = match self {
+            Self::Null => "null",
+            Self::EndOfFile => "end of file (\\x1A)",
+            Self::Control => "control",
+            Self::ZeroWidth => "zero-width",
+            Self::Bidi => "bidirectional text control",
+            Self::NonBreakingSpace => "non-breaking space",
+        };
+
+        write!(f, "{description}")
+    }
+}
+
+/// Number of characters of a category found in the file, and the cells they were found in
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CharacterFindings {
+    /// Number of characters in the file
+    pub count: usize,
+    /// List of (line number, column index) of the cells containing the characters
+    pub locations: CellLocations,
+}
+
+impl CharacterFindings {
+    /// Findings only keeping the first `limit` cell locations
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            count: 0,
+            locations: CellLocations::with_limit(limit),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_categorise() {
+        assert_eq!(
+            InvisibleCharacter::categorise('\0'),
+            Some(InvisibleCharacter::Null)
+        );
+        assert_eq!(
+            InvisibleCharacter::categorise('\u{1A}'),
+            Some(InvisibleCharacter::EndOfFile)
+        );
+        assert_eq!(
+            InvisibleCharacter::categorise('\u{B}'),
+            Some(InvisibleCharacter::Control)
+        );
+        assert_eq!(
+            InvisibleCharacter::categorise('\u{85}'),
+            Some(InvisibleCharacter::Control)
+        );
+        assert_eq!(
+            InvisibleCharacter::categorise('\u{200B}'),
+            Some(InvisibleCharacter::ZeroWidth)
+        );
+        assert_eq!(
+            InvisibleCharacter::categorise('\u{202E}'),
+            Some(InvisibleCharacter::Bidi)
+        );
+        assert_eq!(
+            InvisibleCharacter::categorise('\u{A0}'),
+            Some(InvisibleCharacter::NonBreakingSpace)
+        );
+    }
+
+    #[test]
+    fn test_categorise_ordinary() {
+        for c in ['a', ' ', '\t', '\n', '\r', 'ü', '\u{FFFD}'] {
+            assert_eq!(InvisibleCharacter::categorise(c), None);
+        }
+    }
+}
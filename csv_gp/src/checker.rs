@@ -1,49 +1,537 @@
-use std::{cmp::Ordering, io, path::Path};
+use std::{cmp::Ordering, fs, io, path::Path};
+
+use regex::Regex;
 
 use crate::{
-    cell::Cell, csv_details::CSVDetails, error::CSVError, parser::parse_file,
-    valid_file::save_valid_file,
+    cell::Cell,
+    characters::CharacterFindings,
+    csv_details::CSVDetails,
+    duplicates::{confirm_duplicates, DuplicateTracker},
+    error::{CSVError, InvalidOption},
+    footer::{FooterDetector, FooterOptions},
+    parser::{parse_file_counting, read_rows, Delimiter, ParseOptions},
+    partial::{PartialCheck, PartialReason, Sample, Sampler},
+    preamble::{detect_preamble, SkippedLines},
+    progress::{CheckHooks, ProgressTracker},
+    row_set::RowSet,
+    strict::check_strict,
+    valid_file::{save_rejected_rows, save_valid_file, ValidFileOptions},
 };
 
+/// Options changing which additional checks are run on a file
+#[derive(Debug, Clone, Default)]
+pub struct CheckOptions {
+    /// Options used when parsing the rows of the file
+    pub parse: ParseOptions,
+    /// Indices of the columns that together identify a row, used to find rows with duplicate keys
+    ///
+    /// Finding duplicates keeps a hash and a line number in memory for every row, see `summary_examples` to skip it.
+    pub duplicate_key_columns: Option<Vec<usize>>,
+    /// Options used when writing out the valid rows
+    pub valid_file: ValidFileOptions,
+    /// Only keep totals and the first N example line numbers of each issue, so the memory used doesn't grow
+    /// with the size of the file. Duplicates and the number of columns per line aren't checked in this mode.
+    pub summary_examples: Option<usize>,
+    /// Stop checking after finding this many rows with issues. Can't be used with the valid or rejected rows outputs.
+    pub max_issues: Option<usize>,
+    /// Only check the first N rows (including the header) of the file. Can't be used with the valid or rejected rows
+    /// outputs.
+    pub max_rows: Option<usize>,
+    /// Only check a sample of the rows, the header is always checked. Can't be used with the valid or rejected rows
+    /// outputs.
+    pub sample: Option<Sample>,
+    /// Also parse the whole file strictly according to RFC 4180, reporting every deviation
+    pub strict: bool,
+    /// Find the lines before the header from the column counts of the first rows, rather than skipping
+    /// `parse.skip_lines` lines
+    pub detect_preamble: bool,
+    /// Find the summary rows at the end of the file, and report them apart from the rows with too few columns
+    pub footer: Option<FooterOptions>,
+}
+
+impl CheckOptions {
+    /// Checks the options go together, `valid_rows_output` being whether the valid rows are written out
+    pub(crate) fn validate(&self, valid_rows_output: bool) -> Result<(), InvalidOption> {
+        if self.detect_preamble && self.parse.skip_lines > 0 {
+            return Err(InvalidOption::Conflict {
+                option: "detect preamble",
+                other: "skip lines",
+            });
+        }
+        // the strict parser reads every line of the file
+        if self.strict
+            && (self.detect_preamble
+                || self.parse.skip_lines > 0
+                || self.parse.comment_prefix.is_some())
+        {
+            return Err(InvalidOption::Conflict {
+                option: "strict mode",
+                other: "skipping lines",
+            });
+        }
+
+        if self.parse.fixed_width.is_some() {
+            if self.parse.quoting {
+                return Err(InvalidOption::Conflict {
+                    option: "fixed width",
+                    other: "quoting",
+                });
+            }
+            if self.strict {
+                return Err(InvalidOption::Conflict {
+                    option: "strict mode",
+                    other: "fixed width",
+                });
+            }
+        }
+
+        match &self.sample {
+            Some(Sample::Stride(0)) => {
+                return Err(InvalidOption::Value {
+                    option: "sample stride",
+                    value: "0".into(),
+                })
+            }
+            Some(Sample::Random { fraction, .. }) if !(*fraction > 0.0 && *fraction <= 1.0) => {
+                return Err(InvalidOption::Value {
+                    option: "sample fraction",
+                    value: fraction.to_string(),
+                })
+            }
+            _ => (),
+        }
+
+        if let Some(footer) = &self.footer {
+            if footer.patterns.is_empty()
+                && footer.record_count_pattern.is_none()
+                && !footer.short_rows
+            {
+                return Err(InvalidOption::Empty("footer patterns"));
+            }
+            // the valid rows of a sample can't be compared to the number of records of the whole file
+            if self.sample.is_some() && footer.record_count_pattern.is_some() {
+                return Err(InvalidOption::Conflict {
+                    option: "sampling",
+                    other: "footer record count",
+                });
+            }
+        }
+
+        // the rows that weren't checked aren't known to be valid or not, so they would be missing from the outputs
+        let partial = if self.sample.is_some() {
+            Some("sampling")
+        } else if self.max_issues.is_some() {
+            Some("max issues")
+        } else if self.max_rows.is_some() {
+            Some("max rows")
+        } else {
+            None
+        };
+        if let Some(option) = partial {
+            if valid_rows_output {
+                return Err(InvalidOption::Conflict {
+                    option,
+                    other: "valid rows output",
+                });
+            }
+            if self.valid_file.rejected_rows_path.is_some() {
+                return Err(InvalidOption::Conflict {
+                    option,
+                    other: "rejected rows output",
+                });
+            }
+        }
+
+        if self.summary_examples.is_none() {
+            return Ok(());
+        }
+
+        if self.duplicate_key_columns.is_some() {
+            Err(InvalidOption::Conflict {
+                option: "duplicate key columns",
+                other: "summary mode",
+            })
+        } else if self.valid_file.deduplicate.is_some() {
+            Err(InvalidOption::Conflict {
+                option: "deduplicate",
+                other: "summary mode",
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
 /// Check the file located at `path`, interpreting the file with `delimiter` and `encoding`.
 /// If `valid_rows_output_path` is passed, a file containing the valid rows will be written to the specified path.
 pub fn check_file(
     path: impl AsRef<Path>,
-    delimiter: char,
+    delimiter: impl Into<Delimiter>,
     encoding: &str,
     valid_rows_output_path: Option<impl AsRef<Path>>,
 ) -> Result<CSVDetails, CSVError> {
-    let rows = parse_file(&path, delimiter, encoding)?;
+    check_file_with_options(
+        path,
+        delimiter,
+        encoding,
+        valid_rows_output_path,
+        &CheckOptions::default(),
+    )
+}
 
-    let csv_details = check_rows(rows, delimiter)?;
+/// Same as [`check_file`], with additional `options` for the checks and the valid rows output.
+pub fn check_file_with_options(
+    path: impl AsRef<Path>,
+    delimiter: impl Into<Delimiter>,
+    encoding: &str,
+    valid_rows_output_path: Option<impl AsRef<Path>>,
+    options: &CheckOptions,
+) -> Result<CSVDetails, CSVError> {
+    check_file_with_hooks(
+        path,
+        delimiter,
+        encoding,
+        valid_rows_output_path,
+        options,
+        CheckHooks::default(),
+    )
+}
+
+/// Same as [`check_file_with_options`], reporting the progress and polling for cancellation through `hooks`
+pub fn check_file_with_hooks(
+    path: impl AsRef<Path>,
+    delimiter: impl Into<Delimiter>,
+    encoding: &str,
+    valid_rows_output_path: Option<impl AsRef<Path>>,
+    options: &CheckOptions,
+    hooks: CheckHooks,
+) -> Result<CSVDetails, CSVError> {
+    check_file_inspecting(
+        path,
+        delimiter.into().as_str(),
+        encoding,
+        valid_rows_output_path,
+        options,
+        hooks,
+        |_| (),
+    )
+}
+
+/// Same as [`check_file_with_hooks`], passing every row to `inspect` as it is read, before it is checked
+pub(crate) fn check_file_inspecting(
+    path: impl AsRef<Path>,
+    delimiter: &str,
+    encoding: &str,
+    valid_rows_output_path: Option<impl AsRef<Path>>,
+    options: &CheckOptions,
+    hooks: CheckHooks,
+    mut inspect: impl FnMut(&[Cell]),
+) -> Result<CSVDetails, CSVError> {
+    options.validate(valid_rows_output_path.is_some())?;
+
+    let detected_options;
+    let options = if options.detect_preamble {
+        let mut parse = options.parse.clone();
+        parse.skip_lines = detect_preamble(&path, delimiter, encoding, &options.parse)?;
+        detected_options = CheckOptions {
+            parse,
+            ..options.clone()
+        };
+        &detected_options
+    } else {
+        options
+    };
+
+    let mut progress = ProgressTracker::new(hooks, fs::metadata(&path)?.len());
+
+    let skipped_lines = SkippedLines {
+        comments: options
+            .summary_examples
+            .map_or_else(RowSet::new, RowSet::with_limit),
+        ..Default::default()
+    };
+    let mut rows = parse_file_counting(
+        &path,
+        delimiter,
+        encoding,
+        &options.parse,
+        progress.byte_counter(),
+    )?
+    .recording_skipped_lines(skipped_lines);
+
+    let mut csv_details = check_rows(
+        rows.by_ref().inspect(|row| {
+            if let Ok(cells) = row {
+                inspect(cells)
+            }
+        }),
+        Some(delimiter),
+        options,
+        valid_rows_output_path.is_some() || options.valid_file.rejected_rows_path.is_some(),
+        &mut progress,
+    )?;
+    csv_details.skipped_lines = rows.into_skipped_lines().unwrap_or_default();
+
+    if !csv_details.duplicate_rows.is_empty()
+        || csv_details
+            .duplicate_key_rows
+            .as_ref()
+            .is_some_and(|g| !g.is_empty())
+    {
+        let rows = read_rows(&path, delimiter, encoding, &options.parse)?;
+        confirm_duplicates(
+            &mut csv_details,
+            options.duplicate_key_columns.as_deref(),
+            progress.until_cancelled(rows),
+        )?;
+        progress.poll()?;
+    }
 
     if let Some(valid_rows_path) = valid_rows_output_path {
-        save_valid_file(&path, &csv_details, delimiter, encoding, valid_rows_path)?
+        save_valid_file(
+            &path,
+            &csv_details,
+            delimiter,
+            encoding,
+            valid_rows_path,
+            &options.parse,
+            &options.valid_file,
+            &mut progress,
+        )?
+    }
+
+    if options.strict {
+        csv_details.strict = Some(check_strict(
+            &path,
+            delimiter,
+            encoding,
+            &options.parse,
+            options.summary_examples,
+        )?);
+    }
+
+    if let Some(rejected_rows_path) = &options.valid_file.rejected_rows_path {
+        save_rejected_rows(
+            &path,
+            &csv_details,
+            delimiter,
+            encoding,
+            rejected_rows_path,
+            &options.parse,
+            &mut progress,
+        )?
     }
 
     Ok(csv_details)
 }
 
-fn check_rows(
+/// Checks the rows of a file, whether read from a CSV file or another source. The `delimiter` is `None` when the
+/// cells weren't separated by one, e.g. in a spreadsheet, so the delimiter check is skipped.
+pub(crate) fn check_rows(
     rows: impl Iterator<Item = io::Result<Vec<Cell>>>,
-    delimiter: char,
+    delimiter: Option<&str>,
+    options: &CheckOptions,
+    keep_valid_rows: bool,
+    progress: &mut ProgressTracker,
 ) -> Result<CSVDetails, CSVError> {
-    let mut csv_details = CSVDetails::new();
+    let mut csv_details = match options.summary_examples {
+        Some(limit) => CSVDetails::with_example_limit(limit),
+        None => CSVDetails::new(),
+    };
+    if keep_valid_rows {
+        // all the valid rows are needed to write them out, even in summary mode
+        csv_details.valid_rows = RowSet::new();
+    }
+
+    let mut checker = RowChecker {
+        csv_details,
+        delimiter,
+        options,
+        duplicates: DuplicateTracker::new(options.duplicate_key_columns.clone()),
+        sampler: options.sample.as_ref().map(Sample::sampler),
+        rows_checked: 0,
+        issues: 0,
+        valid_header: false,
+    };
+    let mut footer = options
+        .footer
+        .as_ref()
+        .map(|footer| FooterDetector::new(footer, &options.parse));
+
+    let mut rows_read = 0;
+    let mut stopped = None;
 
     for (i, cells_result) in rows.enumerate() {
+        if options.max_rows.is_some_and(|max| i >= max) {
+            stopped = Some(PartialReason::RowLimit);
+            break;
+        }
+
+        progress.update(rows_read, checker.issues)?;
+
         let cells = cells_result?;
-        csv_details.column_count_per_line.push(cells.len());
+        rows_read += 1;
+        if options.summary_examples.is_none() {
+            checker.csv_details.column_count_per_line.push(cells.len());
+        }
         if i == 0 {
-            csv_details.column_count = cells.len()
+            checker.csv_details.column_count = cells.len()
         }
 
-        check_row(&mut csv_details, &cells, delimiter, i);
+        // the last rows are only checked once it's known they aren't part of the footer
+        let row = match footer.as_mut() {
+            Some(footer) if i != 0 => footer.delay((i, cells)),
+            _ => Some((i, cells)),
+        };
+
+        if let Some((i, cells)) = row {
+            stopped = checker.check(i, &cells);
+            if stopped.is_some() {
+                break;
+            }
+        }
+    }
+
+    if let Some(footer) = footer.filter(|_| stopped != Some(PartialReason::IssueLimit)) {
+        // the footer can only be told apart once the end of the file is reached
+        let rows = if stopped.is_none() {
+            let (found, rows) = footer.finish(checker.csv_details.column_count);
+            checker.csv_details.footer = found;
+            rows
+        } else {
+            footer.into_rows()
+        };
+
+        for (i, cells) in rows {
+            if let Some(reason) = checker.check(i, &cells) {
+                stopped = Some(reason);
+                break;
+            }
+        }
     }
 
+    progress.finish(rows_read, checker.issues);
+
+    let RowChecker {
+        mut csv_details,
+        duplicates,
+        sampler,
+        rows_checked,
+        valid_header,
+        ..
+    } = checker;
+
+    let reason = stopped.or(sampler.map(|_| PartialReason::Sample));
+    csv_details.partial = reason.map(|reason| PartialCheck {
+        reason,
+        rows_checked,
+        rows_read,
+    });
+
+    csv_details.footer.valid_record_count =
+        csv_details.valid_rows.len() - usize::from(valid_header);
+
+    (csv_details.duplicate_rows, csv_details.duplicate_key_rows) = duplicates.into_groups();
+
     Ok(csv_details)
 }
 
+/// The state of checking the rows of a file one by one
+struct RowChecker<'a> {
+    csv_details: CSVDetails,
+    delimiter: Option<&'a str>,
+    options: &'a CheckOptions,
+    duplicates: DuplicateTracker,
+    sampler: Option<Sampler>,
+    rows_checked: usize,
+    issues: usize,
+    /// Whether the header was valid, as it isn't a record. The valid rows only keep the first line numbers in summary
+    /// mode, so they can't tell.
+    valid_header: bool,
+}
+
+impl RowChecker<'_> {
+    /// Checks the row unless it's left out of the sample, returning why checking should stop if it should
+    fn check(&mut self, i: usize, cells: &[Cell]) -> Option<PartialReason> {
+        // the header is always checked, as the column count comes from it
+        if i != 0 && self.sampler.as_mut().is_some_and(|s| !s.includes(i)) {
+            return None;
+        }
+
+        let valid_rows = self.csv_details.valid_rows.len();
+        let format = RowFormat {
+            delimiter: self.delimiter.unwrap_or_default(),
+            quoting: self.options.parse.quoting,
+            delimited: self.delimiter.is_some() && self.options.parse.fixed_width.is_none(),
+            formula_allowlist: self.options.valid_file.formula_allowlist.as_ref(),
+        };
+        check_row_with_format(&mut self.csv_details, cells, format, i);
+        self.rows_checked += 1;
+
+        if !cells.is_empty() && self.options.summary_examples.is_none() {
+            self.duplicates.add(cells, i);
+        }
+
+        if self.csv_details.valid_rows.len() == valid_rows {
+            self.issues += 1;
+            if self
+                .options
+                .max_issues
+                .is_some_and(|max| self.issues >= max)
+            {
+                return Some(PartialReason::IssueLimit);
+            }
+        } else if i == 0 {
+            self.valid_header = true;
+        }
+
+        None
+    }
+}
+
+/// How the cells of a row were read, deciding which checks apply to them
+#[derive(Debug, Clone, Copy)]
+struct RowFormat<'a> {
+    delimiter: &'a str,
+    /// Whether cells can be quoted, the quoting checks are skipped otherwise
+    quoting: bool,
+    /// Whether cells are separated by the delimiter rather than read from fixed-width columns or a spreadsheet, the
+    /// delimiter check is skipped otherwise
+    delimited: bool,
+    /// Cells matching this aren't reported as formulas
+    formula_allowlist: Option<&'a Regex>,
+}
+
+/// Checks `content` as a comma separated UTF-8 file
+#[cfg(test)]
+fn check_content(content: &str, options: &CheckOptions) -> Result<CSVDetails, CSVError> {
+    let file = crate::test_utils::temp_file(content);
+
+    check_file_with_options(file.path(), ',', "utf-8", None::<&Path>, options)
+}
+
+/// Checks a row of a file delimited by `delimiter`, with cells that can be quoted
+#[cfg(test)]
+#[allow(clippy::ptr_arg)]
 fn check_row(csv_details: &mut CSVDetails, cells: &Vec<Cell>, delimiter: char, row_number: usize) {
+    let delimiter = delimiter.to_string();
+    let format = RowFormat {
+        delimiter: &delimiter,
+        quoting: true,
+        delimited: true,
+        formula_allowlist: None,
+    };
+
+    check_row_with_format(csv_details, cells, format, row_number)
+}
+
+/// Checks a row, the checks that don't apply to the `format` of the row being skipped
+fn check_row_with_format(
+    csv_details: &mut CSVDetails,
+    cells: &[Cell],
+    format: RowFormat,
+    row_number: usize,
+) {
     let blank_row = cells.is_empty();
 
     // Cell checks
@@ -55,15 +543,50 @@ fn check_row(csv_details: &mut CSVDetails, cells: &Vec<Cell>, delimiter: char, r
 
     let mut all_empty = true;
 
-    for cell in cells {
-        all_correctly_quoted &= cell.correctly_quoted();
+    let mut has_whitespace_padding = false;
+    let mut has_whitespace_outside_quotes = false;
+
+    for (column, cell) in cells.iter().enumerate() {
+        if format.quoting {
+            all_correctly_quoted &= cell.correctly_quoted();
+            has_quoted_quote |= !cell.is_empty() && cell.contains("\"\"");
+        }
 
-        has_quoted_quote |= !cell.is_empty() && cell.contains("\"\"");
         has_quoted_newline |= cell.contains("\n");
-        has_quoted_delimiter |= cell.contains(&delimiter.to_string());
+        if format.delimited {
+            has_quoted_delimiter |= cell.contains(format.delimiter);
+        }
 
         all_empty &= cell.is_empty();
+
+        has_whitespace_padding |= cell.has_whitespace_padding();
+        has_whitespace_outside_quotes |= cell.has_whitespace_outside_quotes();
         csv_details.invalid_character_count += cell.invalid_character_count();
+
+        if cell.is_formula(format.quoting, format.formula_allowlist) {
+            csv_details.formula_injection.push(row_number, column);
+        }
+
+        let limit = csv_details.example_limit;
+
+        if let Some(encodings) = cell.mojibake() {
+            csv_details
+                .mojibake
+                .entry(encodings)
+                .or_insert_with(|| limit.map_or_else(RowSet::new, RowSet::with_limit))
+                .insert(row_number);
+        }
+
+        for category in cell.invisible_characters() {
+            let findings = csv_details
+                .invisible_characters
+                .entry(category)
+                .or_insert_with(|| {
+                    limit.map_or_else(CharacterFindings::default, CharacterFindings::with_limit)
+                });
+            findings.count += 1;
+            findings.locations.push(row_number, column);
+        }
     }
 
     // Length checks
@@ -80,40 +603,48 @@ fn check_row(csv_details: &mut CSVDetails, cells: &Vec<Cell>, delimiter: char, r
 
     // Write results
     if has_quoted_quote {
-        csv_details.quoted_quote.push(row_number);
+        csv_details.quoted_quote.insert(row_number);
         if all_correctly_quoted {
-            csv_details.quoted_quote_correctly.push(row_number);
+            csv_details.quoted_quote_correctly.insert(row_number);
         }
     }
 
     if has_quoted_newline {
-        csv_details.quoted_newline.push(row_number);
+        csv_details.quoted_newline.insert(row_number);
     }
 
     if has_quoted_delimiter {
-        csv_details.quoted_delimiter.push(row_number);
+        csv_details.quoted_delimiter.insert(row_number);
+    }
+
+    if has_whitespace_padding {
+        csv_details.whitespace_padding.insert(row_number);
+    }
+
+    if has_whitespace_outside_quotes {
+        csv_details.whitespace_outside_quotes.insert(row_number);
     }
 
     if all_empty && !blank_row {
-        csv_details.all_empty_rows.push(row_number);
+        csv_details.all_empty_rows.insert(row_number);
     }
 
     if blank_row {
-        csv_details.blank_rows.push(row_number);
+        csv_details.blank_rows.insert(row_number);
     } else {
         csv_details.row_count += 1;
     }
 
     if !all_correctly_quoted {
-        csv_details.incorrect_cell_quote.push(row_number);
+        csv_details.incorrect_cell_quote.insert(row_number);
     }
 
     if too_few_columns {
-        csv_details.too_few_columns.push(row_number);
+        csv_details.too_few_columns.insert(row_number);
     }
 
     if too_many_columns {
-        csv_details.too_many_columns.push(row_number);
+        csv_details.too_many_columns.insert(row_number);
     }
 
     if all_correctly_quoted && !too_few_columns && !too_many_columns && !blank_row {
@@ -250,3 +781,749 @@ mod check_row_tests {
         assert_eq!(csv_details.valid_rows, HashSet::from([0]));
     }
 }
+
+#[cfg(test)]
+mod whitespace_tests {
+    use crate::row_set::RowSet;
+
+    use super::*;
+
+    #[test]
+    fn test_whitespace_padding() {
+        let mut csv_details = CSVDetails::new();
+
+        check_row(&mut csv_details, &vec![Cell::new("test")], ',', 0);
+        check_row(&mut csv_details, &vec![Cell::new(" test")], ',', 1);
+        check_row(&mut csv_details, &vec![Cell::new(" \"test\"")], ',', 2);
+
+        assert_eq!(csv_details.whitespace_padding, RowSet::from([1]));
+        assert_eq!(csv_details.whitespace_outside_quotes, RowSet::from([2]));
+        assert_eq!(csv_details.incorrect_cell_quote, RowSet::from([2]));
+    }
+}
+
+#[cfg(test)]
+mod invisible_characters_tests {
+    use crate::{cell_locations::CellLocations, characters::InvisibleCharacter};
+
+    use super::*;
+
+    #[test]
+    fn test_invisible_characters() {
+        let mut csv_details = CSVDetails::new();
+
+        check_row(
+            &mut csv_details,
+            &vec![Cell::new("a"), Cell::new("b")],
+            ',',
+            0,
+        );
+        check_row(
+            &mut csv_details,
+            &vec![Cell::new("\0\0"), Cell::new("b\u{A0}")],
+            ',',
+            1,
+        );
+        check_row(
+            &mut csv_details,
+            &vec![Cell::new("a"), Cell::new("\0")],
+            ',',
+            2,
+        );
+
+        assert_eq!(
+            csv_details
+                .invisible_characters
+                .get(&InvisibleCharacter::Null),
+            Some(&CharacterFindings {
+                count: 3,
+                locations: CellLocations::from([(1, 0), (2, 1)])
+            })
+        );
+        assert_eq!(
+            csv_details
+                .invisible_characters
+                .get(&InvisibleCharacter::NonBreakingSpace),
+            Some(&CharacterFindings {
+                count: 1,
+                locations: CellLocations::from([(1, 1)])
+            })
+        );
+        assert_eq!(csv_details.invisible_characters.len(), 2);
+    }
+}
+
+#[cfg(test)]
+mod mojibake_tests {
+    use crate::{mojibake::EncodingPair, row_set::RowSet};
+
+    use super::*;
+
+    #[test]
+    fn test_mojibake() {
+        let mut csv_details = CSVDetails::new();
+
+        check_row(&mut csv_details, &vec![Cell::new("Müller")], ',', 0);
+        check_row(
+            &mut csv_details,
+            &vec![Cell::new("MÃ¼ller"), Cell::new("SÃ¶hne")],
+            ',',
+            1,
+        );
+
+        assert_eq!(
+            csv_details.mojibake.into_iter().collect::<Vec<_>>(),
+            vec![(
+                EncodingPair {
+                    encoded_as: "UTF-8",
+                    decoded_as: "windows-1252"
+                },
+                RowSet::from([1])
+            )]
+        );
+    }
+}
+
+#[cfg(test)]
+mod formula_injection_tests {
+    use crate::cell_locations::CellLocations;
+
+    use super::*;
+
+    #[test]
+    fn test_formula_injection() {
+        let mut csv_details = CSVDetails::new();
+
+        check_row(
+            &mut csv_details,
+            &vec![Cell::new("a"), Cell::new("-5")],
+            ',',
+            0,
+        );
+        check_row(
+            &mut csv_details,
+            &vec![Cell::new("=1+1"), Cell::new("\"@cmd\"")],
+            ',',
+            1,
+        );
+
+        assert_eq!(
+            csv_details.formula_injection,
+            CellLocations::from([(1, 0), (1, 1)])
+        );
+    }
+
+    #[test]
+    fn test_formula_injection_without_quoting() {
+        let options = CheckOptions {
+            parse: ParseOptions {
+                quoting: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let csv_details = check_content("a,b\n\"=1,=2\n", &options).unwrap();
+
+        assert_eq!(csv_details.formula_injection, CellLocations::from([(1, 1)]));
+    }
+}
+
+#[cfg(test)]
+mod check_rows_tests {
+    use std::time::Duration;
+
+    use crate::progress::{CancellationToken, Progress};
+
+    use super::*;
+
+    fn rows(input: &[&[&str]]) -> impl Iterator<Item = io::Result<Vec<Cell>>> {
+        input
+            .iter()
+            .map(|r| Ok(r.iter().map(|c| Cell::new(*c)).collect()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn test_duplicate_rows() {
+        let input: &[&[&str]] = &[&["a", "b"], &["1", "x"], &[], &["1", "x"], &[]];
+
+        let csv_details = check_rows(
+            rows(input),
+            Some(","),
+            &CheckOptions::default(),
+            false,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(csv_details.duplicate_rows, vec![vec![1, 3]]);
+        assert_eq!(csv_details.duplicate_key_rows, None);
+    }
+
+    #[test]
+    fn test_without_quoting() {
+        let input: &[&[&str]] = &[&["a", "b"], &["5\"", "say \"\"hi"]];
+        let options = CheckOptions {
+            parse: ParseOptions {
+                quoting: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let csv_details = check_rows(
+            rows(input),
+            Some(","),
+            &options,
+            false,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(csv_details.incorrect_cell_quote, RowSet::new());
+        assert_eq!(csv_details.quoted_quote, RowSet::new());
+        assert_eq!(csv_details.valid_rows, RowSet::from([0, 1]));
+    }
+
+    #[test]
+    fn test_duplicate_key_rows() {
+        let input: &[&[&str]] = &[&["id", "v"], &["1", "x"], &["2", "x"], &["1", "y"]];
+        let options = CheckOptions {
+            duplicate_key_columns: Some(vec![0]),
+            ..Default::default()
+        };
+
+        let csv_details = check_rows(
+            rows(input),
+            Some(","),
+            &options,
+            false,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(csv_details.duplicate_rows, Vec::<Vec<usize>>::new());
+        assert_eq!(csv_details.duplicate_key_rows, Some(vec![vec![1, 3]]));
+    }
+
+    #[test]
+    fn test_summary() {
+        let input: &[&[&str]] = &[&["a", "b"], &["1"], &["2"], &["3"], &["4", "x"], &["5"]];
+        let options = CheckOptions {
+            summary_examples: Some(2),
+            ..Default::default()
+        };
+
+        let csv_details = check_rows(
+            rows(input),
+            Some(","),
+            &options,
+            false,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(csv_details.too_few_columns.len(), 4);
+        assert_eq!(
+            csv_details.too_few_columns.iter().collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(csv_details.valid_rows.len(), 2);
+        assert!(csv_details.column_count_per_line.is_empty());
+        assert_eq!(csv_details.example_limit, Some(2));
+    }
+
+    #[test]
+    fn test_summary_keep_valid_rows() {
+        let input: &[&[&str]] = &[&["a"], &["1"], &["2"], &["3"]];
+        let options = CheckOptions {
+            summary_examples: Some(1),
+            ..Default::default()
+        };
+
+        let csv_details = check_rows(
+            rows(input),
+            Some(","),
+            &options,
+            true,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(csv_details.valid_rows, RowSet::from([0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn test_summary_conflicts() {
+        let options = CheckOptions {
+            summary_examples: Some(1),
+            duplicate_key_columns: Some(vec![0]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.validate(false).unwrap_err().to_string(),
+            "duplicate key columns can't be used together with summary mode"
+        );
+    }
+
+    #[test]
+    fn test_max_rows() {
+        let input: &[&[&str]] = &[&["a", "b"], &["1"], &["2", "x"], &["3"]];
+        let options = CheckOptions {
+            max_rows: Some(2),
+            ..Default::default()
+        };
+
+        let csv_details = check_rows(
+            rows(input),
+            Some(","),
+            &options,
+            false,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(csv_details.too_few_columns, RowSet::from([1]));
+        assert_eq!(
+            csv_details.partial,
+            Some(PartialCheck {
+                reason: PartialReason::RowLimit,
+                rows_checked: 2,
+                rows_read: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_max_rows_whole_file() {
+        let input: &[&[&str]] = &[&["a", "b"], &["1", "x"]];
+        let options = CheckOptions {
+            max_rows: Some(2),
+            ..Default::default()
+        };
+
+        let csv_details = check_rows(
+            rows(input),
+            Some(","),
+            &options,
+            false,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(csv_details.partial, None);
+    }
+
+    #[test]
+    fn test_max_issues() {
+        let input: &[&[&str]] = &[&["a", "b"], &["1"], &["2", "x"], &["3"], &["4"]];
+        let options = CheckOptions {
+            max_issues: Some(2),
+            ..Default::default()
+        };
+
+        let csv_details = check_rows(
+            rows(input),
+            Some(","),
+            &options,
+            false,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(csv_details.too_few_columns, RowSet::from([1, 3]));
+        assert_eq!(
+            csv_details.partial,
+            Some(PartialCheck {
+                reason: PartialReason::IssueLimit,
+                rows_checked: 4,
+                rows_read: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_sample_stride() {
+        let input: &[&[&str]] = &[&["a", "b"], &["1"], &["2"], &["3"], &["4"], &[]];
+        let options = CheckOptions {
+            sample: Some(Sample::Stride(2)),
+            ..Default::default()
+        };
+
+        let csv_details = check_rows(
+            rows(input),
+            Some(","),
+            &options,
+            false,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(csv_details.too_few_columns, RowSet::from([2, 4]));
+        assert_eq!(csv_details.column_count_per_line.len(), 6);
+        assert_eq!(
+            csv_details.partial,
+            Some(PartialCheck {
+                reason: PartialReason::Sample,
+                rows_checked: 3,
+                rows_read: 6
+            })
+        );
+    }
+
+    #[test]
+    fn test_invalid_sample() {
+        let options = CheckOptions {
+            sample: Some(Sample::Random {
+                fraction: 1.5,
+                seed: None,
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.validate(false).unwrap_err().to_string(),
+            "invalid value 1.5 for sample fraction"
+        );
+    }
+
+    #[test]
+    fn test_partial_check_output_conflicts() {
+        let options = CheckOptions {
+            sample: Some(Sample::Stride(2)),
+            ..Default::default()
+        };
+        assert_eq!(
+            options.validate(true).unwrap_err().to_string(),
+            "sampling can't be used together with valid rows output"
+        );
+
+        let options = CheckOptions {
+            max_rows: Some(10),
+            valid_file: ValidFileOptions {
+                rejected_rows_path: Some("rejected.csv".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            options.validate(false).unwrap_err().to_string(),
+            "max rows can't be used together with rejected rows output"
+        );
+
+        let options = CheckOptions {
+            max_issues: Some(10),
+            ..Default::default()
+        };
+        assert!(options.validate(false).is_ok());
+    }
+
+    #[test]
+    fn test_cancelled_while_writing_outputs() {
+        let file = crate::test_utils::temp_file("a,b\n1,2\n3\n");
+        let out_dir = tempfile::tempdir().unwrap();
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        // the progress is only reported once all the rows were checked, so the check is cancelled from then on
+        let hooks = CheckHooks {
+            on_progress: Some(Box::new(move |_: &Progress| cancel.cancel())),
+            progress_interval: Duration::from_secs(3600),
+            cancellation: Some(token),
+        };
+
+        let result = check_file_with_hooks(
+            file.path(),
+            ",",
+            "utf-8",
+            Some(out_dir.path().join("valid.csv")),
+            &CheckOptions::default(),
+            hooks,
+        );
+
+        assert!(matches!(result, Err(CSVError::Cancelled)));
+    }
+
+    #[test]
+    fn test_char_and_string_delimiters() {
+        let file = crate::test_utils::temp_file("a;b\n1;2\n");
+        let csv_details = check_file(file.path(), ';', "utf-8", None::<&Path>).unwrap();
+        assert_eq!(csv_details.column_count, 2);
+
+        let file = crate::test_utils::temp_file("a~|~b\n1~|~2\n");
+        let csv_details = check_file(file.path(), "~|~", "utf-8", None::<&Path>).unwrap();
+        assert_eq!(csv_details.column_count, 2);
+        assert_eq!(csv_details.valid_rows, RowSet::from([0, 1]));
+    }
+}
+
+#[cfg(test)]
+mod skipped_lines_tests {
+    use super::*;
+
+    fn check(content: &str, options: &CheckOptions) -> CSVDetails {
+        check_content(content, options).unwrap()
+    }
+
+    #[test]
+    fn test_skip_lines_and_comments() {
+        let options = CheckOptions {
+            parse: ParseOptions {
+                skip_lines: 2,
+                comment_prefix: Some("#".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let csv_details = check(
+            "Report\r\n# not a comment yet\r\na,b\r\n# note\r\n1,2\r\n",
+            &options,
+        );
+
+        assert_eq!(
+            csv_details.skipped_lines,
+            SkippedLines {
+                preamble: vec!["Report".into(), "# not a comment yet".into()],
+                comments: RowSet::from([3]),
+            }
+        );
+        assert_eq!(csv_details.valid_rows, RowSet::from([0, 1]));
+        assert!(!csv_details.header_messed_up());
+    }
+
+    #[test]
+    fn test_detect_preamble() {
+        let options = CheckOptions {
+            detect_preamble: true,
+            ..Default::default()
+        };
+
+        let csv_details = check("Report\n\na,b\n1,2\n3,4\n", &options);
+
+        assert_eq!(csv_details.skipped_lines.preamble, vec!["Report", ""]);
+        assert_eq!(csv_details.column_count, 2);
+        assert_eq!(csv_details.valid_rows, RowSet::from([0, 1, 2]));
+    }
+
+    #[test]
+    fn test_skipping_conflicts() {
+        let options = CheckOptions {
+            strict: true,
+            parse: ParseOptions {
+                comment_prefix: Some("#".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            options.validate(false).unwrap_err().to_string(),
+            "strict mode can't be used together with skipping lines"
+        );
+    }
+}
+
+#[cfg(test)]
+mod footer_tests {
+    use regex::Regex;
+
+    use super::*;
+
+    fn check(content: &str, options: &CheckOptions) -> CSVDetails {
+        check_content(content, options).unwrap()
+    }
+
+    fn footer_options(last_rows: usize) -> CheckOptions {
+        CheckOptions {
+            footer: Some(FooterOptions {
+                last_rows,
+                patterns: vec![Regex::new("^Total$").unwrap()],
+                short_rows: false,
+                record_count_pattern: Some(Regex::new(r"^Record count: (\d+)$").unwrap()),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_footer() {
+        let content = "a,b,c\n1,2,3\n4,5\n6,7,8\nTotal,,21\nRecord count: 3\n";
+        let csv_details = check(content, &footer_options(5));
+
+        assert_eq!(csv_details.footer.rows, RowSet::from([4, 5]));
+        assert_eq!(csv_details.footer.declared_record_count, Some(3));
+        assert_eq!(csv_details.footer.valid_record_count, 2);
+        assert!(csv_details.footer.record_count_mismatch());
+        assert_eq!(csv_details.too_few_columns, RowSet::from([2]));
+        assert_eq!(csv_details.valid_rows, RowSet::from([0, 1, 3]));
+        assert_eq!(csv_details.row_count, 4);
+    }
+
+    #[test]
+    fn test_no_footer() {
+        let csv_details = check("a,b\n1,2\n3\n", &CheckOptions::default());
+
+        assert!(csv_details.footer.rows.is_empty());
+        assert_eq!(csv_details.too_few_columns, RowSet::from([2]));
+
+        let csv_details = check("a,b\n1,2\n3,4\n", &footer_options(5));
+
+        assert!(csv_details.footer.rows.is_empty());
+        assert_eq!(csv_details.footer.valid_record_count, 2);
+        assert!(!csv_details.footer.record_count_mismatch());
+    }
+
+    #[test]
+    fn test_truncated_last_record() {
+        let csv_details = check("a,b,c\n1,2,3\n4,5\n", &footer_options(5));
+
+        assert!(csv_details.footer.rows.is_empty());
+        assert_eq!(csv_details.too_few_columns, RowSet::from([2]));
+    }
+
+    #[test]
+    fn test_short_rows() {
+        let options = CheckOptions {
+            footer: Some(FooterOptions {
+                last_rows: 2,
+                patterns: Vec::new(),
+                short_rows: true,
+                record_count_pattern: None,
+            }),
+            ..Default::default()
+        };
+        let csv_details = check("a,b,c\n1,2,3\n4,5\n6,7,8\nTotal,21\n", &options);
+
+        assert_eq!(csv_details.footer.rows, RowSet::from([4]));
+        assert_eq!(csv_details.too_few_columns, RowSet::from([2]));
+        assert_eq!(csv_details.valid_rows, RowSet::from([0, 1, 3]));
+    }
+
+    #[test]
+    fn test_record_count_in_summary_mode() {
+        let options = CheckOptions {
+            summary_examples: Some(0),
+            ..footer_options(5)
+        };
+        let csv_details = check("a,b\n1,2\n3,4\nRecord count: 2\n", &options);
+
+        assert_eq!(csv_details.footer.declared_record_count, Some(2));
+        assert_eq!(csv_details.footer.valid_record_count, 2);
+        assert!(!csv_details.footer.record_count_mismatch());
+    }
+
+    #[test]
+    fn test_invalid_footer_options() {
+        let mut options = footer_options(5);
+        options.sample = Some(Sample::Stride(2));
+        assert_eq!(
+            check_content("a\n", &options).unwrap_err().to_string(),
+            "sampling can't be used together with footer record count"
+        );
+
+        let options = CheckOptions {
+            footer: Some(FooterOptions {
+                last_rows: 5,
+                patterns: Vec::new(),
+                short_rows: false,
+                record_count_pattern: None,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            check_content("a\n", &options).unwrap_err().to_string(),
+            "footer patterns can't be empty"
+        );
+    }
+
+    #[test]
+    fn test_footer_not_detected_when_stopped() {
+        let options = CheckOptions {
+            max_rows: Some(3),
+            ..footer_options(5)
+        };
+        let csv_details = check("a,b\n1,2\n3\n4\n", &options);
+
+        assert!(csv_details.footer.rows.is_empty());
+        assert_eq!(csv_details.too_few_columns, RowSet::from([2]));
+    }
+}
+
+#[cfg(test)]
+mod fixed_width_tests {
+    use crate::fixed_width::ColumnSpec;
+
+    use super::*;
+
+    #[test]
+    fn test_fixed_width() {
+        let options = CheckOptions {
+            parse: ParseOptions::fixed_width(ColumnSpec::from_widths(&[5, 3, 7]).unwrap()),
+            ..Default::default()
+        };
+
+        let csv_details = check_content(
+            "NAME QTY AMOUNT\nAlice 42  12.50\nBob  7\n\nCarol 1    3.00 extra\n\"Dan\" 2   1,000\n",
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(csv_details.column_count, 3);
+        assert_eq!(csv_details.too_few_columns, RowSet::from([2]));
+        assert_eq!(csv_details.too_many_columns, RowSet::from([4]));
+        assert_eq!(csv_details.blank_rows, RowSet::from([3]));
+        assert_eq!(csv_details.valid_rows, RowSet::from([0, 1, 5]));
+        assert!(csv_details.whitespace_padding.is_empty());
+    }
+
+    #[test]
+    fn test_fixed_width_skips_delimiter_and_quote_checks() {
+        let options = CheckOptions {
+            parse: ParseOptions::fixed_width(ColumnSpec::from_widths(&[6, 8]).unwrap()),
+            ..Default::default()
+        };
+
+        let csv_details = check_content(
+            "NAME  AMOUNT
+\"Dan  1,000.00\nA\"\"B  \"2\"\n",
+            &options,
+        )
+        .unwrap();
+
+        assert!(csv_details.quoted_delimiter.is_empty());
+        assert!(csv_details.quoted_quote.is_empty());
+        assert!(csv_details.incorrect_cell_quote.is_empty());
+        assert_eq!(csv_details.valid_rows, RowSet::from([0, 1, 2]));
+    }
+
+    #[test]
+    fn test_fixed_width_conflicts() {
+        let columns = ColumnSpec::from_widths(&[1]).unwrap();
+        let options = CheckOptions {
+            parse: ParseOptions {
+                fixed_width: Some(columns.clone()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check_content("a\n", &options).unwrap_err().to_string(),
+            "fixed width can't be used together with quoting"
+        );
+
+        let options = CheckOptions {
+            parse: ParseOptions::fixed_width(columns),
+            strict: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check_content("a\n", &options).unwrap_err().to_string(),
+            "strict mode can't be used together with fixed width"
+        );
+    }
+}
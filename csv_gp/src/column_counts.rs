@@ -0,0 +1,91 @@
+/// Run-length encoded list of the number of columns per line, as most lines have the same number of columns
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnCounts {
+    /// (line number the run ends at, exclusive, number of columns in the run)
+    runs: Vec<(usize, usize)>,
+}
+
+impl ColumnCounts {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the number of columns of the next line
+    pub fn push(&mut self, column_count: usize) {
+        match self.runs.last_mut() {
+            Some((end, count)) if *count == column_count => *end += 1,
+            _ => {
+                let end = self.len() + 1;
+                self.runs.push((end, column_count))
+            }
+        }
+    }
+
+    /// The number of columns of line `line`
+    pub fn get(&self, line: usize) -> Option<usize> {
+        let run = self.runs.partition_point(|(end, _)| *end <= line);
+        self.runs.get(run).map(|(_, count)| *count)
+    }
+
+    /// Number of lines
+    pub fn len(&self) -> usize {
+        self.runs.last().map_or(0, |(end, _)| *end)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Iterates over the number of columns of each line
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut start = 0;
+
+        self.runs.iter().flat_map(move |&(end, count)| {
+            let run = std::iter::repeat_n(count, end - start);
+            start = end;
+            run
+        })
+    }
+}
+
+impl FromIterator<usize> for ColumnCounts {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut counts = Self::new();
+        iter.into_iter().for_each(|c| counts.push(c));
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_length() {
+        let counts = ColumnCounts::from_iter([2, 2, 2, 0, 2, 1, 1]);
+
+        assert_eq!(counts.runs, vec![(3, 2), (4, 0), (5, 2), (7, 1)]);
+        assert_eq!(counts.len(), 7);
+        assert_eq!(counts.iter().collect::<Vec<_>>(), vec![2, 2, 2, 0, 2, 1, 1]);
+    }
+
+    #[test]
+    fn test_get() {
+        let counts = ColumnCounts::from_iter([2, 2, 0, 3]);
+
+        assert_eq!(counts.get(0), Some(2));
+        assert_eq!(counts.get(1), Some(2));
+        assert_eq!(counts.get(2), Some(0));
+        assert_eq!(counts.get(3), Some(3));
+        assert_eq!(counts.get(4), None);
+    }
+
+    #[test]
+    fn test_empty() {
+        let counts = ColumnCounts::new();
+
+        assert!(counts.is_empty());
+        assert_eq!(counts.len(), 0);
+        assert_eq!(counts.get(0), None);
+    }
+}
@@ -1,4 +1,16 @@
-use std::collections::HashSet;
+use std::collections::BTreeMap;
+
+use crate::{
+    cell_locations::CellLocations,
+    characters::{CharacterFindings, InvisibleCharacter},
+    column_counts::ColumnCounts,
+    footer::Footer,
+    mojibake::EncodingPair,
+    partial::PartialCheck,
+    preamble::SkippedLines,
+    row_set::RowSet,
+    strict::StrictCheck,
+};
 
 #[derive(Debug, Clone, Default)]
 pub struct CSVDetails {
@@ -8,31 +20,56 @@ pub struct CSVDetails {
     pub column_count: usize,
     /// Number of REPLACEMENT CHARACTERs (U+FFFD) in the file
     pub invalid_character_count: usize,
+    /// Invisible or control characters found in the file, per category
+    pub invisible_characters: BTreeMap<InvisibleCharacter, CharacterFindings>,
+    /// List of line numbers containing text that looks like UTF-8 decoded with a single byte encoding, per encoding pair
+    pub mojibake: BTreeMap<EncodingPair, RowSet>,
+    /// List of (line number, column index) of cells that spreadsheet software would interpret as a formula
+    pub formula_injection: CellLocations,
     /// List of line numbers that contain fewer columns than the header
-    pub too_few_columns: Vec<usize>,
+    pub too_few_columns: RowSet,
     /// List of line numbers that contain more columns than the header
-    pub too_many_columns: Vec<usize>,
+    pub too_many_columns: RowSet,
     /// Number of columns per line, the index corresponding to the line number
-    pub column_count_per_line: Vec<usize>,
+    pub column_count_per_line: ColumnCounts,
     /// List of line numbers that contain a correctly quoted delimiter
-    pub quoted_delimiter: Vec<usize>,
+    pub quoted_delimiter: RowSet,
     /// List of line numbers that contain a correctly quoted newline
-    pub quoted_newline: Vec<usize>,
+    pub quoted_newline: RowSet,
     /// List of line numbers that contain quoted-quotes ("")
-    pub quoted_quote: Vec<usize>,
+    pub quoted_quote: RowSet,
     /// List of line numbers that contain correctly quoted-quotes (only contained within quoted cells)
-    pub quoted_quote_correctly: Vec<usize>,
+    pub quoted_quote_correctly: RowSet,
     /// List of line numbers that have incorrectly quoted cells
     /// Incorrect meaning:
     ///     - Missing an opening or closing quote
     ///     - Containing unquoted quotes
-    pub incorrect_cell_quote: Vec<usize>,
+    pub incorrect_cell_quote: RowSet,
+    /// List of line numbers that have unquoted cells with leading or trailing whitespace
+    pub whitespace_padding: RowSet,
+    /// List of line numbers that have quoted cells with whitespace before the opening or after the closing quote
+    pub whitespace_outside_quotes: RowSet,
     /// List of line numbers where all cells in the row are empty (either zero characters or just `""`)
-    pub all_empty_rows: Vec<usize>,
+    pub all_empty_rows: RowSet,
     /// List of line numbers that are completely blank
-    pub blank_rows: Vec<usize>,
+    pub blank_rows: RowSet,
     /// Set of all row numbers that are valid in the file
-    pub valid_rows: HashSet<usize>,
+    pub valid_rows: RowSet,
+    /// Groups of line numbers where the rows are exact duplicates of each other, not checked in summary mode
+    pub duplicate_rows: Vec<Vec<usize>>,
+    /// Groups of line numbers where the rows have the same values in the key columns, if key columns were given
+    pub duplicate_key_rows: Option<Vec<Vec<usize>>>,
+    /// Maximum number of example line numbers kept per issue, if only a summary of the file was kept.
+    /// The lists of line numbers then only contain the first examples, but their lengths are the total counts.
+    pub example_limit: Option<usize>,
+    /// Details of which part of the file was checked, if the whole file wasn't checked
+    pub partial: Option<PartialCheck>,
+    /// Deviations from RFC 4180, if the file was also parsed strictly
+    pub strict: Option<StrictCheck>,
+    /// Lines before the header and comment lines, which aren't rows so don't have line numbers
+    pub skipped_lines: SkippedLines,
+    /// Summary rows at the end of the file, if footer detection was enabled
+    pub footer: Footer,
 }
 
 impl CSVDetails {
@@ -40,15 +77,85 @@ impl CSVDetails {
         Default::default()
     }
 
+    /// Details only keeping totals and the first `limit` line numbers of each issue, so the memory used
+    /// doesn't grow with the size of the file
+    pub fn with_example_limit(limit: usize) -> Self {
+        Self {
+            formula_injection: CellLocations::with_limit(limit),
+            too_few_columns: RowSet::with_limit(limit),
+            too_many_columns: RowSet::with_limit(limit),
+            quoted_delimiter: RowSet::with_limit(limit),
+            quoted_newline: RowSet::with_limit(limit),
+            quoted_quote: RowSet::with_limit(limit),
+            quoted_quote_correctly: RowSet::with_limit(limit),
+            incorrect_cell_quote: RowSet::with_limit(limit),
+            whitespace_padding: RowSet::with_limit(limit),
+            whitespace_outside_quotes: RowSet::with_limit(limit),
+            all_empty_rows: RowSet::with_limit(limit),
+            blank_rows: RowSet::with_limit(limit),
+            valid_rows: RowSet::with_limit(limit),
+            skipped_lines: SkippedLines {
+                comments: RowSet::with_limit(limit),
+                ..Default::default()
+            },
+            footer: Footer {
+                rows: RowSet::with_limit(limit),
+                ..Default::default()
+            },
+            example_limit: Some(limit),
+            ..Default::default()
+        }
+    }
+
     /// The header is considered messed up when none of the rows have the same number of columns as the header
     pub fn header_messed_up(&self) -> bool {
         let bad_row_count = self.too_few_columns.len() + self.too_many_columns.len();
         self.row_count - 1 == bad_row_count
     }
 
+    /// Number of occurrences of each kind of issue, named after the fields of the details
+    pub fn issue_counts(&self) -> Vec<(&'static str, usize)> {
+        let group_rows = |groups: &[Vec<usize>]| groups.iter().map(Vec::len).sum::<usize>();
+
+        vec![
+            ("invalid_character_count", self.invalid_character_count),
+            (
+                "invisible_characters",
+                self.invisible_characters.values().map(|f| f.count).sum(),
+            ),
+            ("mojibake", self.mojibake.values().map(RowSet::len).sum()),
+            ("formula_injection", self.formula_injection.len()),
+            ("too_few_columns", self.too_few_columns.len()),
+            ("too_many_columns", self.too_many_columns.len()),
+            ("quoted_delimiter", self.quoted_delimiter.len()),
+            ("quoted_newline", self.quoted_newline.len()),
+            ("quoted_quote", self.quoted_quote.len()),
+            ("incorrect_cell_quote", self.incorrect_cell_quote.len()),
+            ("whitespace_padding", self.whitespace_padding.len()),
+            (
+                "whitespace_outside_quotes",
+                self.whitespace_outside_quotes.len(),
+            ),
+            ("all_empty_rows", self.all_empty_rows.len()),
+            ("blank_rows", self.blank_rows.len()),
+            ("duplicate_rows", group_rows(&self.duplicate_rows)),
+            (
+                "duplicate_key_rows",
+                self.duplicate_key_rows.as_deref().map_or(0, group_rows),
+            ),
+            ("footer_rows", self.footer.rows.len()),
+        ]
+    }
+
     pub fn report(&self) -> String {
         let mut results = String::new();
 
+        if let Some(partial) = &self.partial {
+            results += &format!("{partial}\n");
+        }
+
+        results += &self.skipped_lines.to_string();
+
         if self.header_messed_up() {
             results += "The header is totally messed up, no rows have the same number of columns as the header.\n";
         }
@@ -66,10 +173,24 @@ impl CSVDetails {
             return results;
         }
 
-        results += &format!(
-            "There are {} ({} of which are valid) rows in the file (including header), with {} columns (according to the header).\n",
-            self.row_count, self.valid_rows.len(), self.column_count
-        );
+        match &self.partial {
+            None => {
+                results += &format!(
+                    "There are {} ({} of which are valid) rows in the file (including header), with {} columns (according to the header).\n",
+                    self.row_count, self.valid_rows.len(), self.column_count
+                );
+            }
+            Some(partial) => {
+                results += &format!(
+                    "There are {} ({} of which are valid) rows in the checked part of the file (including header), with {} columns (according to the header).\n",
+                    self.row_count, self.valid_rows.len(), self.column_count
+                );
+                if let Some(valid_rows) = partial.extrapolate(self.valid_rows.len()) {
+                    results +=
+                        &format!("There are about {valid_rows} valid rows in the whole file.\n");
+                }
+            }
+        }
 
         if !self.too_few_columns.is_empty() || !self.too_many_columns.is_empty() {
             results += &format!(
@@ -92,6 +213,47 @@ impl CSVDetails {
             );
         }
 
+        for (category, findings) in &self.invisible_characters {
+            results += &format!(
+                "There are {} {} characters in {} cells.\n",
+                findings.count,
+                category,
+                findings.locations.len()
+            );
+        }
+
+        for (encodings, rows) in &self.mojibake {
+            results += &format!(
+                "There are {} lines with text that looks like {}, so the text is garbled.\n",
+                rows.len(),
+                encodings
+            );
+        }
+
+        if !self.formula_injection.is_empty() {
+            results += &format!(
+                "There are {} cells that would be interpreted as a formula when opened in a spreadsheet.\n",
+                self.formula_injection.len()
+            );
+        }
+
+        if !self.duplicate_rows.is_empty() {
+            results += &format!(
+                "There are {} groups of duplicate rows, with {} rows in total.\n",
+                self.duplicate_rows.len(),
+                self.duplicate_rows.iter().map(Vec::len).sum::<usize>()
+            );
+        }
+
+        if let Some(duplicate_key_rows) = self.duplicate_key_rows.as_ref().filter(|g| !g.is_empty())
+        {
+            results += &format!(
+                "There are {} groups of rows with duplicate keys, with {} rows in total.\n",
+                duplicate_key_rows.len(),
+                duplicate_key_rows.iter().map(Vec::len).sum::<usize>()
+            );
+        }
+
         if !self.quoted_delimiter.is_empty() {
             results += &format!(
                 "There are {} lines with correctly quoted delimiter.\n",
@@ -128,6 +290,26 @@ impl CSVDetails {
             results += "There are no rows with incorrect cell quotes.\n";
         }
 
+        if !self.whitespace_padding.is_empty() {
+            results += &format!(
+                "There are {} lines with cells padded with whitespace.\n",
+                self.whitespace_padding.len()
+            );
+        }
+
+        if !self.whitespace_outside_quotes.is_empty() {
+            results += &format!(
+                "There are {} lines with whitespace outside of quoted cells.\n",
+                self.whitespace_outside_quotes.len()
+            );
+        }
+
+        results += &self.footer.to_string();
+
+        if let Some(strict) = &self.strict {
+            results += &strict.to_string();
+        }
+
         results
     }
 }
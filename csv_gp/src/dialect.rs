@@ -0,0 +1,205 @@
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::{
+    error::{CSVError, InvalidOption},
+    parser::ParseOptions,
+};
+
+/// Names of the built-in dialects
+pub const PRESETS: &[&str] = &[
+    "rfc4180",
+    "excel",
+    "excel-tab",
+    "unix",
+    "mysql",
+    "postgres-csv",
+];
+
+/// The settings needed to read files written in a particular way, e.g. by one supplier
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dialect {
+    /// Delimiter between cells, any non-empty string
+    pub delimiter: String,
+    pub encoding: String,
+    /// Whether cells can be quoted with `"`
+    pub quoting: bool,
+    /// Character making the next character literal, if any. The escape sequences of MySQL and PostgreSQL such as `\t`
+    /// are decoded, and a cell of only `\N` (NULL) is read as empty.
+    pub escape: Option<char>,
+    /// Treat whitespace between a delimiter and a quote as insignificant
+    pub trim_whitespace_outside_quotes: bool,
+}
+
+impl Default for Dialect {
+    /// RFC 4180 CSV in UTF-8
+    fn default() -> Self {
+        Self {
+            delimiter: ",".into(),
+            encoding: "utf-8".into(),
+            quoting: true,
+            escape: None,
+            trim_whitespace_outside_quotes: false,
+        }
+    }
+}
+
+/// A dialect file, every setting not given is taken from the `base` preset (`rfc4180` by default)
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DialectFile {
+    base: Option<String>,
+    delimiter: Option<String>,
+    encoding: Option<String>,
+    quoting: Option<bool>,
+    escape: Option<char>,
+    trim_whitespace_outside_quotes: Option<bool>,
+}
+
+impl Dialect {
+    /// The built-in dialect with this name, one of [`PRESETS`]
+    pub fn preset(name: &str) -> Result<Self, InvalidOption> {
+        let rfc4180 = Self::default();
+
+        match name {
+            // `unix` only differs from rfc4180 in quoting every cell and ending lines with LF, and PostgreSQL's CSV
+            // format in writing NULL as an unquoted empty cell, which are all read the same way
+            "rfc4180" | "unix" | "postgres-csv" => Ok(rfc4180),
+            // Excel writes CSV in the Windows code page unless saving as "CSV UTF-8"
+            "excel" => Ok(Self {
+                encoding: "windows-1252".into(),
+                ..rfc4180
+            }),
+            "excel-tab" => Ok(Self {
+                delimiter: "\t".into(),
+                encoding: "windows-1252".into(),
+                ..rfc4180
+            }),
+            // the defaults of `SELECT ... INTO OUTFILE` and `LOAD DATA`, with `\N` for NULL
+            "mysql" => Ok(Self {
+                delimiter: "\t".into(),
+                quoting: false,
+                escape: Some('\\'),
+                ..rfc4180
+            }),
+            _ => Err(InvalidOption::Value {
+                option: "dialect",
+                value: name.into(),
+            }),
+        }
+    }
+
+    /// Reads a dialect from a TOML file, e.g.
+    ///
+    /// ```toml
+    /// base = "excel"
+    /// delimiter = ";"
+    /// ```
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CSVError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Reads a dialect from the contents of a TOML dialect file
+    pub fn from_toml(toml: &str) -> Result<Self, CSVError> {
+        let file: DialectFile = toml::from_str(toml)?;
+        let base = Self::preset(file.base.as_deref().unwrap_or("rfc4180"))?;
+
+        let dialect = Self {
+            delimiter: file.delimiter.unwrap_or(base.delimiter),
+            encoding: file.encoding.unwrap_or(base.encoding),
+            quoting: file.quoting.unwrap_or(base.quoting),
+            escape: file.escape.or(base.escape),
+            trim_whitespace_outside_quotes: file
+                .trim_whitespace_outside_quotes
+                .unwrap_or(base.trim_whitespace_outside_quotes),
+        };
+        dialect.validate()?;
+
+        Ok(dialect)
+    }
+
+    /// The preset with this name if there is one, otherwise the dialect file at this path
+    pub fn load(name_or_path: &str) -> Result<Self, CSVError> {
+        if PRESETS.contains(&name_or_path) || !Path::new(name_or_path).exists() {
+            Ok(Self::preset(name_or_path)?)
+        } else {
+            Self::from_file(name_or_path)
+        }
+    }
+
+    /// Checks that the settings don't contradict each other
+    pub fn validate(&self) -> Result<(), InvalidOption> {
+        if self.delimiter.is_empty() {
+            return Err(InvalidOption::Empty("delimiter"));
+        }
+
+        match self.escape {
+            // quotes are already escaped by doubling them
+            Some(escape) if escape == '"' || self.delimiter.contains(escape) => {
+                Err(InvalidOption::Value {
+                    option: "escape",
+                    value: escape.into(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Options to parse the rows of a file in this dialect
+    pub fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            trim_whitespace_outside_quotes: self.trim_whitespace_outside_quotes,
+            quoting: self.quoting,
+            escape: self.escape,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets() {
+        for name in PRESETS {
+            Dialect::preset(name).unwrap().validate().unwrap();
+        }
+
+        assert_eq!(Dialect::preset("excel-tab").unwrap().delimiter, "\t");
+        assert_eq!(Dialect::preset("unix").unwrap(), Dialect::default());
+        assert_eq!(Dialect::preset("postgres-csv").unwrap(), Dialect::default());
+        assert_eq!(
+            Dialect::preset("nope").unwrap_err().to_string(),
+            "invalid value nope for dialect"
+        );
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let dialect = Dialect::from_toml("base = \"mysql\"\ndelimiter = \"|\"\n").unwrap();
+
+        assert_eq!(
+            dialect,
+            Dialect {
+                delimiter: "|".into(),
+                ..Dialect::preset("mysql").unwrap()
+            }
+        );
+    }
+
+    #[test]
+    fn test_from_toml_invalid() {
+        assert!(Dialect::from_toml("delimeter = \";\"")
+            .unwrap_err()
+            .to_string()
+            .starts_with("invalid dialect file"));
+        assert_eq!(
+            Dialect::from_toml("escape = \",\"")
+                .unwrap_err()
+                .to_string(),
+            "invalid value , for escape"
+        );
+    }
+}
@@ -0,0 +1,487 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet, VecDeque},
+    fmt::Display,
+    hash::{Hash, Hasher},
+    path::Path,
+};
+
+use crate::{
+    cell::Cell,
+    checker::{check_file_inspecting, CheckOptions},
+    csv_details::CSVDetails,
+    error::{CSVError, InvalidOption},
+    parser::{self, Delimiter, ParseOptions},
+    progress::CheckHooks,
+    row_set::RowSet,
+};
+
+/// Options for comparing two files
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Options used when parsing the rows of both files
+    pub parse: ParseOptions,
+    /// Indices of the columns identifying a row, used to match the rows of the two files. Rows are matched by their
+    /// line number if not given.
+    pub key_columns: Option<Vec<usize>>,
+}
+
+/// A cell whose value is different in the new file, a missing cell being `None`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellChange {
+    pub column: usize,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// A row of the old file matched with a row of the new file with different values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowChange {
+    /// Line number of the row in the old file
+    pub old_row: usize,
+    /// Line number of the row in the new file
+    pub new_row: usize,
+    pub cells: Vec<CellChange>,
+}
+
+impl Display for RowChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cell = |value: &Option<String>| match value {
+            Some(value) => format!("{value:?}"),
+            None => "nothing".into(),
+        };
+
+        if self.old_row == self.new_row {
+            write!(f, "Row {} changed:", self.old_row)?;
+        } else {
+            write!(
+                f,
+                "Row {} (row {} in the new file) changed:",
+                self.old_row, self.new_row
+            )?;
+        }
+        for (i, change) in self.cells.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(
+                f,
+                "{separator}column {} from {} to {}",
+                change.column,
+                cell(&change.old),
+                cell(&change.new)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A kind of issue found a different number of times in the new file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueChange {
+    /// Name of the issue, as in [`CSVDetails::issue_counts`]
+    pub issue: &'static str,
+    pub old: usize,
+    pub new: usize,
+}
+
+/// Differences between two files parsed with the same dialect
+#[derive(Debug, Clone, Default)]
+pub struct FileDiff {
+    /// Line numbers in the new file of the rows that aren't in the old file
+    pub added_rows: RowSet,
+    /// Line numbers in the old file of the rows that aren't in the new file
+    pub removed_rows: RowSet,
+    /// Rows in both files with different values, in the order of the new file
+    pub changed_rows: Vec<RowChange>,
+    /// Number of rows in both files with the same values
+    pub unchanged_row_count: usize,
+    /// Issues found a different number of times in the two files
+    pub issue_changes: Vec<IssueChange>,
+    pub old_details: CSVDetails,
+    pub new_details: CSVDetails,
+}
+
+impl FileDiff {
+    /// Whether the two files have the same rows and issues
+    pub fn is_empty(&self) -> bool {
+        self.added_rows.is_empty()
+            && self.removed_rows.is_empty()
+            && self.changed_rows.is_empty()
+            && self.issue_changes.is_empty()
+    }
+
+    /// Summary of the differences, listing the first `examples` changed rows
+    pub fn report(&self, examples: usize) -> String {
+        let mut results = format!(
+            "{} rows were added, {} rows were removed and {} rows were changed, {} rows are the same.\n",
+            self.added_rows.len(),
+            self.removed_rows.len(),
+            self.changed_rows.len(),
+            self.unchanged_row_count
+        );
+
+        for row in self.changed_rows.iter().take(examples) {
+            results += &format!("{row}\n");
+        }
+        if self.changed_rows.len() > examples {
+            results += &format!(
+                "And {} more changed rows.\n",
+                self.changed_rows.len() - examples
+            );
+        }
+
+        if self.old_details.column_count != self.new_details.column_count {
+            results += &format!(
+                "The header went from {} to {} columns.\n",
+                self.old_details.column_count, self.new_details.column_count
+            );
+        }
+
+        for change in &self.issue_changes {
+            results += &format!(
+                "The {} count went from {} to {}.\n",
+                change.issue.replace('_', " "),
+                change.old,
+                change.new
+            );
+        }
+
+        results
+    }
+
+    fn compare(&mut self, old_row: usize, old: &[String], new_row: usize, new: &[String]) {
+        let cells: Vec<_> = (0..old.len().max(new.len()))
+            .filter_map(|column| {
+                let (old, new) = (old.get(column), new.get(column));
+                (old != new).then(|| CellChange {
+                    column,
+                    old: old.cloned(),
+                    new: new.cloned(),
+                })
+            })
+            .collect();
+
+        if cells.is_empty() {
+            self.unchanged_row_count += 1;
+        } else {
+            self.changed_rows.push(RowChange {
+                old_row,
+                new_row,
+                cells,
+            });
+        }
+    }
+}
+
+/// Compares the file at `new_path` to the one at `old_path`, both interpreted with `delimiter` and `encoding`.
+/// The rows are matched by their key columns or their line numbers, and compared on the values of their cells.
+///
+/// Each file is read once while it is checked. Only a hash of the values of each row of the old file, and its key, are
+/// kept in memory, rows with the same hash being taken to be the same. The old file is then read again up to the last
+/// changed row, for the old values of the changed rows.
+pub fn diff_files(
+    old_path: impl AsRef<Path>,
+    new_path: impl AsRef<Path>,
+    delimiter: impl Into<Delimiter>,
+    encoding: &str,
+    options: &DiffOptions,
+) -> Result<FileDiff, CSVError> {
+    let delimiter = delimiter.into();
+    let delimiter = delimiter.as_str();
+    let key_columns = options.key_columns.as_deref();
+    if key_columns.is_some_and(<[usize]>::is_empty) {
+        return Err(InvalidOption::Empty("key columns").into());
+    }
+
+    let check_options = CheckOptions {
+        parse: options.parse.clone(),
+        duplicate_key_columns: options.key_columns.clone(),
+        ..Default::default()
+    };
+    let check = |path: &Path, inspect: &mut dyn FnMut(&[Cell])| {
+        check_file_inspecting(
+            path,
+            delimiter,
+            encoding,
+            None::<&Path>,
+            &check_options,
+            CheckHooks::default(),
+            inspect,
+        )
+    };
+
+    let mut old_rows = OldRows::default();
+    let old_details = check(old_path.as_ref(), &mut |cells| {
+        old_rows.add(values(cells, &options.parse), key_columns)
+    })?;
+
+    let mut diff = FileDiff::default();
+    let mut matched = vec![false; old_rows.hashes.len()];
+    let mut changed = Vec::new();
+    let mut new_row = 0;
+    let new_details = check(new_path.as_ref(), &mut |cells| {
+        let i = new_row;
+        new_row += 1;
+
+        let values = values(cells, &options.parse);
+        let old_row = match key_columns {
+            // the headers are always compared
+            _ if i == 0 => (!old_rows.hashes.is_empty()).then_some(0),
+            None => (i < old_rows.hashes.len()).then_some(i),
+            Some(_) if values.is_empty() => return,
+            Some(key_columns) => old_rows.take(&values, key_columns),
+        };
+
+        match old_row {
+            Some(old_row) => {
+                matched[old_row] = true;
+                if old_rows.hashes[old_row] == hash_of(&values) {
+                    diff.unchanged_row_count += 1;
+                } else {
+                    changed.push((old_row, i, values));
+                }
+            }
+            None => {
+                diff.added_rows.insert(i);
+            }
+        }
+    })?;
+
+    for (i, hash) in old_rows.hashes.iter().enumerate() {
+        // blank rows can't be matched by their key
+        if !matched[i] && (key_columns.is_none() || hash.is_some()) {
+            diff.removed_rows.insert(i);
+        }
+    }
+
+    let old_values = read_rows(old_path.as_ref(), delimiter, encoding, options, &changed)?;
+    for (old_row, new_row, new) in changed {
+        diff.compare(old_row, &old_values[&old_row], new_row, &new);
+    }
+
+    diff.issue_changes = old_details
+        .issue_counts()
+        .into_iter()
+        .zip(new_details.issue_counts())
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((issue, old), (_, new))| IssueChange { issue, old, new })
+        .collect();
+    diff.old_details = old_details;
+    diff.new_details = new_details;
+
+    Ok(diff)
+}
+
+/// The values of the cells of a row, unquoted and unescaped
+fn values(cells: &[Cell], parse_options: &ParseOptions) -> Vec<String> {
+    cells
+        .iter()
+        .map(|cell| cell.value(parse_options).into_owned())
+        .collect()
+}
+
+/// Hash of the values of a row, or `None` for a blank row
+fn hash_of(values: &[String]) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    values.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// What is kept of the rows of the old file to match the rows of the new file to
+#[derive(Debug, Default)]
+struct OldRows {
+    /// Hash of the values of each row, by line number
+    hashes: Vec<Option<u64>>,
+    /// Line numbers of the rows with each key, in the order they appear in. Rows missing a key column and the header
+    /// aren't in there.
+    by_key: HashMap<Vec<String>, VecDeque<usize>>,
+}
+
+impl OldRows {
+    fn add(&mut self, values: Vec<String>, key_columns: Option<&[usize]>) {
+        let i = self.hashes.len();
+        self.hashes.push(hash_of(&values));
+
+        if let Some(key) = key_columns
+            .filter(|_| i != 0)
+            .and_then(|key_columns| key(&values, key_columns))
+        {
+            self.by_key.entry(key).or_default().push_back(i);
+        }
+    }
+
+    /// The first row with the same key that wasn't matched yet, rows with the same key being matched in order
+    fn take(&mut self, values: &[String], key_columns: &[usize]) -> Option<usize> {
+        self.by_key.get_mut(&key(values, key_columns)?)?.pop_front()
+    }
+}
+
+/// The values of the key columns, if the row has all of them
+fn key(values: &[String], key_columns: &[usize]) -> Option<Vec<String>> {
+    key_columns
+        .iter()
+        .map(|&i| values.get(i).cloned())
+        .collect()
+}
+
+/// The values of the rows of the old file that changed, by line number
+fn read_rows(
+    path: &Path,
+    delimiter: &str,
+    encoding: &str,
+    options: &DiffOptions,
+    changed: &[(usize, usize, Vec<String>)],
+) -> Result<HashMap<usize, Vec<String>>, CSVError> {
+    let mut old_values = HashMap::new();
+    let old_rows: HashSet<usize> = changed.iter().map(|&(old_row, ..)| old_row).collect();
+    let Some(&last_row) = old_rows.iter().max() else {
+        return Ok(old_values);
+    };
+
+    let rows = parser::read_rows(path, delimiter, encoding, &options.parse)?;
+    for (i, row) in rows.enumerate().take(last_row + 1) {
+        let cells = row?;
+        if old_rows.contains(&i) {
+            old_values.insert(i, values(&cells, &options.parse));
+        }
+    }
+
+    Ok(old_values)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::temp_file;
+
+    use super::*;
+
+    fn diff(old: &str, new: &str, key_columns: Option<Vec<usize>>) -> FileDiff {
+        let old_file = temp_file(old);
+        let new_file = temp_file(new);
+
+        let options = DiffOptions {
+            key_columns,
+            ..Default::default()
+        };
+        diff_files(old_file.path(), new_file.path(), ",", "utf-8", &options).unwrap()
+    }
+
+    fn change(column: usize, old: Option<&str>, new: Option<&str>) -> CellChange {
+        CellChange {
+            column,
+            old: old.map(Into::into),
+            new: new.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn test_diff_by_position() {
+        let diff = diff(
+            "id,amount\n1,10\n2,20\n3,30\n",
+            "id,amount\n1,10\n2,\"25\"\n3,30,x\n4,40\n",
+            None,
+        );
+
+        assert_eq!(diff.added_rows, RowSet::from([4]));
+        assert!(diff.removed_rows.is_empty());
+        assert_eq!(diff.unchanged_row_count, 2);
+        assert_eq!(
+            diff.changed_rows,
+            vec![
+                RowChange {
+                    old_row: 2,
+                    new_row: 2,
+                    cells: vec![change(1, Some("20"), Some("25"))]
+                },
+                RowChange {
+                    old_row: 3,
+                    new_row: 3,
+                    cells: vec![change(2, None, Some("x"))]
+                }
+            ]
+        );
+        assert_eq!(
+            diff.issue_changes,
+            vec![IssueChange {
+                issue: "too_many_columns",
+                old: 0,
+                new: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_by_key() {
+        let diff = diff(
+            "id,amount\n1,10\n2,20\n3,30\n",
+            "id,amount\n3,30\n\n1,15\n4,40\n",
+            Some(vec![0]),
+        );
+
+        assert_eq!(diff.added_rows, RowSet::from([4]));
+        assert_eq!(diff.removed_rows, RowSet::from([2]));
+        assert_eq!(diff.unchanged_row_count, 2);
+        assert_eq!(
+            diff.changed_rows,
+            vec![RowChange {
+                old_row: 1,
+                new_row: 3,
+                cells: vec![change(1, Some("10"), Some("15"))]
+            }]
+        );
+        assert_eq!(
+            diff.changed_rows[0].to_string(),
+            "Row 1 (row 3 in the new file) changed: column 1 from \"10\" to \"15\""
+        );
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let diff = diff(
+            "id,amount\n1,10\n1,20\n",
+            "id,amount\n1,10\n1,20\n1,30\n",
+            Some(vec![0]),
+        );
+
+        assert_eq!(diff.added_rows, RowSet::from([3]));
+        assert_eq!(diff.unchanged_row_count, 3);
+        assert_eq!(
+            diff.issue_changes,
+            vec![IssueChange {
+                issue: "duplicate_key_rows",
+                old: 2,
+                new: 3
+            },]
+        );
+    }
+
+    #[test]
+    fn test_removed_rows_by_position() {
+        let diff = diff("a,b\n1,2\n\n3,4\n", "a,b\n1,3\n", None);
+
+        assert_eq!(diff.removed_rows, RowSet::from([2, 3]));
+        assert_eq!(diff.unchanged_row_count, 1);
+        assert_eq!(
+            diff.changed_rows,
+            vec![RowChange {
+                old_row: 1,
+                new_row: 1,
+                cells: vec![change(1, Some("2"), Some("3"))]
+            }]
+        );
+    }
+
+    #[test]
+    fn test_same_files() {
+        let diff = diff("a,b\n1,2\n", "a,b\n\"1\",2\n", None);
+
+        assert!(diff.is_empty());
+        assert_eq!(
+            diff.report(10),
+            "0 rows were added, 0 rows were removed and 0 rows were changed, 2 rows are the same.\n"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    io,
+};
+
+use crate::{cell::Cell, csv_details::CSVDetails};
+
+/// Keeps track of the hashes of the rows seen so far, grouping row numbers that hash to the same contents.
+/// Different rows can have the same hash, so the groups have to be confirmed with [`confirm_duplicates`].
+/// A hash and a line number are kept for every row, so the memory used grows with the size of the file.
+#[derive(Debug, Default)]
+pub(crate) struct DuplicateTracker {
+    key_columns: Option<Vec<usize>>,
+    rows: HashMap<u64, Vec<usize>>,
+    keys: HashMap<u64, Vec<usize>>,
+}
+
+impl DuplicateTracker {
+    pub(crate) fn new(key_columns: Option<Vec<usize>>) -> Self {
+        Self {
+            key_columns,
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn add(&mut self, cells: &[Cell], row_number: usize) {
+        self.rows
+            .entry(hash_of(cells))
+            .or_default()
+            .push(row_number);
+
+        if let Some(key_columns) = &self.key_columns {
+            // rows missing any of the key columns can't be compared on their key
+            let key: Option<Vec<&Cell>> = key_columns.iter().map(|&i| cells.get(i)).collect();
+
+            if let Some(key) = key {
+                self.keys.entry(hash_of(&key)).or_default().push(row_number);
+            }
+        }
+    }
+
+    /// Returns the groups of duplicate rows, and the groups of duplicate keys if key columns were set
+    pub(crate) fn into_groups(self) -> (Vec<Vec<usize>>, Option<Vec<Vec<usize>>>) {
+        let key_groups = self.key_columns.map(|_| duplicate_groups(self.keys));
+
+        (duplicate_groups(self.rows), key_groups)
+    }
+}
+
+/// Splits the groups of rows with the same hash into groups of rows with the same contents, reading the rows again
+/// up to the last row of a group. Only one row per distinct contents of each group is kept in memory.
+pub(crate) fn confirm_duplicates(
+    csv_details: &mut CSVDetails,
+    key_columns: Option<&[usize]>,
+    rows: impl Iterator<Item = io::Result<Vec<Cell>>>,
+) -> io::Result<()> {
+    let mut row_groups = HashGroups::new(&csv_details.duplicate_rows);
+    let mut key_groups = HashGroups::new(
+        csv_details
+            .duplicate_key_rows
+            .as_deref()
+            .unwrap_or_default(),
+    );
+
+    let Some(last_row) = row_groups.last_row().max(key_groups.last_row()) else {
+        return Ok(());
+    };
+
+    for (i, row) in rows.enumerate().take(last_row + 1) {
+        let cells = row?;
+
+        row_groups.add(i, || cells.clone());
+        if let Some(key_columns) = key_columns {
+            key_groups.add(i, || {
+                key_columns
+                    .iter()
+                    .filter_map(|&column| cells.get(column).cloned())
+                    .collect()
+            });
+        }
+    }
+
+    csv_details.duplicate_rows = row_groups.into_groups();
+    if let Some(duplicate_key_rows) = &mut csv_details.duplicate_key_rows {
+        *duplicate_key_rows = key_groups.into_groups();
+    }
+
+    Ok(())
+}
+
+/// Groups of rows with the same hash, split by the contents of their rows
+struct HashGroups {
+    /// Index of the group of each row in a group
+    group_of: HashMap<usize, usize>,
+    /// The distinct contents of each group, with the rows having them
+    groups: Vec<Vec<(Vec<Cell>, Vec<usize>)>>,
+}
+
+impl HashGroups {
+    fn new(groups: &[Vec<usize>]) -> Self {
+        let group_of = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(group, rows)| rows.iter().map(move |&row| (row, group)))
+            .collect();
+
+        Self {
+            group_of,
+            groups: vec![Vec::new(); groups.len()],
+        }
+    }
+
+    fn last_row(&self) -> Option<usize> {
+        self.group_of.keys().max().copied()
+    }
+
+    /// Adds the row to its group, if it is in one, the contents only being read for rows in a group
+    fn add(&mut self, row: usize, contents: impl FnOnce() -> Vec<Cell>) {
+        let Some(&group) = self.group_of.get(&row) else {
+            return;
+        };
+        let contents = contents();
+        let group = &mut self.groups[group];
+
+        match group.iter_mut().find(|(other, _)| *other == contents) {
+            Some((_, rows)) => rows.push(row),
+            None => group.push((contents, vec![row])),
+        }
+    }
+
+    fn into_groups(self) -> Vec<Vec<usize>> {
+        let mut groups: Vec<Vec<usize>> = self
+            .groups
+            .into_iter()
+            .flatten()
+            .map(|(_, rows)| rows)
+            .filter(|rows| rows.len() > 1)
+            .collect();
+        groups.sort_unstable_by_key(|g| g[0]);
+        groups
+    }
+}
+
+fn hash_of(v: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    v.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Keep only the groups with more than one row, ordered by their first occurrence
+fn duplicate_groups(groups: HashMap<u64, Vec<usize>>) -> Vec<Vec<usize>> {
+    let mut groups: Vec<Vec<usize>> = groups.into_values().filter(|g| g.len() > 1).collect();
+    groups.sort_unstable_by_key(|g| g[0]);
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(cells: &[&str]) -> Vec<Cell> {
+        cells.iter().map(|c| Cell::new(*c)).collect()
+    }
+
+    #[test]
+    fn test_duplicate_rows() {
+        let mut tracker = DuplicateTracker::new(None);
+
+        tracker.add(&row(&["a", "b"]), 0);
+        tracker.add(&row(&["c", "d"]), 1);
+        tracker.add(&row(&["a", "b"]), 2);
+        tracker.add(&row(&["c", "d"]), 3);
+        tracker.add(&row(&["a", "b"]), 4);
+
+        assert_eq!(
+            tracker.into_groups(),
+            (vec![vec![0, 2, 4], vec![1, 3]], None)
+        );
+    }
+
+    #[test]
+    fn test_cell_boundaries_are_significant() {
+        let mut tracker = DuplicateTracker::new(None);
+
+        tracker.add(&row(&["a,b", "c"]), 0);
+        tracker.add(&row(&["a", "b,c"]), 1);
+
+        assert_eq!(tracker.into_groups(), (vec![], None));
+    }
+
+    #[test]
+    fn test_confirm_duplicates_splits_hash_collisions() {
+        // rows 1 and 2 stand for different rows with the same hash
+        let mut csv_details = CSVDetails {
+            duplicate_rows: vec![vec![1, 2, 3, 4]],
+            duplicate_key_rows: Some(vec![vec![1, 2, 4]]),
+            ..Default::default()
+        };
+        let rows = [
+            row(&["id", "value"]),
+            row(&["1", "a"]),
+            row(&["2", "b"]),
+            row(&["1", "a"]),
+            row(&["2", "c"]),
+        ];
+
+        confirm_duplicates(&mut csv_details, Some(&[0]), rows.into_iter().map(Ok)).unwrap();
+
+        assert_eq!(csv_details.duplicate_rows, vec![vec![1, 3]]);
+        assert_eq!(csv_details.duplicate_key_rows, Some(vec![vec![2, 4]]));
+    }
+
+    #[test]
+    fn test_confirm_duplicates_stops_after_last_row() {
+        let mut csv_details = CSVDetails {
+            duplicate_rows: vec![vec![0, 1]],
+            ..Default::default()
+        };
+        let rows = [Ok(row(&["a"])), Ok(row(&["a"]))]
+            .into_iter()
+            .chain(std::iter::once(Err(io::Error::other("not read"))));
+
+        confirm_duplicates(&mut csv_details, None, rows).unwrap();
+
+        assert_eq!(csv_details.duplicate_rows, vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let mut tracker = DuplicateTracker::new(Some(vec![0, 2]));
+
+        tracker.add(&row(&["1", "x", "a"]), 0);
+        tracker.add(&row(&["1", "y", "a"]), 1);
+        tracker.add(&row(&["1", "y"]), 2);
+        tracker.add(&row(&["2", "y", "a"]), 3);
+
+        assert_eq!(tracker.into_groups(), (vec![], Some(vec![vec![0, 1]])));
+    }
+}
@@ -8,10 +8,46 @@ pub enum UnknownEncoding {
     Encoding(String),
 }
 
+#[derive(Debug, Error)]
+pub enum InvalidOption {
+    #[error("invalid value {value} for {option}")]
+    Value { option: &'static str, value: String },
+    #[error("{option} must be a single ASCII character, not {value}")]
+    NotAscii { option: &'static str, value: String },
+    #[error("{0} can't be empty")]
+    Empty(&'static str),
+    #[error("duplicate {option} {value}")]
+    Duplicate { option: &'static str, value: String },
+    #[error("{option} can't be used together with {other}")]
+    Conflict {
+        option: &'static str,
+        other: &'static str,
+    },
+}
+
 #[derive(Debug, Error)]
 pub enum CSVError {
     #[error("{0}")]
     IO(#[from] io::Error),
     #[error("{0}")]
     UnknownEncoding(#[from] UnknownEncoding),
+    #[error("{0}")]
+    InvalidOption(#[from] InvalidOption),
+    #[error("the check was cancelled")]
+    Cancelled,
+    #[error("invalid dialect file: {0}")]
+    DialectFile(#[from] toml::de::Error),
+    #[error("invalid baseline file: {0}")]
+    Baseline(#[from] serde_json::Error),
+    #[error("invalid pattern: {0}")]
+    Pattern(#[from] regex::Error),
+    #[cfg(feature = "excel")]
+    #[error("{0}")]
+    Spreadsheet(#[from] calamine::Error),
+    #[cfg(feature = "arrow")]
+    #[error("{0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[cfg(feature = "parquet")]
+    #[error("{0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
 }
@@ -1,15 +1,189 @@
 use crate::error::{CSVError, UnknownEncoding};
 
-use std::{fs::File, io, path::Path};
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
-use encoding_rs::Encoding;
+use encoding_rs::{Encoder, EncoderResult, Encoding, UTF_16BE, UTF_16LE};
 use encoding_rs_io::DecodeReaderBytesBuilder;
 
+/// Reader adding the number of bytes read to a shared counter
+struct CountingReader<R> {
+    inner: R,
+    count: Arc<AtomicU64>,
+}
+
+impl<R: io::Read> io::Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+/// Writer encoding the UTF-8 text written to it, failing on the first character that can't be encoded
+pub(crate) struct EncodingWriter<W: Write> {
+    inner: W,
+    encoding: &'static Encoding,
+    /// Encoder of the encoding, `None` for UTF-16 which encoding_rs can't encode
+    encoder: Option<Encoder>,
+    /// Bytes of a character split between writes
+    pending: Vec<u8>,
+}
+
+impl<W: Write> EncodingWriter<W> {
+    fn new(inner: W, encoding: &'static Encoding) -> Result<Self, CSVError> {
+        let encoder = if encoding == UTF_16LE || encoding == UTF_16BE {
+            None
+        } else if encoding.output_encoding() == encoding {
+            Some(encoding.new_encoder())
+        } else {
+            // the replacement encoding can only be decoded
+            return Err(UnknownEncoding::Encoding(encoding.name().into()).into());
+        };
+
+        Ok(Self {
+            inner,
+            encoding,
+            encoder,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Encodes `text` to the inner writer, `last` ending the encoder's state (e.g. switching back to ASCII in
+    /// ISO-2022-JP) so the output so far is complete
+    fn encode(&mut self, mut text: &str, last: bool) -> io::Result<()> {
+        let Some(encoder) = &mut self.encoder else {
+            let bytes: Vec<u8> = text
+                .encode_utf16()
+                .flat_map(|unit| {
+                    if self.encoding == UTF_16BE {
+                        unit.to_be_bytes()
+                    } else {
+                        unit.to_le_bytes()
+                    }
+                })
+                .collect();
+            return self.inner.write_all(&bytes);
+        };
+
+        let mut buffer = vec![
+            0;
+            encoder
+                .max_buffer_length_from_utf8_without_replacement(text.len())
+                .unwrap_or(4096)
+                .max(16)
+        ];
+        loop {
+            let (result, read, written) =
+                encoder.encode_from_utf8_without_replacement(text, &mut buffer, last);
+            self.inner.write_all(&buffer[..written])?;
+            text = &text[read..];
+
+            match result {
+                EncoderResult::InputEmpty => break,
+                EncoderResult::OutputFull => (),
+                EncoderResult::Unmappable(c) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "the character {c:?} can't be encoded in {}",
+                            self.encoding.name()
+                        ),
+                    ))
+                }
+            }
+        }
+
+        if last {
+            // an encoder can't be used after its last input
+            *encoder = self.encoding.new_encoder();
+        }
+
+        Ok(())
+    }
+
+    /// Writes out the end of the encoded text
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.pending.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "incomplete UTF-8 character",
+            ));
+        }
+
+        self.encode("", true)
+    }
+}
+
+impl<W: Write> Write for EncodingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+
+        let valid_up_to = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            Err(e) if e.error_len().is_some() => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            Err(e) => e.valid_up_to(),
+        };
+
+        let pending = std::mem::take(&mut self.pending);
+        let text = std::str::from_utf8(&pending[..valid_up_to]).expect("checked above");
+        let result = self.encode(text, false);
+        self.pending = pending[valid_up_to..].to_vec();
+        result?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.finish()?;
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for EncodingWriter<W> {
+    fn drop(&mut self) {
+        // errors can't be reported when dropped, flush to get them
+        let _ = self.finish();
+    }
+}
+
+/// Creates a buffered file writer encoding the text written to it with `encoding`
+pub(crate) fn create_encoded_file(
+    filename: impl AsRef<Path>,
+    encoding: &str,
+) -> Result<EncodingWriter<io::BufWriter<File>>, CSVError> {
+    let encoding = Encoding::for_label(encoding.as_bytes())
+        .ok_or_else(|| UnknownEncoding::Encoding(encoding.into()))?;
+
+    EncodingWriter::new(io::BufWriter::new(File::create(filename)?), encoding)
+}
+
 pub(crate) fn read_encoded_file(
     filename: impl AsRef<Path>,
     encoding: &str,
 ) -> Result<impl io::BufRead, CSVError> {
-    let file = File::open(filename)?;
+    read_encoded_file_counting(filename, encoding, Default::default())
+}
+
+/// Same as [`read_encoded_file`], adding the number of bytes read from the file to `count`
+pub(crate) fn read_encoded_file_counting(
+    filename: impl AsRef<Path>,
+    encoding: &str,
+    count: Arc<AtomicU64>,
+) -> Result<impl io::BufRead, CSVError> {
+    let file = CountingReader {
+        inner: File::open(filename)?,
+        count,
+    };
 
     if let Some(encoding) = Encoding::for_label(encoding.as_bytes()) {
         Ok(io::BufReader::new(
@@ -23,3 +197,80 @@ pub(crate) fn read_encoded_file(
         )))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{ISO_2022_JP, WINDOWS_1252};
+
+    use super::*;
+
+    #[test]
+    fn test_encoding_writer_split_character() {
+        let mut writer = EncodingWriter::new(Vec::new(), WINDOWS_1252).unwrap();
+        let bytes = "café €".as_bytes();
+
+        writer.write_all(&bytes[..4]).unwrap();
+        writer.write_all(&bytes[4..]).unwrap();
+
+        assert_eq!(writer.inner, b"caf\xe9 \x80");
+        assert!(writer.pending.is_empty());
+    }
+
+    #[test]
+    fn test_encoding_writer_unencodable_character() {
+        let mut writer = EncodingWriter::new(Vec::new(), WINDOWS_1252).unwrap();
+
+        let error = writer.write_all("a,ą\n".as_bytes()).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "the character 'ą' can't be encoded in windows-1252"
+        );
+    }
+
+    #[test]
+    fn test_encoding_writer_utf16() {
+        let mut writer = EncodingWriter::new(Vec::new(), UTF_16LE).unwrap();
+        writer.write_all("aé".as_bytes()).unwrap();
+        assert_eq!(writer.inner, b"a\0\xe9\0");
+
+        let mut writer = EncodingWriter::new(Vec::new(), UTF_16BE).unwrap();
+        writer.write_all("aé".as_bytes()).unwrap();
+        assert_eq!(writer.inner, b"\0a\0\xe9");
+    }
+
+    #[test]
+    fn test_encoding_writer_replacement_encoding() {
+        let encoding = Encoding::for_label(b"iso-2022-kr").unwrap();
+
+        assert!(EncodingWriter::new(Vec::new(), encoding).is_err());
+    }
+
+    #[test]
+    fn test_encoding_writer_flush_ends_state() {
+        let mut writer = EncodingWriter::new(Vec::new(), ISO_2022_JP).unwrap();
+
+        writer.write_all("日本".as_bytes()).unwrap();
+        writer.flush().unwrap();
+        writer.write_all("a".as_bytes()).unwrap();
+        writer.flush().unwrap();
+
+        let (decoded, _, had_errors) = ISO_2022_JP.decode(&writer.inner);
+        assert!(!had_errors);
+        assert_eq!(decoded, "日本a");
+        assert!(writer.inner.ends_with(b"\x1b(Ba"));
+    }
+
+    #[test]
+    fn test_encoding_writer_incomplete_character() {
+        let mut writer = EncodingWriter::new(Vec::new(), WINDOWS_1252).unwrap();
+
+        writer.write_all(&"é".as_bytes()[..1]).unwrap();
+
+        assert_eq!(
+            writer.flush().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::str::FromStr;
+
+use crate::{cell::Cell, error::InvalidOption};
+
+/// A column of a fixed-width file, counted in characters from the start of the line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Column {
+    fn end(&self) -> usize {
+        self.start + self.length
+    }
+}
+
+/// Where the columns of a fixed-width file are, in increasing order and without overlapping.
+/// Text between the columns is ignored, text after the last column is read as an extra cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnSpec {
+    columns: Vec<Column>,
+}
+
+impl ColumnSpec {
+    /// Columns at the given positions
+    pub fn new(columns: Vec<Column>) -> Result<Self, InvalidOption> {
+        if columns.is_empty() {
+            return Err(InvalidOption::Empty("fixed width columns"));
+        }
+
+        let mut end = 0;
+        for column in &columns {
+            if column.length == 0 || column.start < end {
+                return Err(InvalidOption::Value {
+                    option: "fixed width columns",
+                    value: format!("{}:{}", column.start, column.length),
+                });
+            }
+            end = column.end();
+        }
+
+        Ok(Self { columns })
+    }
+
+    /// Columns with these widths, one after the other from the start of the line
+    pub fn from_widths(widths: &[usize]) -> Result<Self, InvalidOption> {
+        let columns = widths
+            .iter()
+            .scan(0, |start, &length| {
+                let column = Column {
+                    start: *start,
+                    length,
+                };
+                *start += length;
+                Some(column)
+            })
+            .collect();
+
+        Self::new(columns)
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Splits a line into cells with the padding trimmed. A line ending before the last column has fewer cells, and
+    /// one with text after the last column has an extra cell with that text. A line with only whitespace is blank.
+    pub fn split(&self, line: &str) -> Vec<Cell> {
+        if line.trim().is_empty() {
+            return Vec::new();
+        }
+
+        let chars: Vec<char> = line.chars().collect();
+        let text = |start: usize, end: usize| {
+            let text: String = chars[start..end.min(chars.len())].iter().collect();
+            Cell::new(text.trim())
+        };
+
+        let mut cells: Vec<_> = self
+            .columns
+            .iter()
+            .take_while(|column| column.start < chars.len())
+            .map(|column| text(column.start, column.end()))
+            .collect();
+
+        let end = self.columns.last().map_or(0, Column::end);
+        if chars[end.min(chars.len())..]
+            .iter()
+            .any(|c| !c.is_whitespace())
+        {
+            cells.push(text(end, chars.len()));
+        }
+
+        cells
+    }
+}
+
+impl FromStr for ColumnSpec {
+    type Err = InvalidOption;
+
+    /// Comma separated widths, e.g. `10,20,8`, or `start:length` positions, e.g. `0:10,12:20`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidOption::Value {
+            option: "fixed width columns",
+            value: s.into(),
+        };
+        let number = |n: &str| n.trim().parse::<usize>().map_err(|_| invalid());
+
+        if s.contains(':') {
+            let columns = s
+                .split(',')
+                .map(|column| {
+                    let (start, length) = column.split_once(':').ok_or_else(invalid)?;
+                    Ok(Column {
+                        start: number(start)?,
+                        length: number(length)?,
+                    })
+                })
+                .collect::<Result<_, InvalidOption>>()?;
+
+            Self::new(columns)
+        } else {
+            let widths = s.split(',').map(number).collect::<Result<Vec<_>, _>>()?;
+
+            Self::from_widths(&widths)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(spec: &ColumnSpec, line: &str) -> Vec<String> {
+        spec.split(line).iter().map(Cell::to_string).collect()
+    }
+
+    #[test]
+    fn test_split() {
+        let spec = ColumnSpec::from_widths(&[5, 3, 6]).unwrap();
+
+        assert_eq!(split(&spec, "Alice 42 12.50"), vec!["Alice", "42", "12.50"]);
+        assert_eq!(split(&spec, "Bob  7"), vec!["Bob", "7"]);
+        assert_eq!(
+            split(&spec, "Carol 1   3.00 extra"),
+            vec!["Carol", "1", "3.00", "extra"]
+        );
+        assert_eq!(split(&spec, "Dave  1   3.00   "), vec!["Dave", "1", "3.00"]);
+        assert!(split(&spec, "   ").is_empty());
+    }
+
+    #[test]
+    fn test_positions() {
+        let spec: ColumnSpec = "0:3, 4:2".parse().unwrap();
+
+        assert_eq!(split(&spec, "abc|de"), vec!["abc", "de"]);
+        assert_eq!(spec, "0:3,4:2".parse().unwrap());
+        assert_eq!(
+            "3,2".parse::<ColumnSpec>().unwrap().columns(),
+            [
+                Column {
+                    start: 0,
+                    length: 3
+                },
+                Column {
+                    start: 3,
+                    length: 2
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(
+            "0:3,2:2".parse::<ColumnSpec>().unwrap_err().to_string(),
+            "invalid value 2:2 for fixed width columns"
+        );
+        assert!("3,x".parse::<ColumnSpec>().is_err());
+        assert!("3,0".parse::<ColumnSpec>().is_err());
+        assert!(ColumnSpec::from_widths(&[]).is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use std::{collections::VecDeque, fmt::Display};
+
+use regex::Regex;
+
+use crate::{cell::Cell, parser::ParseOptions, row_set::RowSet};
+
+/// How to find the summary rows at the end of a file, e.g. `Total,,1234` or `Record count: 56`
+#[derive(Debug, Clone)]
+pub struct FooterOptions {
+    /// Only the last N rows of the file can be footer rows
+    pub last_rows: usize,
+    /// Rows with a cell matching one of these are footer rows
+    pub patterns: Vec<Regex>,
+    /// Rows with fewer columns than the header are footer rows too. A truncated last record is then taken to be a
+    /// footer row as well.
+    pub short_rows: bool,
+    /// Pattern matching the number of records declared in the footer, its first group being the number,
+    /// e.g. `Record count: (\d+)`. Rows matching it are footer rows.
+    pub record_count_pattern: Option<Regex>,
+}
+
+/// Summary rows found at the end of the file, which aren't checked as rows
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Footer {
+    /// Line numbers of the footer rows
+    pub rows: RowSet,
+    /// Number of records the footer says the file has, if found
+    pub declared_record_count: Option<usize>,
+    /// Number of valid rows, without the header, to compare to the declared number
+    pub valid_record_count: usize,
+}
+
+impl Footer {
+    /// Whether the footer declares a number of records other than the number of valid rows
+    pub fn record_count_mismatch(&self) -> bool {
+        self.declared_record_count
+            .is_some_and(|declared| declared != self.valid_record_count)
+    }
+}
+
+impl Display for Footer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.rows.is_empty() {
+            writeln!(f, "There are {} footer rows.", self.rows.len())?;
+        }
+
+        match self.declared_record_count {
+            Some(declared) if self.record_count_mismatch() => writeln!(
+                f,
+                "The footer declares {} records, but there are {} valid rows.",
+                declared, self.valid_record_count
+            ),
+            Some(declared) => writeln!(
+                f,
+                "The footer declares {declared} records, the same as the number of valid rows."
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Holds back the last rows of the file until it's known whether they are footer rows
+pub(crate) struct FooterDetector<'a> {
+    options: &'a FooterOptions,
+    parse_options: &'a ParseOptions,
+    rows: VecDeque<(usize, Vec<Cell>)>,
+}
+
+impl<'a> FooterDetector<'a> {
+    pub(crate) fn new(options: &'a FooterOptions, parse_options: &'a ParseOptions) -> Self {
+        Self {
+            options,
+            parse_options,
+            rows: VecDeque::with_capacity(options.last_rows + 1),
+        }
+    }
+
+    /// Adds a row to the held back rows, returning the oldest one once it can't be a footer row anymore
+    pub(crate) fn delay(&mut self, row: (usize, Vec<Cell>)) -> Option<(usize, Vec<Cell>)> {
+        self.rows.push_back(row);
+        if self.rows.len() > self.options.last_rows {
+            self.rows.pop_front()
+        } else {
+            None
+        }
+    }
+
+    /// The held back rows, when the end of the file wasn't reached
+    pub(crate) fn into_rows(self) -> Vec<(usize, Vec<Cell>)> {
+        self.rows.into()
+    }
+
+    /// The footer at the end of the held back rows, the trailing rows matching a pattern or shorter than the
+    /// `column_count` of the header, and the rest of the held back rows to check as usual. Blank rows between and
+    /// after the footer rows are checked as usual.
+    pub(crate) fn finish(mut self, column_count: usize) -> (Footer, Vec<(usize, Vec<Cell>)>) {
+        let mut rows = Vec::from(std::mem::take(&mut self.rows));
+        let mut footer = Footer::default();
+
+        let mut start = rows.len();
+        while start > 0 {
+            let cells = &rows[start - 1].1;
+            let short = self.options.short_rows && cells.len() < column_count;
+            if !cells.is_empty() && !short && !self.matches(cells) {
+                break;
+            }
+            start -= 1;
+        }
+
+        let mut remaining = rows.split_off(start);
+        let mut checked = rows;
+        for (row_number, cells) in remaining.drain(..) {
+            if cells.is_empty() {
+                checked.push((row_number, cells));
+                continue;
+            }
+
+            footer.rows.insert(row_number);
+            footer.declared_record_count = footer
+                .declared_record_count
+                .or_else(|| self.record_count(&cells));
+        }
+
+        (footer, checked)
+    }
+
+    fn values<'c>(&'c self, cells: &'c [Cell]) -> impl Iterator<Item = String> + 'c {
+        cells
+            .iter()
+            .map(|cell| cell.value(self.parse_options).into_owned())
+    }
+
+    fn matches(&self, cells: &[Cell]) -> bool {
+        self.values(cells).any(|value| {
+            self.options
+                .patterns
+                .iter()
+                .chain(&self.options.record_count_pattern)
+                .any(|pattern| pattern.is_match(&value))
+        })
+    }
+
+    fn record_count(&self, cells: &[Cell]) -> Option<usize> {
+        let pattern = self.options.record_count_pattern.as_ref()?;
+
+        self.values(cells).find_map(|value| {
+            pattern
+                .captures(&value)?
+                .get(1)?
+                .as_str()
+                .replace(',', "")
+                .parse()
+                .ok()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detect(rows: &[&[&str]], options: &FooterOptions) -> (Footer, Vec<usize>) {
+        let parse_options = ParseOptions::default();
+        let mut detector = FooterDetector::new(options, &parse_options);
+
+        let mut checked = Vec::new();
+        for (i, row) in rows.iter().enumerate() {
+            let cells = row.iter().map(|&cell| Cell::new(cell)).collect();
+            checked.extend(detector.delay((i + 1, cells)).map(|(i, _)| i));
+        }
+        let (footer, rest) = detector.finish(2);
+        checked.extend(rest.into_iter().map(|(i, _)| i));
+
+        (footer, checked)
+    }
+
+    fn options(patterns: &[&str], record_count_pattern: Option<&str>) -> FooterOptions {
+        FooterOptions {
+            last_rows: 3,
+            patterns: patterns.iter().map(|p| Regex::new(p).unwrap()).collect(),
+            short_rows: false,
+            record_count_pattern: record_count_pattern.map(|p| Regex::new(p).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_trailing_block() {
+        let rows: &[&[&str]] = &[
+            &["1", "2"],
+            &["3"],
+            &["4", "5"],
+            &["Total"],
+            &[],
+            &["3 rows"],
+        ];
+        let (footer, checked) = detect(rows, &options(&["^Total$", "rows$"], None));
+
+        assert_eq!(footer.rows, RowSet::from([4, 6]));
+        assert_eq!(checked, vec![1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn test_short_rows() {
+        let rows: &[&[&str]] = &[&["1", "2"], &["3", "4"], &["Total"]];
+        let (footer, checked) = detect(rows, &options(&[], None));
+
+        assert!(footer.rows.is_empty());
+        assert_eq!(checked, vec![1, 2, 3]);
+
+        let short_rows = FooterOptions {
+            short_rows: true,
+            ..options(&[], None)
+        };
+        let (footer, checked) = detect(rows, &short_rows);
+
+        assert_eq!(footer.rows, RowSet::from([3]));
+        assert_eq!(checked, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_patterns() {
+        let rows: &[&[&str]] = &[&["1", "2"], &["3", "4"], &["\"Total\"", "7"]];
+        let (footer, checked) = detect(rows, &options(&["^Total$"], None));
+
+        assert_eq!(footer.rows, RowSet::from([3]));
+        assert_eq!(checked, vec![1, 2]);
+
+        let (footer, _) = detect(rows, &options(&["^Sum$"], None));
+        assert!(footer.rows.is_empty());
+    }
+
+    #[test]
+    fn test_only_last_rows() {
+        let rows: &[&[&str]] = &[&["x 1"], &["x 2"], &["x 3"], &["x 4"], &["x 5"]];
+        let (footer, checked) = detect(rows, &options(&["^x"], None));
+
+        assert_eq!(footer.rows, RowSet::from([3, 4, 5]));
+        assert_eq!(checked, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_record_count() {
+        let rows: &[&[&str]] = &[&["1", "2"], &["Record count: 1,234", ""]];
+        let (footer, _) = detect(rows, &options(&[], Some(r"Record count: ([\d,]+)")));
+
+        assert_eq!(footer.rows, RowSet::from([2]));
+        assert_eq!(footer.declared_record_count, Some(1234));
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use std::collections::HashSet;
+
+use crate::{
+    cell::Cell,
+    error::{CSVError, InvalidOption},
+    parser::ParseOptions,
+};
+
+/// Options changing how the valid rows are written as JSON Lines
+#[derive(Debug, Clone)]
+pub struct JsonLinesOptions {
+    /// Whether the first row is a header, the rows are written as objects keyed by the header's names if it is and as
+    /// arrays otherwise
+    pub header: bool,
+    /// Add the line of the file each row starts on as a field with this name, or as the first item of the array without
+    /// a header. Lines are counted from 0 like line numbers, but also count the skipped lines and the line breaks in
+    /// quoted cells.
+    pub line_number_field: Option<String>,
+}
+
+impl Default for JsonLinesOptions {
+    fn default() -> Self {
+        Self {
+            header: true,
+            line_number_field: None,
+        }
+    }
+}
+
+/// Writes `value` as a JSON string
+fn write_string(writer: &mut impl Write, value: &str) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, value).map_err(Into::into)
+}
+
+/// Writes a row as a JSON object with a key per header name, in the header's order. Names missing from the header
+/// are the column's index.
+fn write_object(
+    writer: &mut impl Write,
+    header: &[String],
+    line_number: Option<(&str, usize)>,
+    row: &[Cell],
+    parse_options: &ParseOptions,
+) -> io::Result<()> {
+    write!(writer, "{{")?;
+
+    if let Some((field, line_number)) = line_number {
+        write_string(writer, field)?;
+        write!(writer, ":{line_number}")?;
+        if !row.is_empty() {
+            write!(writer, ",")?;
+        }
+    }
+
+    for (i, cell) in row.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+        match header.get(i) {
+            Some(name) => write_string(writer, name)?,
+            None => write_string(writer, &i.to_string())?,
+        }
+        write!(writer, ":")?;
+        write_string(writer, &cell.value(parse_options))?;
+    }
+
+    writeln!(writer, "}}")
+}
+
+/// Writes a row as a JSON array, starting with the line number if given
+fn write_array(
+    writer: &mut impl Write,
+    line_number: Option<usize>,
+    row: &[Cell],
+    parse_options: &ParseOptions,
+) -> io::Result<()> {
+    write!(writer, "[")?;
+
+    if let Some(line_number) = line_number {
+        write!(writer, "{line_number}")?;
+        if !row.is_empty() {
+            write!(writer, ",")?;
+        }
+    }
+
+    for (i, cell) in row.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+        write_string(writer, &cell.value(parse_options))?;
+    }
+
+    writeln!(writer, "]")
+}
+
+/// Fails if two keys of the objects would have the same name, as a JSON object can't have duplicate keys
+fn check_keys(header: &[String], line_number_field: Option<&str>) -> Result<(), InvalidOption> {
+    let mut keys = HashSet::new();
+
+    match line_number_field
+        .into_iter()
+        .chain(header.iter().map(String::as_str))
+        .find(|&key| !keys.insert(key))
+    {
+        Some(key) => Err(InvalidOption::Duplicate {
+            option: "JSON Lines key",
+            value: key.into(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Writes `rows` to `output_path` as JSON Lines with the values as read with `parse_options`, as objects keyed by `header`'s names
+/// unless the options say there's no header. The header row itself is left out. Each row comes with its line number
+/// and the line of the file it starts on.
+pub(crate) fn write_json_lines(
+    rows: impl Iterator<Item = io::Result<(usize, usize, Vec<Cell>)>>,
+    header: &[String],
+    output_path: impl AsRef<Path>,
+    parse_options: &ParseOptions,
+    options: &JsonLinesOptions,
+) -> Result<(), CSVError> {
+    if options.header {
+        check_keys(header, options.line_number_field.as_deref())?;
+    }
+
+    let mut writer = BufWriter::new(File::create(output_path)?);
+
+    for row_result in rows {
+        let (i, start_line, row) = row_result?;
+
+        if options.header {
+            if i == 0 {
+                continue;
+            }
+            let line_number = options
+                .line_number_field
+                .as_deref()
+                .map(|f| (f, start_line));
+            write_object(&mut writer, header, line_number, &row, parse_options)?;
+        } else {
+            let line_number = options.line_number_field.is_some().then_some(start_line);
+            write_array(&mut writer, line_number, &row, parse_options)?;
+        }
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        csv_details::CSVDetails,
+        parser::ParseOptions,
+        progress::ProgressTracker,
+        row_set::RowSet,
+        valid_file::{save_valid_file, OutputFormat, ValidFileOptions},
+    };
+
+    use super::*;
+
+    fn try_write(
+        content: &str,
+        valid_rows: RowSet,
+        json_options: JsonLinesOptions,
+        parse_options: &ParseOptions,
+    ) -> Result<String, CSVError> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rows.csv");
+        let out_path = dir.path().join("rows.jsonl");
+        fs::write(&path, content).unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = valid_rows;
+
+        save_valid_file(
+            &path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            parse_options,
+            &ValidFileOptions {
+                format: OutputFormat::JsonLines(json_options),
+                ..Default::default()
+            },
+            &mut ProgressTracker::default(),
+        )?;
+
+        Ok(fs::read_to_string(out_path).unwrap())
+    }
+
+    fn write(content: &str, valid_rows: RowSet, json_options: JsonLinesOptions) -> String {
+        try_write(content, valid_rows, json_options, &ParseOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_objects() {
+        let output = write(
+            "name,\"a \"\"b\"\"\"\nx,\"1,\n2\"\ninvalid\ny,\n",
+            RowSet::from([0, 1, 3]),
+            JsonLinesOptions::default(),
+        );
+
+        assert_eq!(
+            output,
+            "{\"name\":\"x\",\"a \\\"b\\\"\":\"1,\\n2\"}\n{\"name\":\"y\",\"a \\\"b\\\"\":\"\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_objects_line_numbers() {
+        let output = write(
+            "a,b\ninvalid\n1,2\n",
+            RowSet::from([0, 2]),
+            JsonLinesOptions {
+                line_number_field: Some("line".into()),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(output, "{\"line\":2,\"a\":\"1\",\"b\":\"2\"}\n");
+    }
+
+    #[test]
+    fn test_arrays() {
+        let output = write(
+            "a,b\n\"\"\"q\"\"\",2\n",
+            RowSet::from([0, 1]),
+            JsonLinesOptions {
+                header: false,
+                line_number_field: Some("line".into()),
+            },
+        );
+
+        assert_eq!(output, "[0,\"a\",\"b\"]\n[1,\"\\\"q\\\"\",\"2\"]\n");
+    }
+
+    #[test]
+    fn test_line_numbers_are_lines_of_the_file() {
+        let output = try_write(
+            "title\na,b\n# comment\n\"1\n1\",2\n3,4\n",
+            RowSet::from([0, 1, 2]),
+            JsonLinesOptions {
+                line_number_field: Some("line".into()),
+                ..Default::default()
+            },
+            &ParseOptions {
+                skip_lines: 1,
+                comment_prefix: Some("#".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            output,
+            "{\"line\":3,\"a\":\"1\\n1\",\"b\":\"2\"}\n{\"line\":5,\"a\":\"3\",\"b\":\"4\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let error = |content: &str, line_number_field: Option<&str>| {
+            let json_options = JsonLinesOptions {
+                line_number_field: line_number_field.map(Into::into),
+                ..Default::default()
+            };
+            try_write(
+                content,
+                RowSet::from([0, 1]),
+                json_options,
+                &ParseOptions::default(),
+            )
+            .unwrap_err()
+            .to_string()
+        };
+
+        assert_eq!(error("a,b,a\n1,2,3\n", None), "duplicate JSON Lines key a");
+        assert_eq!(
+            error("line,b\n1,2\n", Some("line")),
+            "duplicate JSON Lines key line"
+        );
+
+        // the rows are arrays without a header, so the names don't matter
+        let json_options = JsonLinesOptions {
+            header: false,
+            ..Default::default()
+        };
+        let output = try_write(
+            "a,a\n",
+            RowSet::from([0]),
+            json_options,
+            &ParseOptions::default(),
+        );
+        assert_eq!(output.unwrap(), "[\"a\",\"a\"]\n");
+    }
+}
@@ -1,7 +1,31 @@
-pub(crate) mod cell;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod baseline;
+pub mod cell;
+pub mod cell_locations;
+pub mod characters;
 pub mod checker;
+pub mod column_counts;
 pub mod csv_details;
+pub mod dialect;
+pub mod diff;
+pub(crate) mod duplicates;
 pub mod error;
 pub(crate) mod file;
+pub mod fixed_width;
+pub mod footer;
+pub mod jsonl;
+pub mod mojibake;
+#[cfg(feature = "parquet")]
+pub(crate) mod parquet;
 pub mod parser;
-pub(crate) mod valid_file;
+pub mod partial;
+pub mod preamble;
+pub mod progress;
+pub mod row_set;
+#[cfg(feature = "excel")]
+pub mod spreadsheet;
+pub mod strict;
+#[cfg(test)]
+pub(crate) mod test_utils;
+pub mod valid_file;
@@ -1,38 +1,463 @@
 use std::{path::PathBuf, process::exit, time::Instant};
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand};
 
-use csv_gp::checker::check_file;
+#[cfg(feature = "excel")]
+use csv_gp::spreadsheet::{check_sheet_with_hooks, is_spreadsheet};
+use csv_gp::{
+    baseline::{compare_to_baseline, Baseline, Tolerance, Tolerances},
+    checker::{check_file_with_hooks, CheckOptions},
+    csv_details::CSVDetails,
+    dialect::Dialect,
+    diff::{diff_files, DiffOptions},
+    error::{CSVError, InvalidOption},
+    fixed_width::ColumnSpec,
+    footer::FooterOptions,
+    parser::ParseOptions,
+    partial::Sample,
+    progress::{CancellationToken, CheckHooks, Progress},
+    valid_file::{Keep, LineTerminator, OutputDialect, OutputFormat, QuoteStyle, ValidFileOptions},
+};
+use indicatif::{ProgressBar, ProgressStyle};
+use regex::Regex;
+#[cfg(feature = "parquet")]
+use {arrow_schema::DataType, csv_gp::arrow::parse_column_types, std::collections::BTreeMap};
+
+/// Dialect the files are read with
+#[derive(Args, Default, Debug)]
+struct DialectArguments {
+    /// Dialect of the file, either a preset (`rfc4180`, `excel`, `excel-tab`, `unix`, `mysql` or `postgres-csv`) or
+    /// the path to a TOML dialect file. `rfc4180` by default.
+    #[clap(long, value_parser = Dialect::load)]
+    dialect: Option<Dialect>,
+    /// Delimiter of the file, overriding the dialect's. Can be any string, e.g. `||` or `~|~`.
+    #[clap(short, long)]
+    delimiter: Option<String>,
+    /// Encoding of the file, overriding the dialect's
+    #[clap(short, long)]
+    encoding: Option<String>,
+    /// Treat whitespace between a delimiter and a quote as insignificant
+    #[clap(long)]
+    trim_whitespace_outside_quotes: bool,
+}
+
+impl DialectArguments {
+    /// The dialect with the overrides applied
+    fn resolve(self) -> Result<Dialect, InvalidOption> {
+        let mut dialect = self.dialect.unwrap_or_default();
+        if let Some(delimiter) = self.delimiter {
+            dialect.delimiter = delimiter;
+        }
+        if let Some(encoding) = self.encoding {
+            dialect.encoding = encoding;
+        }
+        dialect.trim_whitespace_outside_quotes |= self.trim_whitespace_outside_quotes;
+        dialect.validate()?;
+
+        Ok(dialect)
+    }
+}
 
 /// CSV GP: Diagnose all your CSV issues
 #[derive(Parser, Default, Debug)]
+#[clap(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Arguments {
-    /// Path to file to check
-    file_path: PathBuf,
+    #[clap(subcommand)]
+    command: Option<Command>,
+    /// Path to file to check. A file named like a subcommand, e.g. `diff`, is checked with `csv-gp -- diff`
+    #[clap(required = true)]
+    file_path: Option<PathBuf>,
+    /// Sheet to check when the file is a spreadsheet (xlsx, xlsm, xlsb, xls or ods), the first sheet by default.
+    /// Can't be used with a CSV file
+    #[cfg(feature = "excel")]
+    #[clap(long)]
+    sheet: Option<String>,
     /// Path to output the correct rows in the file to
     #[clap(short, long)]
     correct_rows_path: Option<PathBuf>,
-    #[clap(default_value = ",", short, long)]
-    delimiter: char,
-    #[clap(default_value = "utf-8", short, long)]
-    encoding: String,
+    #[clap(flatten)]
+    dialect: DialectArguments,
+    /// Read the file as fixed-width columns rather than delimited cells, given as comma separated widths (e.g.
+    /// `10,20,8`) or `start:length` positions (e.g. `0:10,12:20`). The correct rows are then written as CSV with the
+    /// delimiter.
+    #[clap(long)]
+    fixed_width: Option<ColumnSpec>,
+    /// Only keep totals and the first N example line numbers of each issue, to check huge files in constant memory
+    #[clap(long)]
+    summary_examples: Option<usize>,
+    /// Skip this many lines before the header, e.g. a report title and generation date
+    #[clap(long, default_value = "0")]
+    skip_lines: usize,
+    /// Skip the lines before the header, found as the first row with the number of columns most rows have
+    #[clap(long, conflicts_with = "skip_lines")]
+    detect_preamble: bool,
+    /// Skip lines starting with this prefix, e.g. `#`, as comments
+    #[clap(long)]
+    comment_prefix: Option<String>,
+    /// Look for summary rows, e.g. `Total,,1234`, in the last N rows of the file and report them as the footer
+    /// rather than as rows with too few columns. The trailing rows matching `--footer-pattern` or
+    /// `--footer-record-count`, or shorter than the header with `--footer-short-rows`, are footer rows, so at least
+    /// one of them is needed.
+    #[clap(long)]
+    footer_rows: Option<usize>,
+    /// Rows in the footer with a cell matching this regular expression are footer rows, can be given several times
+    #[clap(long, requires = "footer_rows")]
+    footer_pattern: Vec<Regex>,
+    /// Rows in the footer with fewer columns than the header are footer rows, which hides a truncated last record
+    #[clap(long, requires = "footer_rows")]
+    footer_short_rows: bool,
+    /// Regular expression matching the number of records declared in the footer, its first group being the number,
+    /// e.g. `Record count: (\d+)`. The number is compared to the number of valid rows, so it can't be used when
+    /// sampling.
+    #[clap(long, requires = "footer_rows")]
+    footer_record_count: Option<Regex>,
+    /// Comma separated indices of the columns identifying a row, used to find rows with duplicate keys
+    #[clap(long, value_delimiter = ',')]
+    duplicate_key_columns: Option<Vec<usize>>,
+    /// Format of the correct rows file, `csv` (the rows as they are in the file), `normalised-csv`, `jsonl` or `parquet`
+    /// (if built with the `parquet` feature)
+    #[clap(long, default_value = "csv", requires = "correct_rows_path")]
+    output_format: OutputFormat,
+    /// Comma separated `name:type` Arrow types of the columns in a Parquet output, e.g. `id:Int64,amount:Float64`.
+    /// Other columns are strings.
+    #[cfg(feature = "parquet")]
+    #[clap(long, value_parser = parse_column_types, requires = "correct_rows_path")]
+    column_types: Option<BTreeMap<String, DataType>>,
+    /// Delimiter of a normalised CSV output, the file's delimiter by default. Must be an ASCII character.
+    #[clap(long, requires = "correct_rows_path")]
+    output_delimiter: Option<char>,
+    /// When to quote cells in a normalised CSV output: `necessary`, `always` or `non-numeric`
+    #[clap(long, default_value = "necessary", requires = "correct_rows_path")]
+    output_quote_style: QuoteStyle,
+    /// Line terminator of a normalised CSV output: `crlf` or `lf`
+    #[clap(long, default_value = "crlf", requires = "correct_rows_path")]
+    output_line_terminator: LineTerminator,
+    /// Encoding of a normalised CSV output, writing fails on a character it can't represent
+    #[clap(long, default_value = "utf-8", requires = "correct_rows_path")]
+    output_encoding: String,
+    /// The file has no header, so JSON Lines rows are written as arrays rather than objects keyed by the header
+    #[clap(long, requires = "correct_rows_path")]
+    no_header: bool,
+    /// Add the line of the file each row starts on (from 0, counting skipped lines and line breaks in quoted cells) to
+    /// a JSON Lines output as a field with this name
+    #[clap(long, requires = "correct_rows_path")]
+    line_number_field: Option<String>,
+    /// Path to output the rows that aren't correct to, as they are in the file
+    #[clap(long)]
+    rejected_rows_path: Option<PathBuf>,
+    /// Only output one of each group of duplicate rows to the correct rows path, either `first` or `last`
+    #[clap(long)]
+    deduplicate: Option<Keep>,
+    /// Replace invisible and control characters in the correct rows with this string, use "" to remove them
+    #[clap(long)]
+    replace_invisible_characters: Option<String>,
+    /// Repair text in the correct rows that looks like UTF-8 decoded as windows-1252, windows-1250, windows-1251 or
+    /// Mac Roman. Text in those encodings read as UTF-8 can't be repaired, as its bytes are replaced by U+FFFD
+    #[clap(long)]
+    repair_mojibake: bool,
+    /// Prefix cells in the correct rows that would be interpreted as a formula by spreadsheet software with `'`
+    #[clap(long)]
+    neutralise_formulas: bool,
+    /// Don't treat cells matching this regex as formulas, e.g. "^-$" for placeholder dashes. Plain numbers are
+    /// never treated as formulas.
+    #[clap(long)]
+    formula_allowlist: Option<Regex>,
+    /// Stop checking after finding N rows with issues, can't be used with the correct or rejected rows outputs
+    #[clap(long)]
+    max_issues: Option<usize>,
+    /// Only check the first N rows (including the header), can't be used with the correct or rejected rows outputs
+    #[clap(long)]
+    max_rows: Option<usize>,
+    /// Only check every Nth row, can't be used with the correct or rejected rows outputs
+    #[clap(long, conflicts_with = "sample_fraction")]
+    sample_every: Option<usize>,
+    /// Only check a random sample of this fraction of the rows, between 0 and 1, can't be used with the correct or
+    /// rejected rows outputs
+    #[clap(long)]
+    sample_fraction: Option<f64>,
+    /// Seed for picking the random sample, so the same rows are checked every time
+    #[clap(long, requires = "sample_fraction")]
+    sample_seed: Option<u64>,
+    /// Also parse the file strictly according to RFC 4180, reporting every deviation with its byte position and
+    /// the rows the strict and lenient parsers read differently
+    #[clap(long)]
+    strict: bool,
+    /// Don't show a progress bar while checking
+    #[clap(long)]
+    no_progress: bool,
+    /// Save the issue counts of the file to this path, as a baseline to compare the checks of later files to
+    #[clap(long)]
+    save_baseline: Option<PathBuf>,
+    /// Compare the issue counts of the file to a baseline saved with `--save-baseline`, exiting with code 2 if an
+    /// issue was found more times than its tolerance allows
+    #[clap(long)]
+    baseline: Option<PathBuf>,
+    /// How much an issue count can go up compared to the baseline, as a number (e.g. `10`) or a percentage of the
+    /// baseline count (e.g. `5%`)
+    #[clap(long, default_value = "0", requires = "baseline")]
+    tolerance: Tolerance,
+    /// Tolerance of a single issue, e.g. `too_many_columns=100`, can be given several times. Issues are named after
+    /// the fields of the results, e.g. `incorrect_cell_quote` or `blank_rows`.
+    #[clap(long, value_parser = parse_issue_tolerance, requires = "baseline")]
+    issue_tolerance: Vec<(String, Tolerance)>,
+}
+
+/// An `issue=tolerance` pair
+fn parse_issue_tolerance(s: &str) -> Result<(String, Tolerance), InvalidOption> {
+    let (issue, tolerance) = s.split_once('=').ok_or_else(|| InvalidOption::Value {
+        option: "issue tolerance",
+        value: s.into(),
+    })?;
+
+    if !CSVDetails::new()
+        .issue_counts()
+        .iter()
+        .any(|(name, _)| *name == issue)
+    {
+        return Err(InvalidOption::Value {
+            option: "issue",
+            value: issue.into(),
+        });
+    }
+
+    Ok((issue.into(), tolerance.parse()?))
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two files read with the same dialect, reporting the added, removed and changed rows and how the
+    /// issues found in them changed
+    Diff(DiffArguments),
+}
+
+#[derive(Args, Debug)]
+struct DiffArguments {
+    /// Path to the old file
+    old_path: PathBuf,
+    /// Path to the new file
+    new_path: PathBuf,
+    #[clap(flatten)]
+    dialect: DialectArguments,
+    /// Read the files as fixed-width columns rather than delimited cells, as in checking a file
+    #[clap(long)]
+    fixed_width: Option<ColumnSpec>,
+    /// Skip this many lines before the header in both files
+    #[clap(long, default_value = "0")]
+    skip_lines: usize,
+    /// Skip lines starting with this prefix, e.g. `#`, as comments
+    #[clap(long)]
+    comment_prefix: Option<String>,
+    /// Comma separated indices of the columns identifying a row, used to match the rows of the two files rather
+    /// than matching them by line number
+    #[clap(long, value_delimiter = ',')]
+    key_columns: Option<Vec<usize>>,
+    /// Number of changed rows to list
+    #[clap(long, default_value = "10")]
+    examples: usize,
+}
+
+fn diff(args: DiffArguments) {
+    let dialect = match args.dialect.resolve() {
+        Ok(dialect) => dialect,
+        Err(e) => {
+            eprintln!("{e}");
+            exit(1)
+        }
+    };
+    let options = DiffOptions {
+        parse: ParseOptions {
+            skip_lines: args.skip_lines,
+            comment_prefix: args.comment_prefix,
+            ..match args.fixed_width {
+                Some(columns) => ParseOptions::fixed_width(columns),
+                None => dialect.parse_options(),
+            }
+        },
+        key_columns: args.key_columns,
+    };
+
+    match diff_files(
+        &args.old_path,
+        &args.new_path,
+        &dialect.delimiter,
+        &dialect.encoding,
+        &options,
+    ) {
+        Err(e) => {
+            eprintln!("{e}");
+            exit(1)
+        }
+        Ok(diff) => print!("{}", diff.report(args.examples)),
+    }
+}
+
+fn progress_bar(hidden: bool) -> ProgressBar {
+    if hidden {
+        return ProgressBar::hidden();
+    }
+
+    ProgressBar::new(0).with_style(
+        ProgressStyle::with_template("{bar:40} {bytes}/{total_bytes} ({eta} left) {msg}")
+            .expect("progress bar template is valid"),
+    )
 }
 
 fn main() {
     let args = Arguments::parse();
+    let file_path = match args.command {
+        Some(Command::Diff(diff_args)) => return diff(diff_args),
+        None => args
+            .file_path
+            .expect("the file path is required without a subcommand"),
+    };
+
+    #[cfg(feature = "excel")]
+    if args.sheet.is_some() && !is_spreadsheet(&file_path) {
+        eprintln!(
+            "{}",
+            InvalidOption::Conflict {
+                option: "sheet",
+                other: "CSV input",
+            }
+        );
+        exit(1)
+    }
+
+    // loaded before checking, so a missing baseline doesn't waste a check of a huge file
+    let baseline = match args.baseline.as_deref().map(Baseline::load).transpose() {
+        Ok(baseline) => baseline,
+        Err(e) => {
+            eprintln!("{e}");
+            exit(1)
+        }
+    };
+    let tolerances = Tolerances {
+        default: args.tolerance,
+        issues: args.issue_tolerance.into_iter().collect(),
+    };
 
     let start = Instant::now();
 
-    let result = check_file(
-        args.file_path,
-        args.delimiter,
-        &args.encoding,
-        args.correct_rows_path.as_deref(),
-    );
+    let sample = match (args.sample_every, args.sample_fraction) {
+        (Some(stride), _) => Some(Sample::Stride(stride)),
+        (_, Some(fraction)) => Some(Sample::Random {
+            fraction,
+            seed: args.sample_seed,
+        }),
+        _ => None,
+    };
+
+    let dialect = match args.dialect.resolve() {
+        Ok(dialect) => dialect,
+        Err(e) => {
+            eprintln!("{e}");
+            exit(1)
+        }
+    };
+
+    let mut output_format = args.output_format;
+    match &mut output_format {
+        OutputFormat::Csv => {}
+        OutputFormat::NormalisedCsv(dialect) => {
+            *dialect = OutputDialect {
+                delimiter: args.output_delimiter,
+                quote_style: args.output_quote_style,
+                line_terminator: args.output_line_terminator,
+                encoding: args.output_encoding,
+            }
+        }
+        OutputFormat::JsonLines(json_options) => {
+            json_options.header = !args.no_header;
+            json_options.line_number_field = args.line_number_field;
+        }
+        #[cfg(feature = "parquet")]
+        OutputFormat::Parquet(arrow_options) => {
+            if let Some(column_types) = args.column_types {
+                arrow_options.column_types = column_types;
+            }
+        }
+    }
+
+    let options = CheckOptions {
+        parse: ParseOptions {
+            skip_lines: args.skip_lines,
+            comment_prefix: args.comment_prefix,
+            ..match args.fixed_width {
+                Some(columns) => ParseOptions::fixed_width(columns),
+                None => dialect.parse_options(),
+            }
+        },
+        duplicate_key_columns: args.duplicate_key_columns,
+        valid_file: ValidFileOptions {
+            deduplicate: args.deduplicate,
+            replace_invisible_characters: args.replace_invisible_characters,
+            repair_mojibake: args.repair_mojibake,
+            neutralise_formulas: args.neutralise_formulas,
+            formula_allowlist: args.formula_allowlist,
+            format: output_format,
+            rejected_rows_path: args.rejected_rows_path,
+        },
+        summary_examples: args.summary_examples,
+        max_issues: args.max_issues,
+        max_rows: args.max_rows,
+        sample,
+        strict: args.strict,
+        detect_preamble: args.detect_preamble,
+        footer: args.footer_rows.map(|last_rows| FooterOptions {
+            last_rows,
+            patterns: args.footer_pattern,
+            short_rows: args.footer_short_rows,
+            record_count_pattern: args.footer_record_count,
+        }),
+    };
+
+    let cancellation = CancellationToken::new();
+    let token = cancellation.clone();
+    if let Err(e) = ctrlc::set_handler(move || token.cancel()) {
+        eprintln!("Couldn't set the Ctrl-C handler: {e}");
+    }
+
+    let bar = progress_bar(args.no_progress);
+    let hooks = CheckHooks {
+        on_progress: Some(Box::new(|progress: &Progress| {
+            bar.set_length(progress.total_bytes);
+            bar.set_position(progress.bytes_read);
+            bar.set_message(format!(
+                "{} rows, {} with issues",
+                progress.rows_processed, progress.issues_found
+            ));
+        })),
+        cancellation: Some(cancellation),
+        ..Default::default()
+    };
 
+    let result = match () {
+        #[cfg(feature = "excel")]
+        _ if is_spreadsheet(&file_path) => check_sheet_with_hooks(
+            &file_path,
+            args.sheet.as_deref(),
+            args.correct_rows_path.as_deref(),
+            &options,
+            hooks,
+        ),
+        _ => check_file_with_hooks(
+            &file_path,
+            &dialect.delimiter,
+            &dialect.encoding,
+            args.correct_rows_path.as_deref(),
+            &options,
+            hooks,
+        ),
+    };
+
+    bar.finish_and_clear();
     println!("Checking took {}s.", start.elapsed().as_secs());
 
     match result {
+        Err(CSVError::Cancelled) => {
+            eprintln!("Checking was cancelled.");
+            exit(130)
+        }
         Err(e) => {
             eprintln!("{e}");
             exit(1)
@@ -42,6 +467,26 @@ fn main() {
             if let Some(path) = args.correct_rows_path {
                 println!("Correct rows were saved to {}", path.display())
             }
+            if let Some(path) = args.save_baseline {
+                if let Err(e) = Baseline::from_details(&r).save(&path) {
+                    eprintln!("{e}");
+                    exit(1)
+                }
+                println!("The baseline was saved to {}", path.display())
+            }
+            if let Some(baseline) = baseline {
+                let comparison = match compare_to_baseline(&r, &baseline, &tolerances) {
+                    Ok(comparison) => comparison,
+                    Err(e) => {
+                        eprintln!("{e}");
+                        exit(1)
+                    }
+                };
+                print!("{comparison}");
+                if comparison.has_regressions() {
+                    exit(2)
+                }
+            }
         }
     }
 }
@@ -0,0 +1,135 @@
+use std::{fmt::Display, ops::Range};
+
+use encoding_rs::{Encoding, MACINTOSH, UTF_8, WINDOWS_1250, WINDOWS_1251, WINDOWS_1252};
+
+/// Single byte encodings that UTF-8 text is commonly mistakenly decoded with, in order of preference
+const CANDIDATES: [&Encoding; 4] = [WINDOWS_1252, WINDOWS_1250, WINDOWS_1251, MACINTOSH];
+
+/// Text that was encoded with `encoded_as`, but then decoded with `decoded_as`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EncodingPair {
+    pub encoded_as: &'static str,
+    pub decoded_as: &'static str,
+}
+
+impl Display for EncodingPair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} decoded as {}", self.encoded_as, self.decoded_as)
+    }
+}
+
+/// Byte ranges of all the runs of consecutive non-ASCII characters.
+/// A multi-byte UTF-8 sequence decoded with a single byte encoding only ever produces non-ASCII characters,
+/// so each run can be repaired independently from the rest of the text.
+fn non_ascii_runs(s: &str) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+
+    for (i, c) in s.char_indices() {
+        match (c.is_ascii(), start) {
+            (false, None) => start = Some(i),
+            (true, Some(st)) => {
+                runs.push(st..i);
+                start = None;
+            }
+            _ => (),
+        }
+    }
+
+    if let Some(st) = start {
+        runs.push(st..s.len());
+    }
+
+    runs
+}
+
+/// Undo a run of UTF-8 text having been decoded with one of the candidate encodings, if it looks like it was
+fn repair_run(run: &str) -> Option<(String, EncodingPair)> {
+    CANDIDATES.iter().find_map(|encoding| {
+        let (bytes, _, unmappable) = encoding.encode(run);
+        if unmappable {
+            return None;
+        }
+
+        let repaired = String::from_utf8(bytes.into_owned()).ok()?;
+
+        Some((
+            repaired,
+            EncodingPair {
+                encoded_as: UTF_8.name(),
+                decoded_as: encoding.name(),
+            },
+        ))
+    })
+}
+
+/// Returns the encoding pair of the first piece of text in `s` that looks like mojibake
+pub(crate) fn detect(s: &str) -> Option<EncodingPair> {
+    if s.is_ascii() {
+        return None;
+    }
+
+    non_ascii_runs(s)
+        .into_iter()
+        .find_map(|run| repair_run(&s[run]))
+        .map(|(_, pair)| pair)
+}
+
+/// Replaces all the text in `s` that looks like mojibake with the repaired text
+pub(crate) fn repair(s: &str) -> String {
+    let mut repaired = String::with_capacity(s.len());
+    let mut last = 0;
+
+    for run in non_ascii_runs(s) {
+        if let Some((fixed, _)) = repair_run(&s[run.clone()]) {
+            repaired.push_str(&s[last..run.start]);
+            repaired.push_str(&fixed);
+            last = run.end;
+        }
+    }
+
+    repaired.push_str(&s[last..]);
+    repaired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(decoded_as: &'static str) -> Option<EncodingPair> {
+        Some(EncodingPair {
+            encoded_as: "UTF-8",
+            decoded_as,
+        })
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect("MÃ¼ller"), pair("windows-1252"));
+        assert_eq!(detect("donâ€™t"), pair("windows-1252"));
+        assert_eq!(detect("ÐŸÑ€Ð¸Ð²ÐµÑ‚"), pair("windows-1252"));
+        assert_eq!(detect("РџСЂРёРІРµС‚"), pair("windows-1251"));
+    }
+
+    #[test]
+    fn test_detect_correct_text() {
+        for s in [
+            "plain",
+            "Müller",
+            "€ 5",
+            "Привет",
+            "日本語",
+            "naïve café",
+            "\u{FFFD}",
+        ] {
+            assert_eq!(detect(s), None, "{s}");
+        }
+    }
+
+    #[test]
+    fn test_repair() {
+        assert_eq!(repair("MÃ¼ller & SÃ¶hne"), "Müller & Söhne");
+        assert_eq!(repair("donâ€™t – Müller"), "don’t – Müller");
+        assert_eq!(repair("plain"), "plain");
+    }
+}
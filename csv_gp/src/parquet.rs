@@ -0,0 +1,23 @@
+use std::{fs::File, path::Path};
+
+use parquet::arrow::ArrowWriter;
+
+use crate::{arrow::ValidRowsReader, error::CSVError, progress::ProgressTracker};
+
+/// Writes the rows read by `reader` to a Parquet file at `output_path`, stopping early if the check is cancelled
+pub(crate) fn write_parquet(
+    reader: ValidRowsReader,
+    output_path: impl AsRef<Path>,
+    progress: &mut ProgressTracker,
+) -> Result<(), CSVError> {
+    let schema = arrow_array::RecordBatchReader::schema(&reader);
+    let mut writer = ArrowWriter::try_new(File::create(output_path)?, schema, None)?;
+
+    for batch in progress.until_cancelled(reader) {
+        writer.write(&batch?)?;
+    }
+
+    writer.close()?;
+
+    Ok(())
+}
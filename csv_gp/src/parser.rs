@@ -1,14 +1,77 @@
-use crate::{cell::Cell, error::CSVError, file::read_encoded_file};
-use std::{io, path::Path};
+use crate::{
+    cell::Cell,
+    error::{CSVError, InvalidOption},
+    file::{read_encoded_file, read_encoded_file_counting},
+    fixed_width::ColumnSpec,
+    preamble::SkippedLines,
+};
+use std::{
+    borrow::Cow,
+    io, iter,
+    path::Path,
+    sync::{atomic::AtomicU64, Arc},
+};
+
+/// Options changing how the rows of a file are interpreted
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Treat whitespace between a delimiter and a quote as insignificant, so ` "abc" ` is read as `"abc"`
+    pub trim_whitespace_outside_quotes: bool,
+    /// Whether cells can be quoted with `"`, otherwise quotes are read like any other character
+    pub quoting: bool,
+    /// Character making the next character literal, e.g. `\` in MySQL exports, so that an escaped delimiter, quote
+    /// or line break doesn't end the cell
+    pub escape: Option<char>,
+    /// Number of lines before the header that aren't part of the CSV, e.g. a report title
+    pub skip_lines: usize,
+    /// Lines starting with this string are comments rather than rows, unless they are inside a quoted cell
+    pub comment_prefix: Option<String>,
+    /// Read each line as a row of fixed-width columns rather than cells separated by the delimiter. Fixed-width
+    /// files aren't quoted, so `quoting` must be false.
+    pub fixed_width: Option<ColumnSpec>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            trim_whitespace_outside_quotes: false,
+            quoting: true,
+            escape: None,
+            skip_lines: 0,
+            comment_prefix: None,
+            fixed_width: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Options to read a fixed-width file with these columns
+    pub fn fixed_width(columns: ColumnSpec) -> Self {
+        Self {
+            quoting: false,
+            fixed_width: Some(columns),
+            ..Default::default()
+        }
+    }
+}
 
 struct CSVReader<R> {
     reader: R,
-    delimiter: char,
+    delimiter: String,
+    options: ParseOptions,
 }
 
 impl<R: io::BufRead> CSVReader<R> {
-    fn new(reader: R, delimiter: char) -> Self {
-        Self { reader, delimiter }
+    fn new(reader: R, delimiter: &str) -> Self {
+        Self {
+            reader,
+            delimiter: delimiter.to_string(),
+            options: ParseOptions::default(),
+        }
+    }
+
+    fn with_options(self, options: ParseOptions) -> Self {
+        Self { options, ..self }
     }
 
     /// Returns a owned iterator of all the csv lines
@@ -17,9 +80,16 @@ impl<R: io::BufRead> CSVReader<R> {
     }
 }
 
-struct CSVLineIntoIter<B> {
+pub(crate) struct CSVLineIntoIter<B> {
     lines: io::Lines<B>,
-    delimiter: char,
+    delimiter: String,
+    options: ParseOptions,
+    /// Number of lines of the file read so far
+    lines_read: usize,
+    /// Line of the file the last row read starts on
+    row_start_line: usize,
+    /// The lines that aren't rows, if they are recorded
+    skipped_lines: Option<SkippedLines>,
 }
 
 impl<B: io::BufRead> CSVLineIntoIter<B> {
@@ -27,14 +97,194 @@ impl<B: io::BufRead> CSVLineIntoIter<B> {
         Self {
             lines: reader.reader.lines(),
             delimiter: reader.delimiter,
+            options: reader.options,
+            lines_read: 0,
+            row_start_line: 0,
+            skipped_lines: None,
         }
     }
+
+    /// Records the skipped preamble and comment lines into `skipped_lines`
+    pub(crate) fn recording_skipped_lines(self, skipped_lines: SkippedLines) -> Self {
+        Self {
+            skipped_lines: Some(skipped_lines),
+            ..self
+        }
+    }
+
+    /// The lines skipped while reading, if they were recorded
+    pub(crate) fn into_skipped_lines(self) -> Option<SkippedLines> {
+        self.skipped_lines
+    }
+
+    /// Number of lines of the file read so far, which is where the next row starts unless it's after a comment
+    pub(crate) fn lines_read(&self) -> usize {
+        self.lines_read
+    }
+
+    /// The rows with the line of the file each starts on, counted from 0 including the skipped lines
+    pub(crate) fn with_start_lines(
+        mut self,
+    ) -> impl Iterator<Item = io::Result<(usize, Vec<Cell>)>> {
+        iter::from_fn(move || {
+            let row = self.next()?;
+            Some(row.map(|cells| (self.row_start_line, cells)))
+        })
+    }
+
+    /// Reads the next line of the file, skipping the lines before the header and the comments outside quoted cells
+    fn next_line(&mut self, in_row: bool) -> Option<io::Result<String>> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            let line_number = self.lines_read;
+            self.lines_read += 1;
+
+            if line_number < self.options.skip_lines {
+                if let Some(skipped_lines) = &mut self.skipped_lines {
+                    skipped_lines
+                        .preamble
+                        .push(line.trim_end_matches('\r').to_string());
+                }
+            } else if !in_row
+                && self
+                    .options
+                    .comment_prefix
+                    .as_deref()
+                    .is_some_and(|prefix| line.starts_with(prefix))
+            {
+                if let Some(skipped_lines) = &mut self.skipped_lines {
+                    skipped_lines.comments.insert(line_number);
+                }
+            } else {
+                return Some(Ok(line));
+            }
+        }
+    }
+
+    fn is_open(&self, s: &str) -> bool {
+        // a fixed-width row is always one line
+        if self.options.fixed_width.is_some() {
+            return false;
+        }
+
+        let s = match self.options.escape {
+            // an escaped line break continues the row
+            Some(escape) if ends_with_escape(s, escape) => return true,
+            Some(escape) => Cow::Owned(remove_escaped(s, escape)),
+            None => Cow::Borrowed(s),
+        };
+
+        if !self.options.quoting {
+            false
+        } else if self.options.trim_whitespace_outside_quotes {
+            has_open_quotes(
+                &remove_whitespace_around_quotes(&s, &self.delimiter),
+                &self.delimiter,
+            )
+        } else {
+            has_open_quotes(&s, &self.delimiter)
+        }
+    }
+
+    fn parse_cells(&self, row: &str) -> io::Result<Vec<Cell>> {
+        if let Some(columns) = &self.options.fixed_width {
+            return Ok(columns.split(row));
+        }
+
+        let cells = match (self.options.quoting, self.options.escape) {
+            (true, None) => parse_cells(row, &self.delimiter)?,
+            (quoting, escape) => parse_cells_escaped(row, &self.delimiter, quoting, escape),
+        };
+
+        if self.options.trim_whitespace_outside_quotes {
+            Ok(cells
+                .into_iter()
+                .map(Cell::trim_whitespace_outside_quotes)
+                .collect())
+        } else {
+            Ok(cells)
+        }
+    }
+}
+
+/// Whether the string ends with an escape character that isn't itself escaped
+fn ends_with_escape(s: &str, escape: char) -> bool {
+    !(s.chars().rev().take_while(|&c| c == escape).count()).is_multiple_of(2)
+}
+
+/// Replaces every escaped character and its escape character with a placeholder, so that escaped quotes and
+/// delimiters aren't seen when looking for open quotes
+fn remove_escaped(s: &str, escape: char) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == escape {
+            chars.next();
+            result.push('\u{FFFF}');
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/// Removes whitespace between a quote and a delimiter, newline or either end of the string,
+/// so that ` "a" , "b"` is seen as `"a","b"`
+fn remove_whitespace_around_quotes(s: &str, delimiter: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut whitespace = String::new();
+    let mut chars = s.char_indices();
+
+    loop {
+        let next = chars.next();
+        let at_delimiter = next.is_some_and(|(i, _)| s[i..].starts_with(delimiter));
+
+        match next {
+            Some((_, c)) if c.is_whitespace() && !at_delimiter && c != '\n' => {
+                whitespace.push(c);
+                continue;
+            }
+            next => {
+                // the whitespace is only pushed after this, so the result ends with the previous character
+                let after_boundary =
+                    result.is_empty() || result.ends_with(delimiter) || result.ends_with('\n');
+                let before_boundary = next.is_none_or(|(_, c)| at_delimiter || c == '\n');
+
+                let drop = (after_boundary && next.is_some_and(|(_, c)| c == '"'))
+                    || (result.ends_with('"') && before_boundary);
+
+                if !drop {
+                    result.push_str(&whitespace);
+                }
+                whitespace.clear();
+
+                match next {
+                    Some((i, _)) if at_delimiter => {
+                        result.push_str(delimiter);
+                        // skip the rest of the delimiter
+                        let end = i + delimiter.len();
+                        while chars.clone().next().is_some_and(|(j, _)| j < end) {
+                            chars.next();
+                        }
+                    }
+                    Some((_, c)) => result.push(c),
+                    None => break,
+                }
+            }
+        }
+    }
+
+    result
 }
 
 /// Determines if a passed string has fully closed quotes or not
-fn has_open_quotes(s: &str, delimiter: char) -> bool {
+fn has_open_quotes(s: &str, delimiter: &str) -> bool {
     let mut is_open = false;
-    let mut prev_char: Option<char> = None;
 
     // Remove quoted quotes - Basically any two consecutive quotes can be ignored for the purposes of this
     // function.
@@ -44,23 +294,27 @@ fn has_open_quotes(s: &str, delimiter: char) -> bool {
         .replace("\"\"", "")
         .replace(&format!("\"{}\"", delimiter), "");
 
-    let mut chars = s2.chars().peekable();
-    while let Some(current_char) = chars.next() {
-        match (prev_char, current_char, chars.peek()) {
+    for (i, current_char) in s2.char_indices() {
+        if current_char != '"' {
+            continue;
+        }
+
+        let after_delimiter = s2[..i].ends_with(delimiter);
+        let rest = &s2[i + 1..];
+
+        match (i, rest) {
             // Quote at beginning of line
-            (None, '"', _) => is_open = true,
+            (0, _) => is_open = true,
             // Quote at the end of the string preceeded by the delimiter (`,"`), unless already open
-            (Some(c), '"', None) if c == delimiter && !is_open => is_open = true,
+            (_, "") if after_delimiter && !is_open => is_open = true,
             // Quote at the end of the string
-            (_, '"', None) => is_open = false,
+            (_, "") => is_open = false,
             // Quote followed by the delimiter (`",`), if already open
-            (_, '"', Some(n)) if n == &delimiter && is_open => is_open = false,
+            (_, rest) if rest.starts_with(delimiter) && is_open => is_open = false,
             // Quote preceded by the delimiter (`,"`)
-            (Some(c), '"', _) if c == delimiter => is_open = true,
+            _ if after_delimiter => is_open = true,
             _ => (),
         }
-
-        prev_char = Some(current_char);
     }
 
     is_open
@@ -73,12 +327,12 @@ impl<B: io::BufRead> Iterator for CSVLineIntoIter<B> {
         let mut current_selection = String::new();
 
         loop {
-            match self.lines.next() {
+            match self.next_line(!current_selection.is_empty()) {
                 // we have reached the end of the file
                 None => {
                     // in the case of a dangling quote current selection will be non-empty
                     if !current_selection.is_empty() {
-                        return Some(parse_cells(&current_selection, self.delimiter));
+                        return Some(self.parse_cells(&current_selection));
                     } else {
                         return None;
                     }
@@ -88,14 +342,17 @@ impl<B: io::BufRead> Iterator for CSVLineIntoIter<B> {
                     // special case for strange CRLF files with \r\r\n as line break, not ideal as it will alter valid quoted sequences also but ¯\_(ツ)_/¯
                     let line = line.trim_end_matches('\r');
 
+                    if current_selection.is_empty() {
+                        self.row_start_line = self.lines_read - 1;
+                    }
                     current_selection.push_str(line);
 
-                    if has_open_quotes(&current_selection, self.delimiter) {
+                    if self.is_open(&current_selection) {
                         // this newline is escaped, add back to text and continue loop
                         current_selection.push('\n');
                     } else {
                         // we have a full csv line, parse and return
-                        return Some(parse_cells(&current_selection, self.delimiter));
+                        return Some(self.parse_cells(&current_selection));
                     }
                 }
             }
@@ -103,25 +360,49 @@ impl<B: io::BufRead> Iterator for CSVLineIntoIter<B> {
     }
 }
 
-fn parse_cells(row: &str, delimiter: char) -> io::Result<Vec<Cell>> {
+fn parse_cells(row: &str, delimiter: &str) -> io::Result<Vec<Cell>> {
+    Ok(parse_cells_escaped(row, delimiter, true, None))
+}
+
+/// Same as [`parse_cells`], reading the character after `escape` literally and only treating quotes as such if
+/// `quoting` is set
+fn parse_cells_escaped(
+    row: &str,
+    delimiter: &str,
+    quoting: bool,
+    escape: Option<char>,
+) -> Vec<Cell> {
     if row.is_empty() {
-        return Ok(Vec::new());
+        return Vec::new();
     }
 
     let mut cells = Vec::new();
     let mut current_selection = String::new();
     let mut opened_quote = false;
+    let mut escaped = false;
+    // end of the delimiter the characters are part of, they are skipped until then
+    let mut delimiter_end = 0;
+
+    for (i, char) in row.char_indices() {
+        if i < delimiter_end {
+            continue;
+        }
 
-    for char in row.chars() {
-        if char == delimiter && !opened_quote {
+        if escaped {
+            current_selection.push(char);
+            escaped = false;
+        } else if Some(char) == escape {
+            current_selection.push(char);
+            escaped = true;
+        } else if !opened_quote && row[i..].starts_with(delimiter) {
             // we are at the end of a cell, reset stack
-            cells.push(Cell::new(current_selection.clone()));
-            current_selection = String::new();
+            cells.push(Cell::new(std::mem::take(&mut current_selection)));
+            delimiter_end = i + delimiter.len();
         } else {
             // ... otherwise add to the stack
             current_selection.push(char);
-            // If we're on a quote, add to stack and flip the opened quote flag
-            if char == '"' {
+            // If we're on a quote, flip the opened quote flag
+            if quoting && char == '"' {
                 opened_quote = !opened_quote;
             }
         }
@@ -130,21 +411,120 @@ fn parse_cells(row: &str, delimiter: char) -> io::Result<Vec<Cell>> {
     // add final cell to cells
     cells.push(Cell::new(current_selection));
 
-    Ok(cells)
+    cells
+}
+
+/// Delimiters can be any non-empty string, e.g. `,`, `§` or `~|~`
+fn check_delimiter(delimiter: &str) -> Result<(), InvalidOption> {
+    if delimiter.is_empty() {
+        Err(InvalidOption::Empty("delimiter"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Delimiter between the cells of a row, any non-empty string. Converts from a `char` as well as a string, so both
+/// `','` and `"~|~"` can be passed to the functions taking a delimiter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delimiter(String);
+
+impl Delimiter {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<char> for Delimiter {
+    fn from(delimiter: char) -> Self {
+        Self(delimiter.to_string())
+    }
+}
+
+impl From<&str> for Delimiter {
+    fn from(delimiter: &str) -> Self {
+        Self(delimiter.into())
+    }
+}
+
+impl From<String> for Delimiter {
+    fn from(delimiter: String) -> Self {
+        Self(delimiter)
+    }
+}
+
+impl From<&String> for Delimiter {
+    fn from(delimiter: &String) -> Self {
+        Self(delimiter.clone())
+    }
+}
+
+pub fn parse_file(
+    filename: impl AsRef<Path>,
+    delimiter: impl Into<Delimiter>,
+    encoding: &str,
+) -> Result<impl Iterator<Item = io::Result<Vec<Cell>>>, CSVError> {
+    parse_file_with_options(filename, delimiter, encoding, &ParseOptions::default())
+}
+
+/// Same as [`parse_file`], interpreting the rows according to `options`
+pub fn parse_file_with_options(
+    filename: impl AsRef<Path>,
+    delimiter: impl Into<Delimiter>,
+    encoding: &str,
+    options: &ParseOptions,
+) -> Result<impl Iterator<Item = io::Result<Vec<Cell>>>, CSVError> {
+    read_rows(filename, delimiter.into().as_str(), encoding, options)
 }
 
-pub fn parse_file<'a>(
-    filename: impl AsRef<Path> + 'a,
-    delimiter: char,
-    encoding: &'a str,
-) -> Result<impl Iterator<Item = io::Result<Vec<Cell>>> + 'a, CSVError> {
+/// Same as [`parse_file_with_options`], with access to the state of the parser
+pub(crate) fn read_rows(
+    filename: impl AsRef<Path>,
+    delimiter: &str,
+    encoding: &str,
+    options: &ParseOptions,
+) -> Result<CSVLineIntoIter<impl io::BufRead>, CSVError> {
+    check_delimiter(delimiter)?;
     let reader = read_encoded_file(filename, encoding)?;
 
-    let parser = CSVReader::new(reader, delimiter);
+    let parser = CSVReader::new(reader, delimiter).with_options(options.clone());
 
     Ok(parser.into_lines())
 }
 
+/// Same as [`parse_file_with_options`], adding the number of bytes read from the file to `count`
+pub(crate) fn parse_file_counting(
+    filename: impl AsRef<Path>,
+    delimiter: &str,
+    encoding: &str,
+    options: &ParseOptions,
+    count: Arc<AtomicU64>,
+) -> Result<CSVLineIntoIter<impl io::BufRead>, CSVError> {
+    check_delimiter(delimiter)?;
+    let reader = read_encoded_file_counting(filename, encoding, count)?;
+
+    let parser = CSVReader::new(reader, delimiter).with_options(options.clone());
+
+    Ok(parser.into_lines())
+}
+
+/// Reads the names in the first row of the file, unescaped, or none if the file is empty
+pub(crate) fn read_header(
+    filename: impl AsRef<Path>,
+    delimiter: &str,
+    encoding: &str,
+    options: &ParseOptions,
+) -> Result<Vec<String>, CSVError> {
+    let header = read_rows(filename, delimiter, encoding, options)?
+        .next()
+        .transpose()?
+        .unwrap_or_default();
+
+    Ok(header
+        .iter()
+        .map(|cell| cell.value(options).into_owned())
+        .collect())
+}
+
 #[cfg(test)]
 mod has_open_quotes_tests {
     use super::*;
@@ -153,84 +533,84 @@ mod has_open_quotes_tests {
     fn test_empty() {
         let input = "";
 
-        assert!(!has_open_quotes(input, ','))
+        assert!(!has_open_quotes(input, ","))
     }
 
     #[test]
     fn test_no_quotes() {
         let input = "asdfasdf";
 
-        assert!(!has_open_quotes(input, ','))
+        assert!(!has_open_quotes(input, ","))
     }
 
     #[test]
     fn test_with_opened_quote() {
         let input = "\"asdfasdf";
 
-        assert!(has_open_quotes(input, ','))
+        assert!(has_open_quotes(input, ","))
     }
 
     #[test]
     fn test_with_closed_quote() {
         let input = "\"\"asdfasdf";
 
-        assert!(!has_open_quotes(input, ','))
+        assert!(!has_open_quotes(input, ","))
     }
 
     #[test]
     fn test_two_quotes_middle() {
         let input = "\"asdf\"\"asdf";
 
-        assert!(has_open_quotes(input, ','))
+        assert!(has_open_quotes(input, ","))
     }
 
     #[test]
     fn test_two_quotes_end() {
         let input = "\"asdfasdf\"\"";
 
-        assert!(has_open_quotes(input, ','))
+        assert!(has_open_quotes(input, ","))
     }
 
     #[test]
     fn test_three_quotes_end() {
         let input = "\"asdfasdf\"\"\"";
 
-        assert!(!has_open_quotes(input, ','))
+        assert!(!has_open_quotes(input, ","))
     }
 
     #[test]
     fn test_three_quotes_start() {
         let input = "\"\"\"asdfasdf";
 
-        assert!(has_open_quotes(input, ','))
+        assert!(has_open_quotes(input, ","))
     }
 
     #[test]
     fn test_only_three_quotes_start() {
         let input = "\"\"\"";
 
-        assert!(has_open_quotes(input, ','))
+        assert!(has_open_quotes(input, ","))
     }
 
     #[test]
     fn test_three_quotes_end_of_line() {
         let input = "X,\"\"\"";
 
-        assert!(has_open_quotes(input, ','))
+        assert!(has_open_quotes(input, ","))
     }
 
     #[test]
     fn test_just_delimiter_quotes() {
         let input = "d,e,\",\"";
 
-        assert!(!has_open_quotes(input, ','));
+        assert!(!has_open_quotes(input, ","));
     }
 
     #[test]
     fn test_just_delimiter_open() {
         let input = "a,,\",";
 
-        assert!(has_open_quotes(input, ','));
+        assert!(has_open_quotes(input, ","));
     }
 }
 
@@ -241,7 +621,7 @@ mod parse_rows_tests {
     #[test]
     fn test_simple() {
         let input = "test,row\nnext,row\n".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -257,7 +637,7 @@ mod parse_rows_tests {
     #[test]
     fn test_strange_crlf() {
         let input = "test,\"row\"\r\r\nnext,row\r\r\n".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -273,7 +653,7 @@ mod parse_rows_tests {
     #[test]
     fn test_no_trailing_newline() {
         let input = "test,row\nnext,row".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -289,7 +669,7 @@ mod parse_rows_tests {
     #[test]
     fn test_quoted_newline() {
         let input = "\"test\n\",\"broken\ncolumn\",\"another\ncolumn\"\nnext,row".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -309,7 +689,7 @@ mod parse_rows_tests {
     #[test]
     fn test_quoted_quote() {
         let input = "test,\"\"\"row\"\"\"\nnext,row".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -325,7 +705,7 @@ mod parse_rows_tests {
     #[test]
     fn test_incorrect_quoted_quote() {
         let input = "test,\"\"row\"\"\n\"\"next\"\",row".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -341,7 +721,7 @@ mod parse_rows_tests {
     #[test]
     fn test_quoted_delimiter() {
         let input = "test,\"row,\"\nnext,row".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -357,7 +737,7 @@ mod parse_rows_tests {
     #[test]
     fn test_blank_row() {
         let input = "test,row\n\nnext,row".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -374,7 +754,7 @@ mod parse_rows_tests {
     #[test]
     fn test_empty_row() {
         let input = "test,row\n,\nnext,row".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -391,7 +771,7 @@ mod parse_rows_tests {
     #[test]
     fn test_dangling_quote() {
         let input = "test,row\n\"next,row".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -407,7 +787,7 @@ mod parse_rows_tests {
     #[test]
     fn test_unescaped_cell_quote_does_not_consume_rest_of_rows() {
         let input = "test,row\n\"ne\"xt\",row\nfinal,row".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -424,7 +804,7 @@ mod parse_rows_tests {
     #[test]
     fn test_newline_and_quotes() {
         let input = "A,B,C\nA,X,\"\"\"28-35, GIDC Industrial\nEstate, Nan\"\nY,Z,Q\nX,\"\"\"\nVillege Poicha\"\"\",Q\nX,\"\"\"Villege Poicha\"\"\n\",Q\nN,Y,C".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -456,7 +836,7 @@ mod parse_rows_tests {
     #[test]
     fn test_quotes_just_delimiter() {
         let input = "c1,c2,c3\nd,e,\",\"\na,b,c\nd,e,\",\"".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -474,7 +854,7 @@ mod parse_rows_tests {
     #[test]
     fn test_false_positive_delimiter_removal() {
         let input = "a,b,c\n\"lll\",\"\"\"\",\"\"\",\n\"".as_bytes();
-        let result = CSVReader::new(input, ',')
+        let result = CSVReader::new(input, ",")
             .into_lines()
             .collect::<Result<Vec<_>, _>>();
 
@@ -492,6 +872,71 @@ mod parse_rows_tests {
     }
 }
 
+#[cfg(test)]
+mod remove_whitespace_around_quotes_tests {
+    use super::*;
+
+    #[test]
+    fn test_around_quoted_cells() {
+        let input = " \"a\" ,\t\"b\"\t";
+
+        assert_eq!(remove_whitespace_around_quotes(input, ","), "\"a\",\"b\"")
+    }
+
+    #[test]
+    fn test_inside_and_unquoted_untouched() {
+        let input = " a , \" b \" c";
+
+        assert_eq!(remove_whitespace_around_quotes(input, ","), " a ,\" b \" c")
+    }
+}
+
+#[cfg(test)]
+mod trim_whitespace_outside_quotes_tests {
+    use super::*;
+
+    fn options() -> ParseOptions {
+        ParseOptions {
+            trim_whitespace_outside_quotes: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_trimmed() {
+        let input = "a, \"b\" \n \"c\" ,d".as_bytes();
+        let result = CSVReader::new(input, ",")
+            .with_options(options())
+            .into_lines()
+            .collect::<Result<Vec<_>, _>>();
+
+        assert_eq!(
+            result.unwrap(),
+            vec![
+                vec![Cell::new("a"), Cell::new("\"b\"")],
+                vec![Cell::new("\"c\""), Cell::new("d")],
+            ]
+        )
+    }
+
+    #[test]
+    fn test_quoted_newline_after_whitespace() {
+        let input = "a, \"b\nc\"\nd,e".as_bytes();
+        let result = CSVReader::new(input, ",")
+            .with_options(options())
+            .into_lines()
+            .collect::<Result<Vec<_>, _>>();
+
+        assert_eq!(
+            result.unwrap(),
+            vec![
+                vec![Cell::new("a"), Cell::new("\"b\nc\"")],
+                vec![Cell::new("d"), Cell::new("e")],
+            ]
+        )
+    }
+}
+
 #[cfg(test)]
 mod parse_cells_tests {
     use super::*;
@@ -500,7 +945,7 @@ mod parse_cells_tests {
     fn test_simple() {
         let input = "test,row";
         assert_eq!(
-            parse_cells(input, ',').unwrap(),
+            parse_cells(input, ",").unwrap(),
             vec![Cell::new("test"), Cell::new("row")]
         )
     }
@@ -510,7 +955,7 @@ mod parse_cells_tests {
         let input = "test,\"row\n\"";
 
         assert_eq!(
-            parse_cells(input, ',').unwrap(),
+            parse_cells(input, ",").unwrap(),
             vec![Cell::new("test"), Cell::new("\"row\n\"")]
         )
     }
@@ -520,7 +965,7 @@ mod parse_cells_tests {
         let input = "test,\"\"\"row\"\"\"";
 
         assert_eq!(
-            parse_cells(input, ',').unwrap(),
+            parse_cells(input, ",").unwrap(),
             vec![Cell::new("test"), Cell::new("\"\"\"row\"\"\"")]
         )
     }
@@ -530,13 +975,164 @@ mod parse_cells_tests {
         let input = "test,\"row,\"";
 
         assert_eq!(
-            parse_cells(input, ',').unwrap(),
+            parse_cells(input, ",").unwrap(),
             vec![Cell::new("test"), Cell::new("\"row,\"")]
         )
     }
 
     #[test]
     fn test_empty() {
-        assert_eq!(parse_cells("", ',').unwrap(), vec![])
+        assert_eq!(parse_cells("", ",").unwrap(), vec![])
+    }
+}
+
+#[cfg(test)]
+mod escape_tests {
+    use super::*;
+
+    fn parse(input: &str, quoting: bool) -> Vec<Vec<Cell>> {
+        CSVReader::new(input.as_bytes(), "\t")
+            .with_options(ParseOptions {
+                quoting,
+                escape: Some('\\'),
+                ..Default::default()
+            })
+            .into_lines()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_escaped_delimiter_and_newline() {
+        assert_eq!(
+            parse("a\\\tb\tc\\\nd\ne\\\\\tf\n", false),
+            vec![
+                vec![Cell::new("a\\\tb"), Cell::new("c\\\nd")],
+                vec![Cell::new("e\\\\"), Cell::new("f")],
+            ]
+        )
+    }
+
+    #[test]
+    fn test_without_quoting() {
+        assert_eq!(
+            parse("\"a\t\"b\n", false),
+            vec![vec![Cell::new("\"a"), Cell::new("\"b")]]
+        )
+    }
+
+    #[test]
+    fn test_escaped_quote() {
+        assert_eq!(
+            parse("\"a\\\"\tb\"\tc\n", true),
+            vec![vec![Cell::new("\"a\\\"\tb\""), Cell::new("c")]]
+        )
+    }
+}
+
+#[cfg(test)]
+mod string_delimiter_tests {
+    use super::*;
+
+    fn parse(input: &str, delimiter: &str, options: ParseOptions) -> Vec<Vec<Cell>> {
+        CSVReader::new(input.as_bytes(), delimiter)
+            .with_options(options)
+            .into_lines()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_multi_character_delimiter() {
+        assert_eq!(
+            parse("a||b|c||\n", "||", ParseOptions::default()),
+            vec![vec![Cell::new("a"), Cell::new("b|c"), Cell::new("")]]
+        )
+    }
+
+    #[test]
+    fn test_quoted_multi_character_delimiter() {
+        assert_eq!(
+            parse(
+                "\"a~|~\nb\"~|~c\n\"d\"~|~\"\"\n",
+                "~|~",
+                ParseOptions::default()
+            ),
+            vec![
+                vec![Cell::new("\"a~|~\nb\""), Cell::new("c")],
+                vec![Cell::new("\"d\""), Cell::new("\"\"")],
+            ]
+        )
+    }
+
+    #[test]
+    fn test_non_ascii_delimiter() {
+        assert_eq!(
+            parse("ä§\"b§\"§c\n", "§", ParseOptions::default()),
+            vec![vec![Cell::new("ä"), Cell::new("\"b§\""), Cell::new("c")]]
+        )
+    }
+
+    #[test]
+    fn test_whitespace_around_quotes() {
+        let options = ParseOptions {
+            trim_whitespace_outside_quotes: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            parse(" \"a\n\" || \"b\" ||c\n", "||", options),
+            vec![vec![
+                Cell::new("\"a\n\""),
+                Cell::new("\"b\""),
+                Cell::new("c")
+            ]]
+        )
+    }
+
+    #[test]
+    fn test_empty_delimiter() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+
+        assert_eq!(
+            parse_file(file.path(), "", "utf-8")
+                .err()
+                .unwrap()
+                .to_string(),
+            "delimiter can't be empty"
+        )
+    }
+}
+
+#[cfg(test)]
+mod skip_tests {
+    use crate::row_set::RowSet;
+
+    use super::*;
+
+    #[test]
+    fn test_comment_inside_quoted_cell() {
+        let options = ParseOptions {
+            comment_prefix: Some("#".into()),
+            ..Default::default()
+        };
+        let mut rows = CSVReader::new("#a\n\"b\n#c\",d\n#e\nf\n".as_bytes(), ",")
+            .with_options(options)
+            .into_lines()
+            .recording_skipped_lines(SkippedLines::default());
+
+        let result: Vec<_> = rows.by_ref().collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(
+            result,
+            vec![
+                vec![Cell::new("\"b\n#c\""), Cell::new("d")],
+                vec![Cell::new("f")]
+            ]
+        );
+        assert_eq!(
+            rows.into_skipped_lines().unwrap().comments,
+            RowSet::from([0, 3])
+        );
     }
 }
@@ -0,0 +1,160 @@
+use std::fmt::Display;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// How to pick the rows to check when only checking a sample of the file.
+/// The whole file is still parsed, so rows spanning multiple lines (quoted newlines) are sampled as a whole.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sample {
+    /// Check every Nth row
+    Stride(usize),
+    /// Check each row with a probability of `fraction`, the same seed always picks the same rows
+    Random { fraction: f64, seed: Option<u64> },
+}
+
+impl Sample {
+    pub(crate) fn sampler(&self) -> Sampler {
+        match self {
+            Self::Stride(stride) => Sampler::Stride((*stride).max(1)),
+            Self::Random { fraction, seed } => Sampler::Random {
+                fraction: *fraction,
+                rng: match seed {
+                    Some(seed) => Box::new(StdRng::seed_from_u64(*seed)),
+                    None => Box::new(StdRng::from_entropy()),
+                },
+            },
+        }
+    }
+}
+
+pub(crate) enum Sampler {
+    Stride(usize),
+    Random { fraction: f64, rng: Box<StdRng> },
+}
+
+impl Sampler {
+    /// Whether the row should be checked
+    pub(crate) fn includes(&mut self, row_number: usize) -> bool {
+        match self {
+            Self::Stride(stride) => row_number.is_multiple_of(*stride),
+            Self::Random { fraction, rng } => rng.gen_bool(*fraction),
+        }
+    }
+}
+
+/// Why only part of the file was checked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartialReason {
+    /// The check stopped after finding the maximum number of issues
+    IssueLimit,
+    /// The check stopped after the maximum number of rows
+    RowLimit,
+    /// Only a sample of the rows was checked
+    Sample,
+}
+
+impl PartialReason {
+    /// Short identifier of the reason
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::IssueLimit => "issue_limit",
+            Self::RowLimit => "row_limit",
+            Self::Sample => "sample",
+        }
+    }
+}
+
+/// Details of a check that only covered part of the file
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialCheck {
+    pub reason: PartialReason,
+    /// Number of rows that were checked
+    pub rows_checked: usize,
+    /// Number of rows that were read from the file, which is all of them when sampling
+    pub rows_read: usize,
+}
+
+impl PartialCheck {
+    /// Estimates the count of an issue in the whole file from the count in the checked rows.
+    /// Only possible for samples, as the other checks stop before reading the whole file.
+    pub fn extrapolate(&self, count: usize) -> Option<usize> {
+        if self.reason != PartialReason::Sample || self.rows_checked == 0 {
+            return None;
+        }
+
+        Some((count as f64 * self.rows_read as f64 / self.rows_checked as f64).round() as usize)
+    }
+}
+
+impl Display for PartialCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.reason {
+            PartialReason::IssueLimit => write!(
+                f,
+                "Checking stopped after reaching the maximum number of issues, only the first {} rows were checked.",
+                self.rows_checked
+            ),
+            PartialReason::RowLimit => write!(
+                f,
+                "Only the first {} rows were checked, the rest of the file was not read.",
+                self.rows_checked
+            ),
+            PartialReason::Sample => write!(
+                f,
+                "Only a sample of {} out of {} rows was checked, multiply the counts below by about {:.1} to estimate them for the whole file.",
+                self.rows_checked,
+                self.rows_read,
+                self.rows_read as f64 / self.rows_checked.max(1) as f64
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stride() {
+        let mut sampler = Sample::Stride(3).sampler();
+
+        let sampled: Vec<_> = (0..10).filter(|&i| sampler.includes(i)).collect();
+
+        assert_eq!(sampled, vec![0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn test_random_seeded() {
+        let sample = Sample::Random {
+            fraction: 0.5,
+            seed: Some(42),
+        };
+        let mut first = sample.sampler();
+        let mut second = sample.sampler();
+
+        let sampled: Vec<_> = (0..1000).filter(|&i| first.includes(i)).collect();
+
+        assert_eq!(
+            sampled,
+            (0..1000)
+                .filter(|&i| second.includes(i))
+                .collect::<Vec<_>>()
+        );
+        assert!((400..600).contains(&sampled.len()));
+    }
+
+    #[test]
+    fn test_extrapolate() {
+        let mut partial = PartialCheck {
+            reason: PartialReason::Sample,
+            rows_checked: 100,
+            rows_read: 1000,
+        };
+
+        assert_eq!(partial.extrapolate(3), Some(30));
+
+        partial.reason = PartialReason::RowLimit;
+
+        assert_eq!(partial.extrapolate(3), None);
+    }
+}
@@ -0,0 +1,126 @@
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+
+use crate::{
+    error::CSVError,
+    parser::{read_rows, Delimiter, ParseOptions},
+    row_set::RowSet,
+};
+
+/// Number of rows looked at to detect a preamble
+const DETECTION_ROWS: usize = 100;
+
+/// Lines of the file that were skipped rather than read as rows
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SkippedLines {
+    /// The lines before the header, as they are in the file
+    pub preamble: Vec<String>,
+    /// Numbers of the lines of the file (counting from 0, the preamble included) that were skipped as comments
+    pub comments: RowSet,
+}
+
+impl SkippedLines {
+    pub fn is_empty(&self) -> bool {
+        self.preamble.is_empty() && self.comments.is_empty()
+    }
+}
+
+impl Display for SkippedLines {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.preamble.is_empty() {
+            writeln!(
+                f,
+                "Skipped {} lines before the header, starting with {:?}.",
+                self.preamble.len(),
+                self.preamble[0]
+            )?;
+        }
+
+        if !self.comments.is_empty() {
+            writeln!(f, "Skipped {} comment lines.", self.comments.len())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Number of lines before the header of the file at `path`, the header being the first row with the number of
+/// columns most of the first rows have. Ties go to the higher number of columns, as a preamble is usually a few
+/// lines of text. Comments are skipped according to `options`, and are part of the preamble if they are before the
+/// header.
+pub fn detect_preamble(
+    path: impl AsRef<Path>,
+    delimiter: impl Into<Delimiter>,
+    encoding: &str,
+    options: &ParseOptions,
+) -> Result<usize, CSVError> {
+    let options = ParseOptions {
+        skip_lines: 0,
+        ..options.clone()
+    };
+    let mut rows = read_rows(path, delimiter.into().as_str(), encoding, &options)?;
+
+    // (line the row starts at, number of columns) of the first non-blank rows
+    let mut column_counts = Vec::new();
+    for _ in 0..DETECTION_ROWS {
+        let start = rows.lines_read();
+        match rows.next().transpose()? {
+            Some(row) if row.is_empty() => (),
+            Some(row) => column_counts.push((start, row.len())),
+            None => break,
+        }
+    }
+
+    let mut frequencies = BTreeMap::new();
+    for &(_, count) in &column_counts {
+        *frequencies.entry(count).or_insert(0) += 1;
+    }
+    let Some((&stable_count, _)) = frequencies
+        .iter()
+        .max_by_key(|&(&count, &frequency)| (frequency, count))
+    else {
+        return Ok(0);
+    };
+
+    Ok(column_counts
+        .iter()
+        .find(|&&(_, count)| count == stable_count)
+        .map_or(0, |&(start, _)| start))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::temp_file;
+
+    use super::*;
+
+    fn detect(content: &str, options: &ParseOptions) -> usize {
+        let file = temp_file(content);
+
+        detect_preamble(file.path(), ",", "utf-8", options).unwrap()
+    }
+
+    #[test]
+    fn test_detect_preamble() {
+        let content = "Sales report\nGenerated 1 Jan, 2024\n\na,b,c\n1,2,3\n4,5\n6,7,8\n";
+
+        assert_eq!(detect(content, &ParseOptions::default()), 3);
+    }
+
+    #[test]
+    fn test_detect_no_preamble() {
+        let content = "a,b,c\n1,2,3,4\n5,6,7\n\"x\ny\",8,9\n";
+
+        assert_eq!(detect(content, &ParseOptions::default()), 0);
+        assert_eq!(detect("", &ParseOptions::default()), 0);
+    }
+
+    #[test]
+    fn test_detect_preamble_with_comments() {
+        let options = ParseOptions {
+            comment_prefix: Some("#".into()),
+            ..Default::default()
+        };
+
+        assert_eq!(detect("# a,b,c,d\ntitle\n# x\na,b\n1,2\n", &options), 2);
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::error::CSVError;
+
+/// How far a running check has got
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of bytes of the file read so far
+    pub bytes_read: u64,
+    /// Size of the file in bytes
+    pub total_bytes: u64,
+    /// Number of rows read so far, including rows skipped when sampling
+    pub rows_processed: usize,
+    /// Number of rows with issues found so far
+    pub issues_found: usize,
+}
+
+/// Cancels a running check when [`cancel`](Self::cancel) is called on any of its clones, for example from another
+/// thread. The check then stops with [`CSVError::Cancelled`].
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Function called with the progress of a check
+pub type ProgressCallback<'a> = Box<dyn FnMut(&Progress) + 'a>;
+
+/// Hooks to follow the progress of a check and to cancel it
+pub struct CheckHooks<'a> {
+    /// Called at most once every `progress_interval` while checking, and once more when all the rows were checked
+    pub on_progress: Option<ProgressCallback<'a>>,
+    pub progress_interval: Duration,
+    /// Polled before checking each row, and before writing each row of the outputs
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl Default for CheckHooks<'_> {
+    fn default() -> Self {
+        Self {
+            on_progress: None,
+            progress_interval: Duration::from_millis(100),
+            cancellation: None,
+        }
+    }
+}
+
+/// Keeps track of the progress of a check and calls the hooks
+pub(crate) struct ProgressTracker<'a> {
+    hooks: CheckHooks<'a>,
+    bytes_read: Arc<AtomicU64>,
+    total_bytes: u64,
+    last_report: Instant,
+    /// Progress given in the last update, reported again while the outputs are written
+    rows_processed: usize,
+    issues_found: usize,
+}
+
+impl<'a> ProgressTracker<'a> {
+    pub(crate) fn new(hooks: CheckHooks<'a>, total_bytes: u64) -> Self {
+        Self {
+            hooks,
+            bytes_read: Default::default(),
+            total_bytes,
+            last_report: Instant::now(),
+            rows_processed: 0,
+            issues_found: 0,
+        }
+    }
+
+    /// Counter to be incremented with the bytes read from the file
+    pub(crate) fn byte_counter(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.bytes_read)
+    }
+
+    /// Stops the check if it was cancelled, and reports the progress if it wasn't reported recently
+    pub(crate) fn update(
+        &mut self,
+        rows_processed: usize,
+        issues_found: usize,
+    ) -> Result<(), CSVError> {
+        if self
+            .hooks
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(CSVError::Cancelled);
+        }
+
+        if self.last_report.elapsed() >= self.hooks.progress_interval {
+            self.report(rows_processed, issues_found);
+        }
+
+        Ok(())
+    }
+
+    /// Reports the final progress
+    pub(crate) fn finish(&mut self, rows_processed: usize, issues_found: usize) {
+        self.report(rows_processed, issues_found)
+    }
+
+    /// Stops writing the outputs if the check was cancelled. The last progress is reported again if it wasn't
+    /// reported recently, as the hooks can cancel the check when called.
+    pub(crate) fn poll(&mut self) -> Result<(), CSVError> {
+        self.update(self.rows_processed, self.issues_found)
+    }
+
+    /// Iterates over `items` until the check is cancelled, [`poll`](Self::poll) then returning the cancellation
+    pub(crate) fn until_cancelled<I: Iterator>(&mut self, items: I) -> UntilCancelled<'_, 'a, I> {
+        UntilCancelled {
+            items,
+            progress: self,
+        }
+    }
+
+    fn report(&mut self, rows_processed: usize, issues_found: usize) {
+        self.rows_processed = rows_processed;
+        self.issues_found = issues_found;
+        if let Some(on_progress) = &mut self.hooks.on_progress {
+            on_progress(&Progress {
+                bytes_read: self.bytes_read.load(Ordering::Relaxed),
+                total_bytes: self.total_bytes,
+                rows_processed,
+                issues_found,
+            });
+        }
+        self.last_report = Instant::now();
+    }
+}
+
+/// Iterator that stops early if the check is cancelled
+pub(crate) struct UntilCancelled<'p, 'a, I> {
+    items: I,
+    progress: &'p mut ProgressTracker<'a>,
+}
+
+impl<I: Iterator> Iterator for UntilCancelled<'_, '_, I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.progress.poll().ok()?;
+        self.items.next()
+    }
+}
+
+impl Default for ProgressTracker<'_> {
+    fn default() -> Self {
+        Self::new(CheckHooks::default(), 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancellation() {
+        let token = CancellationToken::new();
+        let mut tracker = ProgressTracker::new(
+            CheckHooks {
+                cancellation: Some(token.clone()),
+                ..Default::default()
+            },
+            0,
+        );
+
+        assert!(tracker.update(1, 0).is_ok());
+
+        token.cancel();
+
+        assert!(matches!(tracker.update(2, 0), Err(CSVError::Cancelled)));
+    }
+
+    #[test]
+    fn test_until_cancelled() {
+        let token = CancellationToken::new();
+        let mut tracker = ProgressTracker::new(
+            CheckHooks {
+                cancellation: Some(token.clone()),
+                ..Default::default()
+            },
+            0,
+        );
+
+        let mut rows = tracker.until_cancelled(0..10);
+        assert_eq!(rows.next(), Some(0));
+        token.cancel();
+        assert_eq!(rows.next(), None);
+
+        assert!(matches!(tracker.poll(), Err(CSVError::Cancelled)));
+    }
+
+    #[test]
+    fn test_report_interval() {
+        let mut reports = Vec::new();
+        let mut tracker = ProgressTracker::new(
+            CheckHooks {
+                on_progress: Some(Box::new(|p: &Progress| reports.push(p.rows_processed))),
+                progress_interval: Duration::from_secs(3600),
+                ..Default::default()
+            },
+            10,
+        );
+
+        tracker.update(1, 0).unwrap();
+        tracker.update(2, 1).unwrap();
+        tracker.finish(3, 1);
+        drop(tracker);
+
+        assert_eq!(reports, vec![3]);
+    }
+}
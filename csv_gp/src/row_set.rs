@@ -0,0 +1,190 @@
+use std::{collections::HashSet, fmt::Debug};
+
+use roaring::RoaringTreemap;
+
+/// A compressed set of line numbers, taking a fraction of the memory of a `HashSet<usize>` or `Vec<usize>`
+/// when the file has a lot of rows.
+///
+/// The set can be limited to only keep a number of example line numbers, in which case it keeps counting the
+/// line numbers past the limit without storing them, and `len` is the total count.
+#[derive(Clone, Default, PartialEq)]
+pub struct RowSet {
+    rows: RoaringTreemap,
+    limit: Option<usize>,
+    /// Number of line numbers added past the limit
+    overflow: usize,
+    /// Last line number added past the limit, so adding the same one twice in a row is only counted once
+    last_overflow: Option<usize>,
+}
+
+impl RowSet {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// A set only keeping the first `limit` line numbers added to it, while still counting all of them
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a line number to the set, returning whether it wasn't already present.
+    /// Past the limit, line numbers are assumed to be added in ascending order.
+    pub fn insert(&mut self, row: usize) -> bool {
+        if self
+            .limit
+            .is_none_or(|limit| (self.rows.len() as usize) < limit)
+        {
+            return self.rows.insert(row as u64);
+        }
+
+        if self.last_overflow == Some(row) || self.rows.contains(row as u64) {
+            return false;
+        }
+
+        self.overflow += 1;
+        self.last_overflow = Some(row);
+        true
+    }
+
+    /// Removes a line number from the set, returning whether it was present
+    pub fn remove(&mut self, row: usize) -> bool {
+        self.rows.remove(row as u64)
+    }
+
+    /// Whether the line number is in the set, only the kept line numbers are known if the set is truncated
+    pub fn contains(&self, row: usize) -> bool {
+        self.rows.contains(row as u64)
+    }
+
+    /// Number of line numbers added to the set, including any past the limit
+    pub fn len(&self) -> usize {
+        self.rows.len() as usize + self.overflow
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of line numbers kept in the set, which is less than `len` if the set is truncated
+    pub fn kept_len(&self) -> usize {
+        self.rows.len() as usize
+    }
+
+    /// Whether line numbers past the limit were left out of the set
+    pub fn is_truncated(&self) -> bool {
+        self.overflow > 0
+    }
+
+    /// The `n`th smallest line number kept in the set
+    pub fn select(&self, n: usize) -> Option<usize> {
+        self.rows.select(n as u64).map(|row| row as usize)
+    }
+
+    /// Iterates over the line numbers kept in the set in ascending order
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        self.rows.iter().map(|row| row as usize)
+    }
+
+    /// Iterates over the line numbers kept in the set greater than or equal to `start` in ascending order
+    pub fn iter_from(&self, start: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut iter = self.rows.iter();
+        iter.advance_to(start as u64);
+        iter.map(|row| row as usize)
+    }
+}
+
+impl Debug for RowSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<usize> for RowSet {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        Self {
+            rows: iter.into_iter().map(|row| row as u64).collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl Extend<usize> for RowSet {
+    fn extend<T: IntoIterator<Item = usize>>(&mut self, iter: T) {
+        iter.into_iter().for_each(|row| {
+            self.insert(row);
+        })
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for RowSet {
+    fn from(rows: [usize; N]) -> Self {
+        Self::from_iter(rows)
+    }
+}
+
+/// Compares the kept line numbers to a list of line numbers in ascending order
+impl PartialEq<Vec<usize>> for RowSet {
+    fn eq(&self, other: &Vec<usize>) -> bool {
+        self.iter().eq(other.iter().copied())
+    }
+}
+
+/// Compares the kept line numbers to a set of line numbers
+impl PartialEq<HashSet<usize>> for RowSet {
+    fn eq(&self, other: &HashSet<usize>) -> bool {
+        self.kept_len() == other.len() && other.iter().all(|&row| self.contains(row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_membership() {
+        let mut rows = RowSet::new();
+
+        assert!(rows.insert(5));
+        assert!(!rows.insert(5));
+        rows.insert(1);
+
+        assert!(rows.contains(1));
+        assert!(!rows.contains(2));
+        assert_eq!(rows.len(), 2);
+        assert!(rows.remove(1));
+        assert_eq!(rows, RowSet::from([5]));
+        assert_eq!(rows, vec![5]);
+        assert_eq!(rows, HashSet::from([5]));
+        assert_ne!(rows, vec![1, 5]);
+    }
+
+    #[test]
+    fn test_iteration_is_sorted() {
+        let rows = RowSet::from([10_000_000_000, 3, 1]);
+
+        assert_eq!(rows.iter().collect::<Vec<_>>(), vec![1, 3, 10_000_000_000]);
+        assert_eq!(
+            rows.iter_from(2).collect::<Vec<_>>(),
+            vec![3, 10_000_000_000]
+        );
+        assert_eq!(rows.select(1), Some(3));
+        assert_eq!(rows.select(3), None);
+        assert_eq!(format!("{rows:?}"), "{1, 3, 10000000000}");
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut rows = RowSet::with_limit(2);
+
+        rows.extend([1, 3, 3, 5, 5, 8]);
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows.kept_len(), 2);
+        assert!(rows.is_truncated());
+        assert_eq!(rows.iter().collect::<Vec<_>>(), vec![1, 3]);
+        assert!(!rows.contains(5));
+    }
+}
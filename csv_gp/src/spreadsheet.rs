@@ -0,0 +1,354 @@
+use std::{fs, io, path::Path, sync::atomic::Ordering};
+
+use calamine::{open_workbook_auto, Data, Reader};
+
+use crate::{
+    cell::Cell,
+    checker::{check_rows, CheckOptions},
+    csv_details::CSVDetails,
+    duplicates::confirm_duplicates,
+    error::{CSVError, InvalidOption},
+    jsonl::write_json_lines,
+    parser::ParseOptions,
+    progress::{CheckHooks, ProgressTracker},
+    valid_file::{filter_valid_rows, write_normalised_csv, OutputDialect, OutputFormat},
+};
+
+/// Extensions of the spreadsheet files that can be checked
+pub const EXTENSIONS: &[&str] = &["xlsx", "xlsm", "xlsb", "xls", "ods"];
+
+/// Delimiter the rows are converted to CSV with, unless the output dialect has its own. The cells of a sheet aren't
+/// separated by it, so it isn't checked for.
+const DELIMITER: &str = ",";
+
+/// Whether the file at `path` is a spreadsheet according to its extension
+pub fn is_spreadsheet(path: impl AsRef<Path>) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+/// Text of a spreadsheet cell as it would be written in a CSV, dates in ISO 8601
+fn cell_text(data: &Data) -> String {
+    match data {
+        Data::DateTime(date_time) => match date_time.as_datetime() {
+            Some(date_time) => {
+                let text = date_time.to_string();
+                match text.strip_suffix(" 00:00:00") {
+                    Some(date) => date.to_string(),
+                    None => text,
+                }
+            }
+            None => date_time.to_string(),
+        },
+        data => data.to_string(),
+    }
+}
+
+/// Reads the rows of `sheet` (the first sheet if not given) of the spreadsheet at `path`.
+/// The sheet is a grid, so each row ends at its last non-empty cell, and rows with only empty cells are blank.
+pub fn read_sheet(path: impl AsRef<Path>, sheet: Option<&str>) -> Result<Vec<Vec<Cell>>, CSVError> {
+    let mut workbook = open_workbook_auto(path)?;
+
+    let range = match sheet {
+        Some(sheet) => {
+            if !workbook.sheet_names().iter().any(|name| name == sheet) {
+                return Err(InvalidOption::Value {
+                    option: "sheet",
+                    value: sheet.into(),
+                }
+                .into());
+            }
+            workbook.worksheet_range(sheet)?
+        }
+        None => match workbook.worksheet_range_at(0) {
+            Some(range) => range?,
+            None => return Ok(Vec::new()),
+        },
+    };
+
+    Ok(sheet_rows(range.rows()))
+}
+
+/// Rows of a sheet, see [`read_sheet`]
+fn sheet_rows<'a>(rows: impl Iterator<Item = &'a [Data]>) -> Vec<Vec<Cell>> {
+    rows.map(|row| {
+        let used = row
+            .iter()
+            .rposition(|data| *data != Data::Empty)
+            .map_or(0, |last| last + 1);
+
+        row[..used]
+            .iter()
+            .map(|data| Cell::new(cell_text(data)))
+            .collect()
+    })
+    .collect()
+}
+
+/// The options with the parsing settings of a sheet: its cells can't be quoted, so quotes are just characters
+fn sheet_options(options: &CheckOptions) -> CheckOptions {
+    CheckOptions {
+        parse: ParseOptions {
+            quoting: false,
+            escape: None,
+            ..options.parse.clone()
+        },
+        ..options.clone()
+    }
+}
+
+/// Checks that the options can be used for a spreadsheet, which isn't parsed
+fn validate(options: &CheckOptions) -> Result<(), InvalidOption> {
+    let other = "spreadsheet input";
+
+    if options.strict {
+        return Err(InvalidOption::Conflict {
+            option: "strict mode",
+            other,
+        });
+    }
+    if options.parse.fixed_width.is_some() {
+        return Err(InvalidOption::Conflict {
+            option: "fixed width",
+            other,
+        });
+    }
+    if options.detect_preamble
+        || options.parse.skip_lines > 0
+        || options.parse.comment_prefix.is_some()
+    {
+        return Err(InvalidOption::Conflict {
+            option: "skipping lines",
+            other,
+        });
+    }
+
+    #[cfg(feature = "parquet")]
+    if matches!(options.valid_file.format, OutputFormat::Parquet(_)) {
+        return Err(InvalidOption::Conflict {
+            option: "parquet output",
+            other,
+        });
+    }
+
+    Ok(())
+}
+
+/// Check `sheet` (the first sheet if not given) of the spreadsheet at `path`, with the same checks as a CSV file.
+/// If `valid_rows_output_path` is passed, the valid rows are written to it as CSV, or in the format of the options.
+/// The parsing options other than `trim_whitespace_outside_quotes` don't apply, and the cells are never quoted.
+pub fn check_sheet(
+    path: impl AsRef<Path>,
+    sheet: Option<&str>,
+    valid_rows_output_path: Option<impl AsRef<Path>>,
+    options: &CheckOptions,
+) -> Result<CSVDetails, CSVError> {
+    check_sheet_with_hooks(
+        path,
+        sheet,
+        valid_rows_output_path,
+        options,
+        CheckHooks::default(),
+    )
+}
+
+/// Same as [`check_sheet`], reporting the progress and polling for cancellation through `hooks`
+pub fn check_sheet_with_hooks(
+    path: impl AsRef<Path>,
+    sheet: Option<&str>,
+    valid_rows_output_path: Option<impl AsRef<Path>>,
+    options: &CheckOptions,
+    hooks: CheckHooks,
+) -> Result<CSVDetails, CSVError> {
+    options.validate(valid_rows_output_path.is_some())?;
+    validate(options)?;
+
+    let options = sheet_options(options);
+
+    let total_bytes = fs::metadata(&path)?.len();
+    let mut progress = ProgressTracker::new(hooks, total_bytes);
+
+    // the whole sheet is read at once
+    let rows = read_sheet(&path, sheet)?;
+    progress
+        .byte_counter()
+        .store(total_bytes, Ordering::Relaxed);
+
+    let mut csv_details = check_rows(
+        rows.iter().cloned().map(Ok),
+        None,
+        &options,
+        valid_rows_output_path.is_some() || options.valid_file.rejected_rows_path.is_some(),
+        &mut progress,
+    )?;
+    confirm_duplicates(
+        &mut csv_details,
+        options.duplicate_key_columns.as_deref(),
+        rows.iter().cloned().map(Ok),
+    )?;
+
+    if let Some(output_path) = valid_rows_output_path {
+        create_parent_dir(&output_path)?;
+        let valid_rows = filter_valid_rows(
+            rows.iter().cloned().map(Ok),
+            &csv_details,
+            &options.parse,
+            &options.valid_file,
+        )?;
+        let valid_rows = progress.until_cancelled(valid_rows);
+
+        match &options.valid_file.format {
+            OutputFormat::NormalisedCsv(dialect) => {
+                write_normalised_csv(valid_rows, DELIMITER, &options.parse, output_path, dialect)?
+            }
+            OutputFormat::JsonLines(json_options) => {
+                let header = match (json_options.header, rows.first()) {
+                    (true, Some(header)) => header.iter().map(Cell::to_string).collect(),
+                    _ => Vec::new(),
+                };
+                // every row of a sheet is a single line
+                write_json_lines(
+                    valid_rows.map(|row| row.map(|(i, row)| (i, i, row))),
+                    &header,
+                    output_path,
+                    &options.parse,
+                    json_options,
+                )?
+            }
+            _ => write_normalised_csv(
+                valid_rows,
+                DELIMITER,
+                &options.parse,
+                output_path,
+                &OutputDialect::default(),
+            )?,
+        }
+        progress.poll()?;
+    }
+
+    if let Some(rejected_rows_path) = &options.valid_file.rejected_rows_path {
+        create_parent_dir(rejected_rows_path)?;
+        let rejected = rows.into_iter().enumerate().filter(|(i, _)| {
+            !csv_details.valid_rows.contains(*i) && !csv_details.footer.rows.contains(*i)
+        });
+
+        write_normalised_csv(
+            progress.until_cancelled(rejected.map(Ok)),
+            DELIMITER,
+            &options.parse,
+            rejected_rows_path,
+            &OutputDialect::default(),
+        )?;
+        progress.poll()?;
+    }
+
+    Ok(csv_details)
+}
+
+fn create_parent_dir(path: impl AsRef<Path>) -> io::Result<()> {
+    match path.as_ref().parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use calamine::{ExcelDateTime, ExcelDateTimeType};
+
+    use crate::row_set::RowSet;
+
+    use super::*;
+
+    fn text(rows: &[Vec<Cell>]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(Cell::to_string).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_sheet_rows() {
+        let s = |s: &str| Data::String(s.into());
+        let rows = [
+            vec![s("id"), s("name"), Data::Empty],
+            vec![Data::Int(1), Data::Empty, Data::Empty],
+            vec![Data::Empty, Data::Empty, Data::Empty],
+            vec![Data::Float(2.0), s("say \"hi\""), s("extra")],
+        ];
+
+        assert_eq!(
+            text(&sheet_rows(rows.iter().map(Vec::as_slice))),
+            vec![
+                vec!["id", "name"],
+                vec!["1"],
+                vec![],
+                vec!["2", "say \"hi\"", "extra"]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sheet_skips_delimiter_and_quote_checks() {
+        let s = |s: &str| Data::String(s.into());
+        let rows = [
+            vec![s("name"), s("address")],
+            vec![s("Acme, Inc."), s("1 Main St, Springfield")],
+            vec![s("\"Dan"), s("5\" screen")],
+        ];
+        let rows = sheet_rows(rows.iter().map(Vec::as_slice));
+
+        let csv_details = check_rows(
+            rows.into_iter().map(Ok),
+            None,
+            &sheet_options(&CheckOptions::default()),
+            false,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert!(csv_details.quoted_delimiter.is_empty());
+        assert!(csv_details.quoted_quote.is_empty());
+        assert!(csv_details.incorrect_cell_quote.is_empty());
+        assert_eq!(csv_details.valid_rows, RowSet::from([0, 1, 2]));
+    }
+
+    #[test]
+    fn test_cell_text() {
+        let date = |value| {
+            Data::DateTime(ExcelDateTime::new(
+                value,
+                ExcelDateTimeType::DateTime,
+                false,
+            ))
+        };
+
+        assert_eq!(cell_text(&date(45292.0)), "2024-01-01");
+        assert_eq!(cell_text(&date(45292.5)), "2024-01-01 12:00:00");
+        assert_eq!(cell_text(&Data::Float(12.5)), "12.5");
+        assert_eq!(cell_text(&Data::Bool(true)), "true");
+        assert_eq!(cell_text(&Data::Empty), "");
+    }
+
+    #[test]
+    fn test_is_spreadsheet() {
+        assert!(is_spreadsheet("suppliers.XLSX"));
+        assert!(is_spreadsheet("dir/suppliers.ods"));
+        assert!(!is_spreadsheet("suppliers.csv"));
+        assert!(!is_spreadsheet("xlsx"));
+    }
+
+    #[test]
+    fn test_spreadsheet_conflicts() {
+        let options = CheckOptions {
+            strict: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            validate(&options).unwrap_err().to_string(),
+            "strict mode can't be used together with spreadsheet input"
+        );
+    }
+}
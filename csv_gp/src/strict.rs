@@ -0,0 +1,547 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    fmt::Display,
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use encoding_rs::Encoding;
+
+use crate::{
+    cell::Cell,
+    error::{CSVError, InvalidOption, UnknownEncoding},
+    parser::{read_rows, CSVLineIntoIter, ParseOptions},
+    row_set::RowSet,
+};
+
+/// A deviation from RFC 4180
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Violation {
+    /// A quote in a field that doesn't start with a quote, e.g. `a"b`
+    QuoteInUnquotedField,
+    /// Text between the closing quote of a field and the next delimiter or line break, e.g. `"a"b`
+    TextAfterClosingQuote,
+    /// A carriage return that isn't followed by a line feed, outside of a quoted field
+    BareCarriageReturn,
+    /// The file ends inside a quoted field, the position is the field's opening quote
+    UnterminatedQuote,
+}
+
+impl Violation {
+    /// Short identifier of the violation
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::QuoteInUnquotedField => "quote_in_unquoted_field",
+            Self::TextAfterClosingQuote => "text_after_closing_quote",
+            Self::BareCarriageReturn => "bare_carriage_return",
+            Self::UnterminatedQuote => "unterminated_quote",
+        }
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::QuoteInUnquotedField => write!(f, "quote inside an unquoted field"),
+            Self::TextAfterClosingQuote => write!(f, "text after a closing quote"),
+            Self::BareCarriageReturn => write!(f, "carriage return without a line feed"),
+            Self::UnterminatedQuote => write!(f, "quoted field that is never closed"),
+        }
+    }
+}
+
+/// Where a deviation from RFC 4180 was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Finding {
+    pub violation: Violation,
+    /// Position of the offending byte from the start of the file
+    pub byte_offset: u64,
+    /// Number of the record the byte is in, according to the strict parser
+    pub record: usize,
+}
+
+/// Results of parsing a file strictly according to RFC 4180, compared to the lenient parser
+#[derive(Debug, Clone, Default)]
+pub struct StrictCheck {
+    /// Deviations from RFC 4180 in the order they appear in the file, only the first examples in summary mode
+    pub findings: Vec<Finding>,
+    /// Number of deviations of each kind
+    pub counts: BTreeMap<Violation, usize>,
+    /// Number of records according to the strict parser
+    pub record_count: usize,
+    /// Line numbers of the rows that the lenient parser reads differently from the strict parser
+    pub differing_rows: RowSet,
+}
+
+impl StrictCheck {
+    /// Total number of deviations from RFC 4180
+    pub fn finding_count(&self) -> usize {
+        self.counts.values().sum()
+    }
+}
+
+impl Display for StrictCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.counts.is_empty() {
+            writeln!(f, "The file strictly follows RFC 4180.")?;
+        }
+
+        for (violation, count) in &self.counts {
+            let first = self
+                .findings
+                .iter()
+                .find(|finding| finding.violation == *violation)
+                .expect("the first finding of each violation is kept");
+            writeln!(
+                f,
+                "There are {count} cases of {violation}, which RFC 4180 doesn't allow, the first at byte {} (record {}).",
+                first.byte_offset, first.record
+            )?;
+        }
+
+        if !self.differing_rows.is_empty() {
+            writeln!(
+                f,
+                "There are {} rows read differently by the lenient and strict parsers.",
+                self.differing_rows.len()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Nothing read in the current field yet
+    FieldStart,
+    Unquoted,
+    Quoted,
+    /// A quote was read in a quoted field, which either closes the field or escapes the next quote
+    QuoteInQuoted,
+    /// The quoted field was closed and followed by more text
+    AfterClosingQuote,
+}
+
+/// A record read by the strict parser, with the fields' values unquoted and unescaped
+struct StrictRecord {
+    /// Number of line feeds before the record, to match it with the row of the lenient parser starting on that line
+    start_line: usize,
+    fields: Vec<Vec<u8>>,
+    findings: Vec<Finding>,
+}
+
+/// RFC 4180 state machine over the bytes of a file in an ASCII compatible encoding.
+/// Records end with either CRLF or LF, a blank line is a record without fields like in the lenient parser.
+/// Parsing carries on after a violation, keeping the offending bytes in the field.
+struct StrictParser<R> {
+    reader: R,
+    delimiter: u8,
+    /// Offset of the next byte to read
+    offset: u64,
+    /// Number of line feeds read
+    line: usize,
+    record: usize,
+    done: bool,
+}
+
+impl<R: BufRead> StrictParser<R> {
+    fn new(reader: R, delimiter: u8) -> Self {
+        Self {
+            reader,
+            delimiter,
+            offset: 0,
+            line: 0,
+            record: 0,
+            done: false,
+        }
+    }
+
+    /// Skips a UTF-8 byte order mark at the start of the file, as it isn't part of the text
+    fn skip_bom(&mut self) -> io::Result<()> {
+        if self.reader.fill_buf()?.starts_with(b"\xEF\xBB\xBF") {
+            self.reader.consume(3);
+            self.offset = 3;
+        }
+        Ok(())
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        let byte = self.reader.fill_buf()?.first().copied();
+        if byte.is_some() {
+            self.reader.consume(1);
+            self.offset += 1;
+        }
+        if byte == Some(b'\n') {
+            self.line += 1;
+        }
+        Ok(byte)
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<StrictRecord>> {
+        let mut fields = Vec::new();
+        let mut field = Vec::new();
+        let mut findings = Vec::new();
+        let mut state = State::FieldStart;
+        let mut started = false;
+        let mut quote_start = 0;
+        let mut pending_cr = None;
+
+        let start_line = self.line;
+        let record = self.record;
+        let finding = |violation, byte_offset| Finding {
+            violation,
+            byte_offset,
+            record,
+        };
+
+        loop {
+            let Some(byte) = self.next_byte()? else {
+                self.done = true;
+                if !started {
+                    return Ok(None);
+                }
+                if let Some(cr) = pending_cr {
+                    findings.push(finding(Violation::BareCarriageReturn, cr));
+                }
+                if state == State::Quoted {
+                    findings.push(finding(Violation::UnterminatedQuote, quote_start));
+                }
+                break;
+            };
+            let offset = self.offset - 1;
+            started = true;
+
+            if let Some(cr) = pending_cr.take() {
+                if byte == b'\n' {
+                    break;
+                }
+                findings.push(finding(Violation::BareCarriageReturn, cr));
+                field.push(b'\r');
+                state = match state {
+                    State::FieldStart => State::Unquoted,
+                    State::QuoteInQuoted => State::AfterClosingQuote,
+                    state => state,
+                };
+            }
+
+            match (state, byte) {
+                (State::Quoted, b'"') => state = State::QuoteInQuoted,
+                (State::Quoted, _) => field.push(byte),
+                (State::QuoteInQuoted, b'"') => {
+                    field.push(b'"');
+                    state = State::Quoted;
+                }
+                (_, b'\n') => break,
+                (_, b'\r') => pending_cr = Some(offset),
+                (_, _) if byte == self.delimiter => {
+                    fields.push(std::mem::take(&mut field));
+                    state = State::FieldStart;
+                }
+                (State::FieldStart, b'"') => {
+                    quote_start = offset;
+                    state = State::Quoted;
+                }
+                (State::FieldStart, _) => {
+                    field.push(byte);
+                    state = State::Unquoted;
+                }
+                (State::Unquoted, b'"') => {
+                    findings.push(finding(Violation::QuoteInUnquotedField, offset));
+                    field.push(byte);
+                }
+                (State::QuoteInQuoted, _) => {
+                    findings.push(finding(Violation::TextAfterClosingQuote, offset));
+                    field.push(byte);
+                    state = State::AfterClosingQuote;
+                }
+                (State::Unquoted | State::AfterClosingQuote, _) => field.push(byte),
+            }
+        }
+
+        // a blank line has no fields, while `""` is a single empty field
+        if !fields.is_empty() || !field.is_empty() || state != State::FieldStart {
+            fields.push(field);
+        }
+        self.record += 1;
+
+        Ok(Some(StrictRecord {
+            start_line,
+            fields,
+            findings,
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for StrictParser<R> {
+    type Item = io::Result<StrictRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        self.read_record().transpose()
+    }
+}
+
+/// The next row of the lenient parser, with the line it starts on
+fn next_row<B: BufRead>(rows: &mut CSVLineIntoIter<B>) -> io::Result<Option<(usize, Vec<Cell>)>> {
+    let start_line = rows.lines_read();
+    rows.next()
+        .transpose()
+        .map(|row| row.map(|row| (start_line, row)))
+}
+
+/// Parses the file at `path` strictly according to RFC 4180, reporting every deviation and the rows that the lenient
+/// parser reads differently. Only keeps the first `example_limit` findings and differing rows, if given.
+pub(crate) fn check_strict(
+    path: impl AsRef<Path>,
+    delimiter: &str,
+    encoding: &str,
+    parse_options: &ParseOptions,
+    example_limit: Option<usize>,
+) -> Result<StrictCheck, CSVError> {
+    let encoding_name = encoding;
+    let encoding = Encoding::for_label(encoding.as_bytes())
+        .ok_or_else(|| UnknownEncoding::Encoding(encoding_name.into()))?;
+    if !encoding.is_ascii_compatible() {
+        return Err(InvalidOption::Conflict {
+            option: "strict mode",
+            other: "an encoding that isn't ASCII compatible",
+        }
+        .into());
+    }
+    // a single byte of UTF-8 is an ASCII character
+    let &[delimiter_byte] = delimiter.as_bytes() else {
+        return Err(InvalidOption::Conflict {
+            option: "strict mode",
+            other: "a delimiter that isn't a single ASCII character",
+        }
+        .into());
+    };
+
+    let mut parser = StrictParser::new(BufReader::new(File::open(&path)?), delimiter_byte);
+    parser.skip_bom()?;
+    let mut lenient = read_rows(&path, delimiter, encoding_name, parse_options)?;
+
+    let mut strict_check = StrictCheck {
+        differing_rows: example_limit.map(RowSet::with_limit).unwrap_or_default(),
+        ..Default::default()
+    };
+
+    // the records and rows are matched by the line they start on, so that a row read differently doesn't shift the
+    // comparison of all the rows after it
+    let mut record = parser.next().transpose()?;
+    let mut row = next_row(&mut lenient)?;
+    // line number of the current lenient row
+    let mut i = 0;
+
+    loop {
+        let order = match (&record, &row) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(record), Some((start_line, _))) => record.start_line.cmp(start_line),
+        };
+
+        match (order, &record, &row) {
+            (Ordering::Equal, Some(record), Some((_, cells))) => {
+                let same = record.fields.len() == cells.len()
+                    && record.fields.iter().zip(cells).all(|(field, cell)| {
+                        encoding.decode_without_bom_handling(field).0 == cell.value(parse_options)
+                    });
+                if !same {
+                    strict_check.differing_rows.insert(i);
+                }
+            }
+            // the strict record starts inside the previous lenient row
+            (Ordering::Less, _, _) if i > 0 => {
+                strict_check.differing_rows.insert(i - 1);
+            }
+            // the lenient row starts inside a strict record
+            (Ordering::Greater, _, _) => {
+                strict_check.differing_rows.insert(i);
+            }
+            _ => (),
+        }
+
+        if order != Ordering::Greater {
+            let findings = record.take().map(|record| record.findings);
+            for finding in findings.into_iter().flatten() {
+                let count = strict_check.counts.entry(finding.violation).or_default();
+                *count += 1;
+                // the first finding of each violation is always kept, for the report
+                if *count == 1
+                    || example_limit.is_none_or(|limit| strict_check.findings.len() < limit)
+                {
+                    strict_check.findings.push(finding);
+                }
+            }
+            record = parser.next().transpose()?;
+        }
+        if order != Ordering::Less {
+            row = next_row(&mut lenient)?;
+            i += 1;
+        }
+    }
+
+    strict_check.record_count = parser.record;
+
+    Ok(strict_check)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    type Record = (Vec<String>, Vec<(Violation, u64)>);
+
+    fn records(content: &[u8]) -> Vec<Record> {
+        StrictParser::new(content, b',')
+            .map(|record| {
+                let record = record.unwrap();
+                (
+                    record
+                        .fields
+                        .into_iter()
+                        .map(|field| String::from_utf8(field).unwrap())
+                        .collect(),
+                    record
+                        .findings
+                        .into_iter()
+                        .map(|finding| (finding.violation, finding.byte_offset))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_valid() {
+        assert_eq!(
+            records(b"a,\"b,\r\n\"\"c\"\"\"\r\n\r\n,\"\"\n"),
+            vec![
+                (vec!["a".into(), "b,\r\n\"c\"".into()], vec![]),
+                (vec![], vec![]),
+                (vec!["".into(), "".into()], vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quote_in_unquoted_field() {
+        assert_eq!(
+            records(b"a,b\"c\n"),
+            vec![(
+                vec!["a".into(), "b\"c".into()],
+                vec![(Violation::QuoteInUnquotedField, 3)]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_text_after_closing_quote() {
+        assert_eq!(
+            records(b"x\n\"a\" ,b\n"),
+            vec![
+                (vec!["x".into()], vec![]),
+                (
+                    vec!["a ".into(), "b".into()],
+                    vec![(Violation::TextAfterClosingQuote, 5)]
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bare_carriage_return() {
+        assert_eq!(
+            records(b"a\rb,c\r"),
+            vec![(
+                vec!["a\rb".into(), "c".into()],
+                vec![
+                    (Violation::BareCarriageReturn, 1),
+                    (Violation::BareCarriageReturn, 5)
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_unterminated_quote() {
+        assert_eq!(
+            records(b"a,\"b\nc"),
+            vec![(
+                vec!["a".into(), "b\nc".into()],
+                vec![(Violation::UnterminatedQuote, 2)]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_check_strict() {
+        let file = NamedTempFile::new().unwrap();
+        fs::write(file.path(), "\u{FEFF}a,b\n\"x\"y,1\nq\"\"r,2\n").unwrap();
+
+        let strict_check =
+            check_strict(file.path(), ",", "utf-8", &ParseOptions::default(), None).unwrap();
+
+        assert_eq!(strict_check.record_count, 3);
+        assert_eq!(
+            strict_check.findings,
+            vec![
+                Finding {
+                    violation: Violation::TextAfterClosingQuote,
+                    byte_offset: 10,
+                    record: 1
+                },
+                Finding {
+                    violation: Violation::QuoteInUnquotedField,
+                    byte_offset: 15,
+                    record: 2
+                },
+                Finding {
+                    violation: Violation::QuoteInUnquotedField,
+                    byte_offset: 16,
+                    record: 2
+                },
+            ]
+        );
+        assert_eq!(strict_check.finding_count(), 3);
+        // the lenient parser reads `"x"y,1` as an open quote, so it joins the last two lines
+        assert_eq!(strict_check.differing_rows, RowSet::from([1]));
+    }
+
+    #[test]
+    fn test_check_strict_realigns_after_differing_row() {
+        let file = NamedTempFile::new().unwrap();
+        fs::write(file.path(), "a,b\n\"x\"y,1\nz\",2\n3,4\n5,6\n").unwrap();
+
+        let strict_check =
+            check_strict(file.path(), ",", "utf-8", &ParseOptions::default(), None).unwrap();
+
+        assert_eq!(strict_check.record_count, 5);
+        // the lenient parser reads lines 1 and 2 as a single row, the rows after it are read the same way
+        assert_eq!(strict_check.differing_rows, RowSet::from([1]));
+    }
+
+    #[test]
+    fn test_check_strict_encoding() {
+        let file = NamedTempFile::new().unwrap();
+        fs::write(file.path(), "a\n").unwrap();
+
+        let error =
+            check_strict(file.path(), ",", "utf-16le", &ParseOptions::default(), None).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "strict mode can't be used together with an encoding that isn't ASCII compatible"
+        );
+    }
+}
@@ -0,0 +1,10 @@
+use std::io::Write;
+
+use tempfile::NamedTempFile;
+
+/// A temporary file containing `content`, deleted when dropped
+pub(crate) fn temp_file(content: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(content.as_bytes()).unwrap();
+    file
+}
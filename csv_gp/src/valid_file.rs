@@ -1,31 +1,407 @@
-use crate::{csv_details::CSVDetails, error::CSVError, parser::parse_file};
+use crate::{
+    cell::Cell,
+    csv_details::CSVDetails,
+    error::{CSVError, InvalidOption},
+    file::create_encoded_file,
+    jsonl::{write_json_lines, JsonLinesOptions},
+    parser::{read_header, read_rows, ParseOptions},
+    progress::ProgressTracker,
+};
 
-use std::{fs, io, path::Path};
+#[cfg(feature = "parquet")]
+use crate::{
+    arrow::{read_valid_rows_arrow, ArrowOptions},
+    parquet::write_parquet,
+};
 
-/// Saves a file containing only the valid rows according to the passed CSVDetails
-pub(crate) fn save_valid_file(
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use regex::Regex;
+
+/// Which of the duplicated rows to keep when deduplicating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keep {
+    First,
+    Last,
+}
+
+impl FromStr for Keep {
+    type Err = InvalidOption;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first" => Ok(Self::First),
+            "last" => Ok(Self::Last),
+            _ => Err(InvalidOption::Value {
+                option: "keep",
+                value: s.into(),
+            }),
+        }
+    }
+}
+
+/// When cells are quoted in a normalised CSV output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QuoteStyle {
+    /// Only cells containing the delimiter, a quote or a line break
+    #[default]
+    Necessary,
+    /// Every cell
+    Always,
+    /// Every cell that isn't a number
+    NonNumeric,
+}
+
+impl FromStr for QuoteStyle {
+    type Err = InvalidOption;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "necessary" => Ok(Self::Necessary),
+            "always" => Ok(Self::Always),
+            "non-numeric" => Ok(Self::NonNumeric),
+            _ => Err(InvalidOption::Value {
+                option: "quote style",
+                value: s.into(),
+            }),
+        }
+    }
+}
+
+impl From<QuoteStyle> for csv::QuoteStyle {
+    fn from(quote_style: QuoteStyle) -> Self {
+        match quote_style {
+            QuoteStyle::Necessary => Self::Necessary,
+            QuoteStyle::Always => Self::Always,
+            QuoteStyle::NonNumeric => Self::NonNumeric,
+        }
+    }
+}
+
+/// Line terminator of a normalised CSV output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineTerminator {
+    /// `\r\n`, as in RFC 4180
+    #[default]
+    Crlf,
+    /// `\n`
+    Lf,
+}
+
+impl FromStr for LineTerminator {
+    type Err = InvalidOption;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "crlf" => Ok(Self::Crlf),
+            "lf" => Ok(Self::Lf),
+            _ => Err(InvalidOption::Value {
+                option: "line terminator",
+                value: s.into(),
+            }),
+        }
+    }
+}
+
+/// How a normalised CSV output is written, regardless of how the file was written
+#[derive(Debug, Clone)]
+pub struct OutputDialect {
+    /// Delimiter between cells, the file's delimiter if not given. Must be an ASCII character, so it has to be given
+    /// when the file's delimiter isn't one.
+    pub delimiter: Option<char>,
+    pub quote_style: QuoteStyle,
+    pub line_terminator: LineTerminator,
+    /// Encoding label, writing fails on the first character that can't be encoded in it
+    pub encoding: String,
+}
+
+impl Default for OutputDialect {
+    fn default() -> Self {
+        Self {
+            delimiter: None,
+            quote_style: QuoteStyle::default(),
+            line_terminator: LineTerminator::default(),
+            encoding: "utf-8".into(),
+        }
+    }
+}
+
+/// Format of the valid rows file
+#[derive(Debug, Clone, Default)]
+pub enum OutputFormat {
+    /// CSV with the same delimiter, with the cells as they were in the file
+    #[default]
+    Csv,
+    /// RFC 4180 CSV, with the cells unquoted and unescaped then quoted again according to the dialect
+    NormalisedCsv(OutputDialect),
+    /// JSON Lines, with a JSON object (or array) per row
+    JsonLines(JsonLinesOptions),
+    /// Parquet, with the column names from the header and the column types from the options
+    #[cfg(feature = "parquet")]
+    Parquet(ArrowOptions),
+}
+
+impl FromStr for OutputFormat {
+    type Err = InvalidOption;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(Self::Csv),
+            "normalised-csv" => Ok(Self::NormalisedCsv(OutputDialect::default())),
+            "jsonl" => Ok(Self::JsonLines(JsonLinesOptions::default())),
+            #[cfg(feature = "parquet")]
+            "parquet" => Ok(Self::Parquet(ArrowOptions::default())),
+            _ => Err(InvalidOption::Value {
+                option: "output format",
+                value: s.into(),
+            }),
+        }
+    }
+}
+
+/// Options changing what is written to the valid rows file
+#[derive(Debug, Clone, Default)]
+pub struct ValidFileOptions {
+    /// Only write one of each group of duplicate rows (or duplicate keys, if key columns were given)
+    pub deduplicate: Option<Keep>,
+    /// Replace every invisible or control character with this string, an empty string removes them
+    pub replace_invisible_characters: Option<String>,
+    /// Replace text that looks like UTF-8 decoded with a single byte encoding with the correctly decoded text
+    pub repair_mojibake: bool,
+    /// Prefix cells that spreadsheet software would interpret as a formula with `'`
+    pub neutralise_formulas: bool,
+    /// Cells matching this pattern aren't treated as formulas, on top of plain numbers such as `-12.5`
+    pub formula_allowlist: Option<Regex>,
+    /// Format the valid rows are written in
+    pub format: OutputFormat,
+    /// Also write the rows that aren't valid to this path, as they were in the file
+    pub rejected_rows_path: Option<PathBuf>,
+}
+
+/// Returns the valid rows that are left out of the output as they duplicate another valid row
+fn duplicates_to_skip(csv_details: &CSVDetails, keep: Keep) -> HashSet<usize> {
+    let groups = csv_details
+        .duplicate_key_rows
+        .as_ref()
+        .unwrap_or(&csv_details.duplicate_rows);
+
+    let mut skipped = HashSet::new();
+
+    for group in groups {
+        let mut valid = group
+            .iter()
+            .filter(|&&i| csv_details.valid_rows.contains(i));
+
+        let kept = match keep {
+            Keep::First => valid.next(),
+            Keep::Last => valid.next_back(),
+        };
+
+        if let Some(kept) = kept {
+            skipped.extend(
+                group
+                    .iter()
+                    .filter(|&i| i != kept && csv_details.valid_rows.contains(*i)),
+            );
+        }
+    }
+
+    skipped
+}
+
+/// Applies the fixes in `options` to a valid row read with `parse_options`. Formulas are neutralised last, as the other
+/// fixes can change how a cell starts.
+fn fix_row(
+    mut row: Vec<Cell>,
+    parse_options: &ParseOptions,
+    options: &ValidFileOptions,
+) -> Vec<Cell> {
+    if options.repair_mojibake {
+        row = row.into_iter().map(Cell::repair_mojibake).collect();
+    }
+    if let Some(with) = &options.replace_invisible_characters {
+        row = row
+            .into_iter()
+            .map(|c| c.replace_invisible_characters(with))
+            .collect();
+    }
+    if options.neutralise_formulas {
+        let allowlist = options.formula_allowlist.as_ref();
+        row = row
+            .into_iter()
+            .map(|c| c.neutralise_formula(parse_options.quoting, allowlist))
+            .collect();
+    }
+    row
+}
+
+/// Reads the valid rows according to the passed CSVDetails with their line numbers, with the fixes in `options`
+/// applied. All the valid rows must have been kept, which isn't the case in summary mode without a valid rows output.
+pub(crate) fn read_valid_rows(
+    path: impl AsRef<Path>,
+    csv_details: &CSVDetails,
+    delimiter: &str,
+    encoding: &str,
+    parse_options: &ParseOptions,
+    options: &ValidFileOptions,
+) -> Result<impl Iterator<Item = io::Result<(usize, Vec<Cell>)>>, CSVError> {
+    filter_valid_rows(
+        read_rows(path, delimiter, encoding, parse_options)?,
+        csv_details,
+        parse_options,
+        options,
+    )
+}
+
+/// Same as [`read_valid_rows`], with the rows of the file already read into `rows`
+pub(crate) fn filter_valid_rows(
+    rows: impl Iterator<Item = io::Result<Vec<Cell>>>,
+    csv_details: &CSVDetails,
+    parse_options: &ParseOptions,
+    options: &ValidFileOptions,
+) -> Result<impl Iterator<Item = io::Result<(usize, Vec<Cell>)>>, CSVError> {
+    let rows = filter_valid_rows_with(
+        rows.map(|row| row.map(|row| ((), row))),
+        csv_details,
+        parse_options,
+        options,
+    )?;
+
+    Ok(rows.map(|row| row.map(|(i, (), row)| (i, row))))
+}
+
+/// Same as [`filter_valid_rows`], keeping a value read along with each row, such as the line of the file it starts on
+pub(crate) fn filter_valid_rows_with<T>(
+    rows: impl Iterator<Item = io::Result<(T, Vec<Cell>)>>,
+    csv_details: &CSVDetails,
+    parse_options: &ParseOptions,
+    options: &ValidFileOptions,
+) -> Result<impl Iterator<Item = io::Result<(usize, T, Vec<Cell>)>>, CSVError> {
+    if csv_details.valid_rows.is_truncated() {
+        return Err(InvalidOption::Conflict {
+            option: "reading the valid rows",
+            other: "summary mode",
+        }
+        .into());
+    }
+
+    let skipped = options
+        .deduplicate
+        .map(|keep| duplicates_to_skip(csv_details, keep))
+        .unwrap_or_default();
+    let valid_rows = csv_details.valid_rows.clone();
+    let parse_options = parse_options.clone();
+    let options = options.clone();
+
+    let rows = rows
+        .enumerate()
+        .filter(move |(i, _)| valid_rows.contains(*i) && !skipped.contains(i))
+        .map(move |(i, row_result)| {
+            row_result.map(|(value, row)| (i, value, fix_row(row, &parse_options, &options)))
+        });
+
+    Ok(rows)
+}
+
+/// Saves a file containing only the rows that aren't valid according to the passed CSVDetails, as they were in the file.
+/// The rows of a fixed-width file are converted to CSV.
+pub(crate) fn save_rejected_rows(
     path: impl AsRef<Path>,
     csv_details: &CSVDetails,
-    delimiter: char,
+    delimiter: &str,
     encoding: &str,
     output_path: impl AsRef<Path>,
+    parse_options: &ParseOptions,
+    progress: &mut ProgressTracker,
 ) -> Result<(), CSVError> {
-    // Create intermediate directories
     if let Some(parent) = output_path.as_ref().parent() {
         fs::create_dir_all(parent)?;
     }
 
+    // footer rows aren't rows of data to fix
+    let rejected = read_rows(path, delimiter, encoding, parse_options)?
+        .enumerate()
+        .filter(|(i, _)| {
+            !csv_details.valid_rows.contains(*i) && !csv_details.footer.rows.contains(*i)
+        });
+
+    if parse_options.fixed_width.is_some() {
+        let rows = rejected.map(|(i, row_result)| row_result.map(|row| (i, row)));
+        write_normalised_csv(
+            progress.until_cancelled(rows),
+            delimiter,
+            parse_options,
+            output_path,
+            &OutputDialect::default(),
+        )?;
+        return progress.poll();
+    }
+
+    // rejected rows have any number of columns, so they are written as they are rather than with a CSV writer
+    let mut writer = BufWriter::new(File::create(output_path)?);
+
+    for (_, row_result) in progress.until_cancelled(rejected) {
+        let row: Vec<_> = row_result?.iter().map(Cell::to_string).collect();
+        writeln!(writer, "{}", row.join(delimiter))?;
+    }
+
+    writer.flush()?;
+
+    progress.poll()
+}
+
+/// Writes `rows` read with `parse_options` to `output_path` as CSV in `dialect`, with `delimiter` unless the dialect
+/// has its own. The delimiter must be a single ASCII character. The rows can have different numbers of cells, blank rows
+/// are left out.
+pub(crate) fn write_normalised_csv(
+    rows: impl Iterator<Item = io::Result<(usize, Vec<Cell>)>>,
+    delimiter: &str,
+    parse_options: &ParseOptions,
+    output_path: impl AsRef<Path>,
+    dialect: &OutputDialect,
+) -> Result<(), CSVError> {
+    let delimiter = dialect
+        .delimiter
+        .map_or_else(|| delimiter.to_string(), String::from);
+    // a single byte of UTF-8 is an ASCII character
+    let &[delimiter] = delimiter.as_bytes() else {
+        return Err(InvalidOption::NotAscii {
+            option: "output delimiter",
+            value: delimiter,
+        }
+        .into());
+    };
+
+    let terminator = match dialect.line_terminator {
+        LineTerminator::Crlf => csv::Terminator::CRLF,
+        LineTerminator::Lf => csv::Terminator::Any(b'\n'),
+    };
+
     let mut writer = csv::WriterBuilder::new()
-        .delimiter(delimiter as u8)
-        .quote_style(csv::QuoteStyle::Never) // quoting was untouched during parsing so set to avoid double quoting
-        .from_path(output_path)
-        .map_err(Into::<io::Error>::into)?;
-
-    for (i, row_result) in parse_file(path, delimiter, encoding)?.enumerate() {
-        if csv_details.valid_rows.contains(&i) {
-            let row = row_result?;
-            writer.write_record(row).map_err(Into::<io::Error>::into)?;
+        .flexible(true)
+        .delimiter(delimiter)
+        .quote_style(dialect.quote_style.into())
+        .terminator(terminator)
+        .from_writer(create_encoded_file(output_path, &dialect.encoding)?);
+
+    for row_result in rows {
+        let (_, row) = row_result?;
+        // a blank row would be written as `""`, and has nothing to keep anyway
+        if row.is_empty() {
+            continue;
         }
+        writer
+            .write_record(
+                row.iter()
+                    .map(|cell| cell.value(parse_options).into_owned()),
+            )
+            .map_err(Into::<io::Error>::into)?;
     }
 
     writer.flush()?;
@@ -33,19 +409,136 @@ pub(crate) fn save_valid_file(
     Ok(())
 }
 
+/// Saves a file containing only the valid rows according to the passed CSVDetails
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn save_valid_file(
+    path: impl AsRef<Path>,
+    csv_details: &CSVDetails,
+    delimiter: &str,
+    encoding: &str,
+    output_path: impl AsRef<Path>,
+    parse_options: &ParseOptions,
+    options: &ValidFileOptions,
+    progress: &mut ProgressTracker,
+) -> Result<(), CSVError> {
+    // Create intermediate directories
+    if let Some(parent) = output_path.as_ref().parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    match &options.format {
+        // the cells of a fixed-width file have no delimiter between them to keep, so they are converted to CSV
+        OutputFormat::Csv if parse_options.fixed_width.is_some() => {
+            let rows = read_valid_rows(
+                &path,
+                csv_details,
+                delimiter,
+                encoding,
+                parse_options,
+                options,
+            )?;
+
+            write_normalised_csv(
+                progress.until_cancelled(rows),
+                delimiter,
+                parse_options,
+                output_path,
+                &OutputDialect::default(),
+            )?;
+        }
+        OutputFormat::Csv => {
+            let rows = read_valid_rows(
+                &path,
+                csv_details,
+                delimiter,
+                encoding,
+                parse_options,
+                options,
+            )?;
+
+            // the cells are written as they are, quoting was untouched during parsing, and the delimiter can be any
+            // string, so this is done without a CSV writer
+            let mut writer = BufWriter::new(File::create(output_path)?);
+
+            for row_result in progress.until_cancelled(rows) {
+                let (_, row) = row_result?;
+                let row: Vec<_> = row.iter().map(Cell::raw).collect();
+                writeln!(writer, "{}", row.join(delimiter))?;
+            }
+
+            writer.flush()?;
+        }
+        OutputFormat::NormalisedCsv(dialect) => {
+            let rows = read_valid_rows(
+                &path,
+                csv_details,
+                delimiter,
+                encoding,
+                parse_options,
+                options,
+            )?;
+
+            write_normalised_csv(
+                progress.until_cancelled(rows),
+                delimiter,
+                parse_options,
+                output_path,
+                dialect,
+            )?;
+        }
+        OutputFormat::JsonLines(json_options) => {
+            let header = if json_options.header {
+                read_header(&path, delimiter, encoding, parse_options)?
+            } else {
+                Vec::new()
+            };
+            let rows = filter_valid_rows_with(
+                read_rows(&path, delimiter, encoding, parse_options)?.with_start_lines(),
+                csv_details,
+                parse_options,
+                options,
+            )?;
+
+            write_json_lines(
+                progress.until_cancelled(rows),
+                &header,
+                output_path,
+                parse_options,
+                json_options,
+            )?;
+        }
+        #[cfg(feature = "parquet")]
+        OutputFormat::Parquet(arrow_options) => {
+            let reader = read_valid_rows_arrow(
+                path.as_ref().to_path_buf(),
+                csv_details,
+                delimiter,
+                encoding,
+                parse_options,
+                options,
+                arrow_options,
+            )?;
+
+            write_parquet(reader, output_path, progress)?;
+        }
+    }
+
+    // the rows stop early if the check was cancelled
+    progress.poll()
+}
+
 #[cfg(test)]
 mod tests {
-    use std::{
-        collections::HashSet,
-        fs::{self},
-    };
+    use std::fs::{self};
+
+    use crate::row_set::RowSet;
 
     use super::*;
 
     fn rows_with_valid(path: impl AsRef<Path>) -> (CSVDetails, impl AsRef<Path>) {
         fs::write(&path, "a,b\ninvalid\n\"\"\"quoted\"\"\",row").unwrap();
         let mut csv_details = CSVDetails::new();
-        csv_details.valid_rows = HashSet::from_iter(vec![0, 2]);
+        csv_details.valid_rows = RowSet::from([0, 2]);
 
         (csv_details, path)
     }
@@ -56,7 +549,17 @@ mod tests {
         let (csv_details, path) = rows_with_valid(dir.path().join("test_save_valid_file_base.csv"));
         let out_path = dir.path().join("test_save_valid_file.csv");
 
-        save_valid_file(path, &csv_details, ',', "utf-8", &out_path).unwrap();
+        save_valid_file(
+            path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &ValidFileOptions::default(),
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
 
         let file = fs::read_to_string(out_path).unwrap();
 
@@ -69,10 +572,474 @@ mod tests {
         let (csv_details, path) = rows_with_valid(dir.path().join("create_parent_dir.csv"));
         let out_path = dir.path().join("parent").join("child.csv");
 
-        save_valid_file(path, &csv_details, ',', "utf-8", &out_path).unwrap();
+        save_valid_file(
+            path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &ValidFileOptions::default(),
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
 
         let file = fs::read_to_string(out_path).unwrap();
 
         assert_eq!(file, "a,b\n\"\"\"quoted\"\"\",row\n")
     }
+
+    #[test]
+    fn test_save_valid_file_string_delimiter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("string_delimiter.csv");
+        let out_path = dir.path().join("string_delimiter_out.csv");
+        fs::write(&path, "a~|~b\n\"x~|~y\"~|~§\n").unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = RowSet::from([0, 1]);
+
+        save_valid_file(
+            &path,
+            &csv_details,
+            "~|~",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &ValidFileOptions::default(),
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            fs::read_to_string(out_path).unwrap()
+        )
+    }
+
+    #[test]
+    fn test_normalised_csv_needs_ascii_delimiter() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("normalised_delimiter.csv");
+        fs::write(&path, "a§b\n").unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = RowSet::from([0]);
+        let options = ValidFileOptions {
+            format: OutputFormat::NormalisedCsv(OutputDialect::default()),
+            ..Default::default()
+        };
+
+        let error = save_valid_file(
+            &path,
+            &csv_details,
+            "§",
+            "utf-8",
+            dir.path().join("normalised_delimiter_out.csv"),
+            &ParseOptions::default(),
+            &options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "output delimiter must be a single ASCII character, not §"
+        )
+    }
+
+    #[test]
+    fn test_fixed_width_converted_to_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fixed_width.txt");
+        fs::write(
+            &path,
+            "NAME AMOUNT
+Alice 1,000
+Bob
+",
+        )
+        .unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = RowSet::from([0, 1]);
+        let parse_options = ParseOptions::fixed_width("5,6".parse().unwrap());
+        let out_path = dir.path().join("fixed_width_out.csv");
+        let rejected_path = dir.path().join("fixed_width_rejected.csv");
+
+        save_valid_file(
+            &path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            &parse_options,
+            &ValidFileOptions::default(),
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+        save_rejected_rows(
+            &path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &rejected_path,
+            &parse_options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(out_path).unwrap(),
+            "NAME,AMOUNT\r\nAlice,\"1,000\"\r\n"
+        );
+        assert_eq!(fs::read_to_string(rejected_path).unwrap(), "Bob\r\n");
+    }
+
+    fn rows_with_duplicates(path: impl AsRef<Path>) -> (CSVDetails, impl AsRef<Path>) {
+        fs::write(&path, "id,v\n1,a\n2,b\n1,a\n1,c\n").unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = RowSet::from([0, 1, 2, 3, 4]);
+        csv_details.duplicate_rows = vec![vec![1, 3]];
+
+        (csv_details, path)
+    }
+
+    #[test]
+    fn test_save_valid_file_deduplicate() {
+        let dir = tempfile::tempdir().unwrap();
+        let (csv_details, path) = rows_with_duplicates(dir.path().join("deduplicate.csv"));
+        let out_path = dir.path().join("deduplicate_out.csv");
+        let options = ValidFileOptions {
+            deduplicate: Some(Keep::First),
+            ..Default::default()
+        };
+
+        save_valid_file(
+            path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        let file = fs::read_to_string(out_path).unwrap();
+
+        assert_eq!(file, "id,v\n1,a\n2,b\n1,c\n")
+    }
+
+    #[test]
+    fn test_save_valid_file_deduplicate_keys_keep_last() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut csv_details, path) = rows_with_duplicates(dir.path().join("deduplicate_keys.csv"));
+        csv_details.duplicate_key_rows = Some(vec![vec![1, 3, 4]]);
+        csv_details.valid_rows.remove(4);
+        let out_path = dir.path().join("deduplicate_keys_out.csv");
+        let options = ValidFileOptions {
+            deduplicate: Some(Keep::Last),
+            ..Default::default()
+        };
+
+        save_valid_file(
+            path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        let file = fs::read_to_string(out_path).unwrap();
+
+        assert_eq!(file, "id,v\n2,b\n1,a\n")
+    }
+
+    #[test]
+    fn test_save_valid_file_replace_invisible_characters() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("invisible.csv");
+        fs::write(&path, "a,b\n\0x\u{200B},y\u{A0}z\n").unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = RowSet::from([0, 1]);
+        let out_path = dir.path().join("invisible_out.csv");
+        let options = ValidFileOptions {
+            replace_invisible_characters: Some("".into()),
+            ..Default::default()
+        };
+
+        save_valid_file(
+            path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        let file = fs::read_to_string(out_path).unwrap();
+
+        assert_eq!(file, "a,b\nx,yz\n")
+    }
+
+    #[test]
+    fn test_save_valid_file_repair_mojibake() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mojibake.csv");
+        fs::write(&path, "name,city\nMÃ¼ller,ZÃ¼rich\n").unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = RowSet::from([0, 1]);
+        let out_path = dir.path().join("mojibake_out.csv");
+        let options = ValidFileOptions {
+            repair_mojibake: true,
+            ..Default::default()
+        };
+
+        save_valid_file(
+            path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        let file = fs::read_to_string(out_path).unwrap();
+
+        assert_eq!(file, "name,city\nMüller,Zürich\n")
+    }
+
+    #[test]
+    fn test_save_valid_file_neutralise_formulas() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("formulas.csv");
+        fs::write(&path, "a,b\n=1+1,-5\n\"@cmd\",ok\n").unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = RowSet::from([0, 1, 2]);
+        let out_path = dir.path().join("formulas_out.csv");
+        let options = ValidFileOptions {
+            neutralise_formulas: true,
+            ..Default::default()
+        };
+
+        save_valid_file(
+            path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        let file = fs::read_to_string(out_path).unwrap();
+
+        assert_eq!(file, "a,b\n'=1+1,-5\n\"'@cmd\",ok\n")
+    }
+
+    #[test]
+    fn test_save_valid_file_neutralise_formulas_after_other_fixes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hidden_formulas.csv");
+        fs::write(&path, "a,b\n\u{200B}=HYPERLINK(1),\0@SUM(A1)\n-,+44 1234\n").unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = RowSet::from([0, 1, 2]);
+        let out_path = dir.path().join("hidden_formulas_out.csv");
+        let options = ValidFileOptions {
+            replace_invisible_characters: Some("".into()),
+            neutralise_formulas: true,
+            formula_allowlist: Some(Regex::new(r"^(-|\+[\d ]+)$").unwrap()),
+            ..Default::default()
+        };
+
+        save_valid_file(
+            path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        let file = fs::read_to_string(out_path).unwrap();
+
+        assert_eq!(file, "a,b\n'=HYPERLINK(1),'@SUM(A1)\n-,+44 1234\n")
+    }
+
+    #[test]
+    fn test_save_valid_file_normalised_csv() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("normalised.csv");
+        fs::write(&path, "a;b\n\"x;y\";\"say \"\"hi\"\"\"\n\"plain\";café\n").unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = RowSet::from([0, 1, 2]);
+        let out_path = dir.path().join("normalised_out.csv");
+        let options = ValidFileOptions {
+            format: OutputFormat::NormalisedCsv(OutputDialect {
+                delimiter: Some(','),
+                encoding: "windows-1252".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        save_valid_file(
+            path,
+            &csv_details,
+            ";",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        let file = fs::read(out_path).unwrap();
+
+        assert_eq!(file, b"a,b\r\nx;y,\"say \"\"hi\"\"\"\r\nplain,caf\xe9\r\n");
+    }
+
+    #[test]
+    fn test_save_valid_file_normalised_csv_quote_style() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quote_style.csv");
+        fs::write(&path, "a,b\n1,x\n").unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = RowSet::from([0, 1]);
+        let out_path = dir.path().join("quote_style_out.csv");
+        let options = ValidFileOptions {
+            format: OutputFormat::NormalisedCsv(OutputDialect {
+                quote_style: "non-numeric".parse().unwrap(),
+                line_terminator: "lf".parse().unwrap(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        save_valid_file(
+            path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        let file = fs::read_to_string(out_path).unwrap();
+
+        assert_eq!(file, "\"a\",\"b\"\n1,\"x\"\n");
+    }
+
+    #[test]
+    fn test_save_valid_file_normalised_csv_unencodable_character() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("unencodable.csv");
+        fs::write(&path, "name\nŁódź\n").unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.valid_rows = RowSet::from([0, 1]);
+        let options = ValidFileOptions {
+            format: OutputFormat::NormalisedCsv(OutputDialect {
+                encoding: "windows-1252".into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let error = save_valid_file(
+            path,
+            &csv_details,
+            ",",
+            "utf-8",
+            dir.path().join("unencodable_out.csv"),
+            &ParseOptions::default(),
+            &options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "the character 'Ł' can't be encoded in windows-1252"
+        );
+    }
+
+    #[test]
+    fn test_save_rejected_rows() {
+        let dir = tempfile::tempdir().unwrap();
+        let (csv_details, path) = rows_with_valid(dir.path().join("rejected.csv"));
+        let rejected_path = dir.path().join("rejected_rows.csv");
+
+        save_rejected_rows(
+            path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &rejected_path,
+            &ParseOptions::default(),
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(rejected_path).unwrap(), "invalid\n");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_save_valid_file_parquet() {
+        use arrow_schema::DataType;
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("parquet.csv");
+        fs::write(&path, "id,name\n1,\"a,b\"\nx\n2,c\n").unwrap();
+        let mut csv_details = CSVDetails::new();
+        csv_details.column_count = 2;
+        csv_details.valid_rows = RowSet::from([0, 1, 3]);
+        let out_path = dir.path().join("parquet.parquet");
+        let options = ValidFileOptions {
+            format: OutputFormat::Parquet(ArrowOptions {
+                column_types: [("id".to_string(), DataType::Int64)].into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        save_valid_file(
+            &path,
+            &csv_details,
+            ",",
+            "utf-8",
+            &out_path,
+            &ParseOptions::default(),
+            &options,
+            &mut ProgressTracker::default(),
+        )
+        .unwrap();
+
+        let reader = SerializedFileReader::new(File::open(out_path).unwrap()).unwrap();
+        let rows: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().to_string())
+            .collect();
+        assert_eq!(rows, vec!["{id: 1, name: \"a,b\"}", "{id: 2, name: \"c\"}"]);
+    }
 }
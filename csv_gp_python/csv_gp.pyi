@@ -1,6 +1,233 @@
+from collections.abc import Callable, Iterable, Iterator, Set
+from typing import Any, Literal
+
 class UnknownEncoding(Exception):  # noqa: N818
     pass
 
+class RowSet(Set[int]):
+    """
+    Read-only view of a set of line numbers, iterated in ascending order
+
+    In summary mode only the first example line numbers are kept, while `len()` is the total count
+
+    Compares equal to a list of the same line numbers in ascending order, or a set of the same line numbers. It's a
+    `collections.abc.Set`, so it can be combined with other sets of line numbers with `|`, `&`, `-` and `^`, which
+    return a new RowSet, and compared to them with `<=`, `<`, `>=` and `>`
+    """
+
+    def __len__(self) -> int: ...
+    def __contains__(self, row: int) -> bool: ...
+    def __getitem__(self, index: int) -> int: ...
+    def __iter__(self) -> Iterator[int]: ...
+    def __or__(self, other: Set[int]) -> RowSet: ...
+    def __and__(self, other: Set[int]) -> RowSet: ...
+    def __sub__(self, other: Set[int]) -> RowSet: ...
+    def __xor__(self, other: Set[int]) -> RowSet: ...
+    def isdisjoint(self, other: Iterable[int]) -> bool: ...
+
+class ColumnCounts:
+    """
+    Read-only view of the number of columns per line, the index corresponding to the line number
+
+    Compares equal to a list of the same column counts
+    """
+
+    def __len__(self) -> int: ...
+    def __getitem__(self, index: int) -> int: ...
+    def __iter__(self) -> Iterator[int]: ...
+
+class PartialCheck:
+    """
+    Details of a check that only covered part of the file
+    """
+
+    @property
+    def reason(self) -> Literal["issue_limit", "row_limit", "sample"]:
+        """
+        Why only part of the file was checked
+        """
+
+    @property
+    def rows_checked(self) -> int:
+        """
+        Number of rows that were checked
+        """
+
+    @property
+    def rows_read(self) -> int:
+        """
+        Number of rows that were read from the file, which is all of them when sampling
+        """
+
+    def extrapolate(self, count: int) -> int | None:
+        """
+        Estimates the count of an issue in the whole file from the count in the checked rows
+
+        Only possible for samples, as the other checks stop before reading the whole file
+        """
+
+Violation = Literal["quote_in_unquoted_field", "text_after_closing_quote", "bare_carriage_return", "unterminated_quote"]
+
+class StrictFinding:
+    """
+    A deviation from RFC 4180
+    """
+
+    @property
+    def violation(self) -> Violation: ...
+    @property
+    def byte_offset(self) -> int:
+        """
+        Position of the offending byte from the start of the file
+        """
+
+    @property
+    def record(self) -> int:
+        """
+        Number of the record the byte is in, according to the strict parser
+        """
+
+class StrictCheck:
+    """
+    Results of parsing the file strictly according to RFC 4180, compared to the lenient parser
+    """
+
+    @property
+    def findings(self) -> list[StrictFinding]:
+        """
+        Deviations from RFC 4180 in the order they appear in the file, only the first examples in summary mode
+        """
+
+    @property
+    def counts(self) -> dict[Violation, int]:
+        """
+        Number of deviations of each kind
+        """
+
+    @property
+    def finding_count(self) -> int:
+        """
+        Total number of deviations from RFC 4180
+        """
+
+    @property
+    def record_count(self) -> int:
+        """
+        Number of records according to the strict parser
+        """
+
+    @property
+    def differing_rows(self) -> RowSet:
+        """
+        Line numbers of the rows that the lenient parser reads differently from the strict parser
+        """
+
+DialectPreset = Literal["rfc4180", "excel", "excel-tab", "unix", "mysql", "postgres-csv"]
+
+class Dialect:
+    """
+    The settings needed to read files written in a particular way, usable in place of a delimiter
+    """
+
+    def __init__(
+        self,
+        delimiter: str = ",",
+        encoding: str = "utf-8",
+        quoting: bool = True,
+        escape: str | None = None,
+        trim_whitespace_outside_quotes: bool = False,
+    ) -> None:
+        """
+        `delimiter` can be any non-empty string. If `quoting` isn't set, quotes are read like any other character.
+        `escape` makes the next character literal, e.g. `\\` in MySQL exports, and can't be a quote or part of the
+        delimiter. The sequences `\\0`, `\\b`, `\\n`, `\\r`, `\\t` and `\\Z` are decoded to their control characters,
+        and a cell of only `\\N` (NULL) is read as empty
+        """
+
+    @staticmethod
+    def preset(name: DialectPreset) -> Dialect:
+        """
+        The built-in dialect with this name
+        """
+
+    @staticmethod
+    def presets() -> list[DialectPreset]:
+        """
+        Names of the built-in dialects
+        """
+
+    @staticmethod
+    def from_file(path: str) -> Dialect:
+        """
+        Reads a dialect from a TOML file, settings not in the file are taken from the `base` preset (`rfc4180` by
+        default)
+        """
+
+    @property
+    def delimiter(self) -> str: ...
+    @property
+    def encoding(self) -> str: ...
+    @property
+    def quoting(self) -> bool: ...
+    @property
+    def escape(self) -> str | None: ...
+    @property
+    def trim_whitespace_outside_quotes(self) -> bool: ...
+
+class RowIterator:
+    """
+    Lazy iterator over the `(line number, cells)` of the rows of a file
+
+    The file is closed when the iterator is exhausted, closed, or when leaving a `with` block
+    """
+
+    def __iter__(self) -> RowIterator: ...
+    def __next__(self) -> tuple[int, list[str]]: ...
+    def __enter__(self) -> RowIterator: ...
+    def __exit__(self, exc_type: object, exc_value: object, traceback: object) -> None: ...
+    def close(self) -> None:
+        """
+        Closes the file, the iterator doesn't yield any more rows afterwards
+        """
+
+class ValidRowsStream:
+    """
+    Stream of the valid rows of a file as Arrow record batches, implementing the Arrow PyCapsule interface
+
+    It can be read once, e.g. with `pyarrow.RecordBatchReader.from_stream(stream)` or `polars.DataFrame(stream)`
+    """
+
+    def __arrow_c_stream__(self, requested_schema: object | None = None) -> object: ...
+
+class Progress:
+    """
+    How far a running check has got
+    """
+
+    @property
+    def bytes_read(self) -> int:
+        """
+        Number of bytes of the file read so far
+        """
+
+    @property
+    def total_bytes(self) -> int:
+        """
+        Size of the file in bytes
+        """
+
+    @property
+    def rows_processed(self) -> int:
+        """
+        Number of rows read so far, including rows skipped when sampling
+        """
+
+    @property
+    def issues_found(self) -> int:
+        """
+        Number of rows with issues found so far
+        """
+
 class CSVDetails:
     @property
     def row_count(self) -> int:
@@ -21,49 +248,87 @@ class CSVDetails:
         """
 
     @property
-    def column_count_per_line(self) -> list[int]:
+    def invisible_character_counts(self) -> dict[str, int]:
+        """
+        Number of invisible or control characters in the file, per category
+
+        Categories are `null`, `end_of_file`, `control`, `zero_width`, `bidi` and `non_breaking_space`
+        """
+
+    @property
+    def invisible_character_locations(self) -> dict[str, list[tuple[int, int]]]:
+        """
+        List of (line number, column index) of the cells containing invisible or control characters, per category
+        """
+
+    @property
+    def mojibake(self) -> dict[tuple[str, str], RowSet]:
+        """
+        List of line numbers containing text that looks like UTF-8 decoded with a single byte encoding (windows-1252,
+        windows-1250, windows-1251 or Mac Roman), keyed by (the encoding the text was written in, the encoding it was
+        mistakenly decoded with). Text in a single byte encoding read as UTF-8 is counted in `invalid_character_count`
+        """
+
+    @property
+    def formula_injection(self) -> list[tuple[int, int]]:
+        """
+        List of (line number, column index) of cells that spreadsheet software would interpret as a formula
+
+        These are cells starting with `=`, `+`, `-`, `@`, a tab or a carriage return, excluding plain numbers
+        """
+
+    @property
+    def formula_injection_count(self) -> int:
+        """
+        Number of cells that spreadsheet software would interpret as a formula
+        """
+
+    @property
+    def column_count_per_line(self) -> ColumnCounts:
         """
         Number of columns per line, the index corresponding to the line number
+
+        Empty in summary mode
         """
 
     @property
-    def too_few_columns(self) -> list[int]:
+    def too_few_columns(self) -> RowSet:
         """
         List of line numbers that contain fewer columns than the header
         """
 
     @property
-    def too_many_columns(self) -> list[int]:
+    def too_many_columns(self) -> RowSet:
         """
         List of line numbers that contain more columns than the header
         """
 
     @property
-    def quoted_delimiter(self) -> list[int]:
+    def quoted_delimiter(self) -> RowSet:
         """
         List of line numbers that contain a correctly quoted delimiter
         """
 
     @property
-    def quoted_newline(self) -> list[int]:
+    def quoted_newline(self) -> RowSet:
         """
         List of line numbers that contain a correctly quoted newline
         """
 
     @property
-    def quoted_quote(self) -> list[int]:
+    def quoted_quote(self) -> RowSet:
         """
         List of line numbers that contain quoted-quotes ("")
         """
 
     @property
-    def quoted_quote_correctly(self) -> list[int]:
+    def quoted_quote_correctly(self) -> RowSet:
         """
         List of line numbers that contain correctly quoted-quotes (only contained within quoted cells)
         """
 
     @property
-    def incorrect_cell_quote(self) -> list[int]:
+    def incorrect_cell_quote(self) -> RowSet:
         """
         List of line numbers that have incorrectly quoted cells
 
@@ -73,37 +338,511 @@ class CSVDetails:
         """
 
     @property
-    def all_empty_rows(self) -> list[int]:
+    def whitespace_padding(self) -> RowSet:
+        """
+        List of line numbers that have unquoted cells with leading or trailing whitespace
+        """
+
+    @property
+    def whitespace_outside_quotes(self) -> RowSet:
+        """
+        List of line numbers that have quoted cells with whitespace before the opening or after the closing quote
+        """
+
+    @property
+    def all_empty_rows(self) -> RowSet:
         """
         List of line numbers where all cells in the row are empty (either zero characters or just `""`)
         """
 
     @property
-    def blank_rows(self) -> list[int]:
+    def blank_rows(self) -> RowSet:
         """
         List of line numbers that are completely blank
         """
 
     @property
-    def valid_rows(self) -> set[int]:
+    def valid_rows(self) -> RowSet:
         """
         Set of all row numbers that are valid in the file
         """
 
+    @property
+    def duplicate_rows(self) -> list[list[int]]:
+        """
+        Groups of line numbers where the rows are exact duplicates of each other, not checked in summary mode
+        """
+
+    @property
+    def duplicate_key_rows(self) -> list[list[int]] | None:
+        """
+        Groups of line numbers where the rows have the same values in the key columns, if key columns were given
+        """
+
+    @property
+    def example_limit(self) -> int | None:
+        """
+        Maximum number of example line numbers kept per issue, if only a summary of the file was kept
+
+        The row sets then only contain the first examples, but their lengths are the total counts
+        """
+
+    @property
+    def partial(self) -> PartialCheck | None:
+        """
+        Details of which part of the file was checked, if the whole file wasn't checked
+        """
+
+    @property
+    def strict(self) -> StrictCheck | None:
+        """
+        Deviations from RFC 4180, if the file was checked with `strict`
+        """
+
+    @property
+    def preamble(self) -> list[str]:
+        """
+        The lines before the header that were skipped, as they are in the file
+        """
+
+    @property
+    def comment_lines(self) -> RowSet:
+        """
+        Numbers of the lines of the file (counting from 0, the preamble included) that were skipped as comments. These
+        are lines of the file rather than line numbers of rows
+        """
+
+    @property
+    def footer_rows(self) -> RowSet:
+        """
+        List of line numbers of the summary rows at the end of the file, if `footer_rows` was passed. These aren't
+        checked as rows
+        """
+
+    @property
+    def declared_record_count(self) -> int | None:
+        """
+        Number of records the footer says the file has, if `footer_record_count` was passed and matched
+        """
+
+    @property
+    def record_count_mismatch(self) -> bool:
+        """
+        Whether the footer declares a number of records other than the number of valid rows (without the header)
+        """
+
     @property
     def header_messed_up(self) -> bool:
         """
         The header is considered messed up when none of the rows have the same number of columns as the header
         """
 
-def check_file(path: str, delimiter: str, encoding: str, valid_rows_output_path: str | None = None) -> CSVDetails:
+    @property
+    def issue_counts(self) -> dict[str, int]:
+        """
+        Number of occurrences of each kind of issue, keyed by the name of the attribute, e.g. `too_many_columns`
+        """
+
+    def save_baseline(self, path: str) -> None:
+        """
+        Save the row, column and issue counts to `path` as JSON, to compare the checks of later files to with
+        `compare_to_baseline`
+        """
+
+class BaselineComparison:
+    """
+    Differences between the issue counts of a check and a baseline, its string being a report of them
+    """
+
+    @property
+    def deltas(self) -> dict[str, tuple[int, int]]:
+        """
+        (baseline count, current count) of the issues found a different number of times than in the baseline. Issues
+        missing from the baseline aren't compared
+        """
+
+    @property
+    def regressions(self) -> list[str]:
+        """
+        The issues found more times than their tolerance allows
+        """
+
+    @property
+    def has_regressions(self) -> bool: ...
+
+class RowChange:
+    """
+    A row of the old file matched with a row of the new file with different values
+    """
+
+    @property
+    def old_row(self) -> int: ...
+    @property
+    def new_row(self) -> int: ...
+    @property
+    def cells(self) -> list[tuple[int, str | None, str | None]]:
+        """
+        (column index, old value, new value) of the cells with different values, a missing cell being None
+        """
+
+class FileDiff:
+    """
+    Differences between two files read with the same dialect, truthy if there are any
+    """
+
+    @property
+    def added_rows(self) -> list[int]:
+        """
+        Line numbers in the new file of the rows that aren't in the old file
+        """
+
+    @property
+    def removed_rows(self) -> list[int]:
+        """
+        Line numbers in the old file of the rows that aren't in the new file
+        """
+
+    @property
+    def changed_rows(self) -> list[RowChange]:
+        """
+        Rows in both files with different values, in the order of the new file
+        """
+
+    @property
+    def unchanged_row_count(self) -> int: ...
+    @property
+    def issue_changes(self) -> dict[str, tuple[int, int]]:
+        """
+        (old count, new count) of the issues found a different number of times in the two files, keyed by the name of
+        the `CSVDetails` attribute, e.g. `too_many_columns`
+        """
+
+    @property
+    def old_details(self) -> CSVDetails: ...
+    @property
+    def new_details(self) -> CSVDetails: ...
+    def report(self, examples: int = 10) -> str:
+        """
+        Summary of the differences, listing the first `examples` changed rows
+        """
+
+def check_file(
+    path: str,
+    delimiter: str | Dialect,
+    encoding: str | None = None,
+    valid_rows_output_path: str | None = None,
+    duplicate_key_columns: list[int] | None = None,
+    deduplicate: Literal["first", "last"] | None = None,
+    trim_whitespace_outside_quotes: bool = False,
+    replace_invisible_characters: str | None = None,
+    repair_mojibake: bool = False,
+    neutralise_formulas: bool = False,
+    formula_allowlist: str | None = None,
+    summary_examples: int | None = None,
+    max_issues: int | None = None,
+    max_rows: int | None = None,
+    sample_every: int | None = None,
+    sample_fraction: float | None = None,
+    sample_seed: int | None = None,
+    progress_callback: Callable[[Progress], None] | None = None,
+    valid_rows_output_format: Literal["csv", "normalised-csv", "jsonl", "parquet"] = "csv",
+    valid_rows_output_schema: Any | None = None,
+    rejected_rows_output_path: str | None = None,
+    strict: bool = False,
+    skip_lines: int = 0,
+    comment_prefix: str | None = None,
+    detect_preamble: bool = False,
+    footer_rows: int | None = None,
+    footer_patterns: list[str] | None = None,
+    footer_record_count: str | None = None,
+    footer_short_rows: bool = False,
+    fixed_width: str | list[int] | None = None,
+) -> CSVDetails:
     """
     Check the file located at `path`, interpreting the file with `delimiter` and `encoding`
 
+    `delimiter` can be any non-empty string, e.g. `,`, `§` or `~|~`. It can also be a `Dialect`, `encoding` then
+    overrides the dialect's. The encoding is UTF-8 if neither gives one
+
     If `valid_rows_output_path` is passed, a file containing the valid rows will be written to the specified path
+
+    If `duplicate_key_columns` is passed, rows with the same values in those columns are reported as duplicate keys.
+    Finding duplicates keeps a hash and a line number in memory for every row, unless `summary_examples` is passed
+
+    If `deduplicate` is passed, only the first or last of each group of duplicates is written to `valid_rows_output_path`
+
+    If `trim_whitespace_outside_quotes` is set, whitespace between a delimiter and a quote is ignored when parsing
+
+    If `replace_invisible_characters` is passed, invisible or control characters are replaced with it in the valid rows
+    output, an empty string removes them
+
+    If `repair_mojibake` is set, text that looks like UTF-8 decoded as windows-1252, windows-1250, windows-1251 or Mac
+    Roman is repaired in the valid rows output. The other way around can't be repaired, as the bytes that aren't valid
+    UTF-8 are replaced by U+FFFD; check the file with its actual encoding instead
+
+    If `neutralise_formulas` is set, cells that would be interpreted as a formula are prefixed with `'` in the valid
+    rows output, after the other fixes. Cells matching the `formula_allowlist` regex, e.g. `"^-$"`, aren't treated as
+    formulas, nor are plain numbers
+
+    If `summary_examples` is passed, only totals and the first `summary_examples` line numbers of each issue are kept,
+    so that checking huge files uses a constant amount of memory. Duplicates and the number of columns per line are
+    not checked in this mode
+
+    If `max_issues` is passed, checking stops after finding that many rows with issues
+
+    If `max_rows` is passed, only the first `max_rows` rows (including the header) are checked
+
+    If `sample_every` is passed, only every Nth row is checked. If `sample_fraction` is passed, only a random sample
+    of that fraction of the rows is checked, `sample_seed` makes the sample the same every time. The whole file is
+    still parsed when sampling, and the header is always checked
+
+    `valid_rows_output_path` and `rejected_rows_output_path` can't be used with `max_issues`, `max_rows` or sampling,
+    as the rows that weren't checked would be missing from them
+
+    If `valid_rows_output_format` is "normalised-csv", the valid rows are written as UTF-8 RFC 4180 CSV, with the
+    cells only quoted when necessary. Only possible with a single ASCII character delimiter
+
+    If `valid_rows_output_format` is "jsonl", each valid row is written as a JSON object keyed by the header's names,
+    with the quotes unescaped
+
+    If `valid_rows_output_format` is "parquet", the valid rows are written as a Parquet file with columns named after
+    the header. `valid_rows_output_schema`, any object implementing `__arrow_c_schema__` such as a `pyarrow.Schema`,
+    sets the column types of the Parquet file
+
+    If `rejected_rows_output_path` is passed, the rows that aren't valid are written to that path as they appear in
+    the file
+
+    If `strict` is set, the whole file is also parsed strictly according to RFC 4180, records ending with CRLF or LF.
+    Every deviation is reported with its byte position, along with the rows the strict and lenient parsers read
+    differently. Only possible with an ASCII compatible encoding and delimiter, and without skipping lines
+
+    If `skip_lines` is passed, that many lines before the header are skipped, e.g. a report title. If `detect_preamble`
+    is set, the lines before the first row with the number of columns most of the first rows have are skipped instead.
+    If `comment_prefix` is passed, lines starting with it are skipped as comments. Line numbers start from the header
+    after the skipped lines, so the same options must be passed to `get_rows`
+
+    If `footer_rows` is passed, summary rows such as `Total,,1234` in the last `footer_rows` rows are reported as
+    `footer_rows` rather than as rows with too few columns. The trailing rows with a cell matching one of the
+    `footer_patterns` regular expressions or `footer_record_count` are footer rows. If `footer_short_rows` is set, the
+    trailing rows with fewer columns than the header are footer rows too, which hides a truncated last record. At
+    least one of them must be passed.
+    `footer_record_count` is a regular expression matching the number of records declared in the footer, e.g.
+    `Record count: (\\d+)`, its first group being the number. The number is compared to the number of valid rows, so
+    it can't be used with sampling. Footer rows aren't detected if checking stops early
+
+    If `fixed_width` is passed, the file is read as fixed-width columns rather than cells separated by the delimiter,
+    given as a list of widths (e.g. `[10, 20, 8]`) or a string of widths or `start:length` positions (e.g.
+    `"0:10,12:20"`). The padding around the values is trimmed, and lines ending early or with text after the last
+    column have too few or too many columns. The valid and rejected rows are written as CSV with the delimiter
+
+    If `progress_callback` is passed, it is called with the progress at most every 100ms while checking, and once more
+    when all the rows were checked. An exception raised by the callback stops the check and is re-raised
+
+    The GIL is released while checking, so other threads can run. A KeyboardInterrupt stops the check
+    """
+
+def check_sheet(
+    path: str,
+    sheet: str | None = None,
+    valid_rows_output_path: str | None = None,
+    duplicate_key_columns: list[int] | None = None,
+    deduplicate: Literal["first", "last"] | None = None,
+    replace_invisible_characters: str | None = None,
+    repair_mojibake: bool = False,
+    neutralise_formulas: bool = False,
+    formula_allowlist: str | None = None,
+    summary_examples: int | None = None,
+    max_issues: int | None = None,
+    max_rows: int | None = None,
+    valid_rows_output_format: Literal["csv", "normalised-csv", "jsonl"] = "csv",
+    rejected_rows_output_path: str | None = None,
+    footer_rows: int | None = None,
+    footer_patterns: list[str] | None = None,
+    footer_record_count: str | None = None,
+    footer_short_rows: bool = False,
+) -> CSVDetails:
+    """
+    Check the sheet named `sheet` of the spreadsheet (xlsx, xlsm, xlsb, xls or ods) located at `path`, the first
+    sheet if not passed
+
+    The rows of the sheet are checked like the rows of a CSV file, with trailing empty cells not counted as columns,
+    so a row with empty cells at the end has too few columns, and dates written as ISO 8601. The valid and rejected rows are written as CSV, and the other options are the
+    same as `check_file`'s
+    """
+
+def get_rows(
+    path: str,
+    delimiter: str | Dialect,
+    encoding: str | None = None,
+    row_numbers: Iterable[int] | None = None,
+    trim_whitespace_outside_quotes: bool = False,
+    decode: bool = False,
+    skip_lines: int = 0,
+    comment_prefix: str | None = None,
+    fixed_width: str | list[int] | None = None,
+) -> list[tuple[int, list[str]]]:
+    """
+    Returns all the rows in the file in `row_numbers`, a RowSet such as `CSVDetails.valid_rows` or any iterable of
+    line numbers. `row_numbers` has to be passed, a TypeError is raised otherwise. The GIL is released while reading
+    the file
+
+    `delimiter` and `encoding` are the same as for `check_file`, `encoding` being the dialect's if None
+
+    If `trim_whitespace_outside_quotes` is set, whitespace between a delimiter and a quote is ignored when parsing
+
+    `skip_lines` and `comment_prefix` skip lines as in `check_file`, `skip_lines` being the length of the `preamble`
+    when it was detected. A fixed-width file is read with the same `fixed_width` as in `check_file`
+
+    The cells are returned as they are in the file, including any quotes. If `decode` is set, the quotes around quoted
+    cells are removed and doubled quotes unescaped instead, e.g. `"say ""hi"""` is returned as `say "hi"`. Escaped
+    characters are unescaped too in a dialect with an escape character
+    """
+
+def iter_rows(
+    path: str,
+    delimiter: str | Dialect,
+    encoding: str | None = None,
+    row_numbers: Iterable[int] | None = None,
+    row_ranges: list[range] | None = None,
+    trim_whitespace_outside_quotes: bool = False,
+    decode: bool = False,
+    skip_lines: int = 0,
+    comment_prefix: str | None = None,
+    fixed_width: str | list[int] | None = None,
+) -> RowIterator:
+    """
+    Returns a lazy iterator over the rows in the file, reading the file as the rows are requested
+
+    If `row_numbers` (a RowSet or any iterable of line numbers) or `row_ranges` are passed, only the rows in
+    `row_numbers` or in any of the ascending ranges are yielded, and the file is closed as soon as there are no more
+    rows to yield
+
+    If `trim_whitespace_outside_quotes` is set, whitespace between a delimiter and a quote is ignored when parsing
+
+    If `decode` is set, the cells are unquoted and unescaped as in `get_rows`
+
+    `skip_lines`, `comment_prefix` and `fixed_width` are the same as for `get_rows`
+    """
+
+def read_valid_rows(
+    details: CSVDetails,
+    path: str,
+    delimiter: str | Dialect,
+    encoding: str | None = None,
+    schema: Any | None = None,
+    batch_size: int = 65536,
+    trim_whitespace_outside_quotes: bool = False,
+    deduplicate: Literal["first", "last"] | None = None,
+    replace_invisible_characters: str | None = None,
+    repair_mojibake: bool = False,
+    neutralise_formulas: bool = False,
+    formula_allowlist: str | None = None,
+    skip_lines: int = 0,
+    comment_prefix: str | None = None,
+    fixed_width: str | list[int] | None = None,
+) -> ValidRowsStream:
+    """
+    Returns a stream of the valid rows of the file located at `path` that was checked into `details`, as Arrow record
+    batches of `batch_size` rows, without the header
+
+    The header gives the column names, and all the columns are strings unless a `schema` (any object implementing
+    `__arrow_c_schema__`, such as a `pyarrow.Schema`) with a field per column is passed. The values are then converted
+    to the field types, and empty cells in non-string columns are null
+
+    The other options are the same as for `check_file`, and must be the same as when the file was checked. The details
+    of a file checked in summary mode can only be used if `valid_rows_output_path` was passed
+    """
+
+def diff_files(
+    old_path: str,
+    new_path: str,
+    delimiter: str | Dialect,
+    encoding: str | None = None,
+    key_columns: list[int] | None = None,
+    trim_whitespace_outside_quotes: bool = False,
+    skip_lines: int = 0,
+    comment_prefix: str | None = None,
+    fixed_width: str | list[int] | None = None,
+) -> FileDiff:
+    """
+    Compare the file located at `new_path` to the one located at `old_path`, both read with the same options as
+    `get_rows`
+
+    If `key_columns` is passed, the rows with the same values in those columns are matched, in the order they appear
+    in if several rows have the same key. Otherwise the rows with the same line number are matched. Rows are compared
+    on the values of their cells, so a cell quoted differently isn't a change
+
+    Both files are also checked, with `key_columns` as the `duplicate_key_columns`, to compare their issues. Each file
+    is read once while it is checked, only keeping a hash of the values of each row of the old file (and its key) in
+    memory, so rows with the same hash are taken to be the same
+    """
+
+def compare_to_baseline(
+    details: CSVDetails,
+    baseline_path: str,
+    tolerance: int | str = 0,
+    issue_tolerances: dict[str, int | str] | None = None,
+) -> BaselineComparison:
+    """
+    Compare the issue counts of `details` to a baseline saved with `CSVDetails.save_baseline`
+
+    `tolerance` is how much an issue count can go up compared to the baseline before it is a regression, either a
+    number of occurrences or a percentage of the baseline count such as `"5%"`. `issue_tolerances` overrides it for
+    some issues, keyed by the name of the issue as in `CSVDetails.issue_counts`
+
+    A partial check (see `CSVDetails.partial`) can't be compared to the baseline of a full check, or the other way
+    around, as the counts of a part of the file aren't those of the whole file
+    """
+
+async def check_file_async(
+    path: str,
+    delimiter: str | Dialect,
+    encoding: str | None = None,
+    valid_rows_output_path: str | None = None,
+    duplicate_key_columns: list[int] | None = None,
+    deduplicate: Literal["first", "last"] | None = None,
+    trim_whitespace_outside_quotes: bool = False,
+    replace_invisible_characters: str | None = None,
+    repair_mojibake: bool = False,
+    neutralise_formulas: bool = False,
+    formula_allowlist: str | None = None,
+    summary_examples: int | None = None,
+    max_issues: int | None = None,
+    max_rows: int | None = None,
+    sample_every: int | None = None,
+    sample_fraction: float | None = None,
+    sample_seed: int | None = None,
+    progress_callback: Callable[[Progress], None] | None = None,
+    valid_rows_output_format: Literal["csv", "normalised-csv", "jsonl", "parquet"] = "csv",
+    valid_rows_output_schema: Any | None = None,
+    rejected_rows_output_path: str | None = None,
+    strict: bool = False,
+    skip_lines: int = 0,
+    comment_prefix: str | None = None,
+    detect_preamble: bool = False,
+    footer_rows: int | None = None,
+    footer_patterns: list[str] | None = None,
+    footer_record_count: str | None = None,
+    footer_short_rows: bool = False,
+    fixed_width: str | list[int] | None = None,
+) -> CSVDetails:
+    """
+    Same as `check_file`, run on the default executor of the running event loop
+
+    `progress_callback` is called from the executor thread
     """
 
-def get_rows(path: str, delimiter: str, encoding: str, row_numbers: set[int]) -> list[tuple[int, list[str]]]:
+async def get_rows_async(
+    path: str,
+    delimiter: str | Dialect,
+    encoding: str | None = None,
+    row_numbers: Iterable[int] | None = None,
+    trim_whitespace_outside_quotes: bool = False,
+    decode: bool = False,
+    skip_lines: int = 0,
+    comment_prefix: str | None = None,
+    fixed_width: str | list[int] | None = None,
+) -> list[tuple[int, list[str]]]:
     """
-    Returns all the rows in the file in the `row_numbers` set
+    Same as `get_rows`, run on the default executor of the running event loop
     """
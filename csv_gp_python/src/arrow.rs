@@ -0,0 +1,117 @@
+use std::{ffi::CString, sync::Arc};
+
+use ::csv_gp::{
+    arrow::{read_valid_rows_arrow, ArrowOptions, ValidRowsReader},
+    valid_file::{Keep, ValidFileOptions},
+};
+use arrow_array::ffi_stream::FFI_ArrowArrayStream;
+use arrow_schema::{ffi::FFI_ArrowSchema, Schema};
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyCapsule};
+use regex::Regex;
+
+use crate::{
+    dialect::{parse_options, DialectArg, FixedWidthArg},
+    PyCSVDetails, PyCSVError,
+};
+
+/// An exported Arrow C stream, owned by the capsule until a consumer moves it out
+#[repr(transparent)]
+struct ArrayStream(FFI_ArrowArrayStream);
+
+// SAFETY: the stream only wraps a `ValidRowsReader`, which is `Send`
+unsafe impl Send for ArrayStream {}
+
+/// Imports a schema from an object implementing the Arrow PyCapsule interface, such as a `pyarrow.Schema`
+pub(crate) fn import_schema(schema: &PyAny) -> PyResult<Schema> {
+    let capsule: &PyCapsule = schema
+        .call_method0("__arrow_c_schema__")?
+        .downcast()
+        .map_err(PyErr::from)?;
+
+    // SAFETY: `arrow_schema` capsules contain an `ArrowSchema`, which stays owned by the capsule
+    let ffi_schema = unsafe { &*(capsule.pointer() as *const FFI_ArrowSchema) };
+
+    Schema::try_from(ffi_schema).map_err(|e| PyCSVError(e.into()).into())
+}
+
+/// Stream of the valid rows of a file as Arrow record batches, which can be read once through the Arrow PyCapsule
+/// interface, e.g. by `pyarrow.RecordBatchReader.from_stream` or `polars.DataFrame`
+#[pyclass(name = "ValidRowsStream", module = "csv_gp")]
+pub(crate) struct PyValidRowsStream(Option<ValidRowsReader>);
+
+#[pymethods]
+impl PyValidRowsStream {
+    #[pyo3(signature = (requested_schema=None))]
+    fn __arrow_c_stream__(
+        &mut self,
+        py: Python<'_>,
+        requested_schema: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        // the requested schema is only a hint, the schema is given when creating the stream
+        let _ = requested_schema;
+
+        let reader = self
+            .0
+            .take()
+            .ok_or_else(|| PyValueError::new_err("the stream was already read"))?;
+        let stream = ArrayStream(FFI_ArrowArrayStream::new(Box::new(reader)));
+        let name = CString::new("arrow_array_stream").expect("name has no nul bytes");
+
+        Ok(PyCapsule::new(py, stream, Some(name))?.into())
+    }
+}
+
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+#[pyo3(signature = (details, path, delimiter, encoding=None, schema=None, batch_size=65536, trim_whitespace_outside_quotes=false, deduplicate=None, replace_invisible_characters=None, repair_mojibake=false, neutralise_formulas=false, formula_allowlist=None, skip_lines=0, comment_prefix=None, fixed_width=None))]
+pub(crate) fn read_valid_rows(
+    details: PyRef<'_, PyCSVDetails>,
+    path: String,
+    delimiter: DialectArg,
+    encoding: Option<String>,
+    schema: Option<&PyAny>,
+    batch_size: usize,
+    trim_whitespace_outside_quotes: bool,
+    deduplicate: Option<&str>,
+    replace_invisible_characters: Option<String>,
+    repair_mojibake: bool,
+    neutralise_formulas: bool,
+    formula_allowlist: Option<&str>,
+    skip_lines: usize,
+    comment_prefix: Option<String>,
+    fixed_width: Option<FixedWidthArg>,
+) -> PyResult<PyValidRowsStream> {
+    let dialect = delimiter.resolve(encoding, trim_whitespace_outside_quotes);
+    let options = ValidFileOptions {
+        deduplicate: deduplicate
+            .map(str::parse::<Keep>)
+            .transpose()
+            .map_err(|e| PyCSVError(e.into()))?,
+        replace_invisible_characters,
+        repair_mojibake,
+        neutralise_formulas,
+        formula_allowlist: formula_allowlist
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| PyCSVError(e.into()))?,
+        ..Default::default()
+    };
+    let arrow_options = ArrowOptions {
+        schema: schema.map(import_schema).transpose()?.map(Arc::new),
+        batch_size,
+        ..Default::default()
+    };
+
+    let reader = read_valid_rows_arrow(
+        path,
+        &details.0,
+        &dialect.delimiter,
+        &dialect.encoding,
+        &parse_options(&dialect, skip_lines, comment_prefix, fixed_width)?,
+        &options,
+        &arrow_options,
+    )
+    .map_err(PyCSVError)?;
+
+    Ok(PyValidRowsStream(Some(reader)))
+}
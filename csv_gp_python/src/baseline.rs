@@ -0,0 +1,80 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use ::csv_gp::baseline::{self, Baseline, Comparison, Tolerance, Tolerances};
+use pyo3::prelude::*;
+
+use crate::{PyCSVDetails, PyCSVError};
+
+/// A tolerance, either a number of occurrences or a string as accepted by the CLI, e.g. `"5%"`
+#[derive(FromPyObject)]
+pub(crate) enum ToleranceArg {
+    Count(usize),
+    Spec(String),
+}
+
+impl TryFrom<ToleranceArg> for Tolerance {
+    type Error = PyCSVError;
+
+    fn try_from(value: ToleranceArg) -> Result<Self, Self::Error> {
+        match value {
+            ToleranceArg::Count(count) => Ok(Tolerance::Count(count)),
+            ToleranceArg::Spec(spec) => spec.parse::<Tolerance>().map_err(|e| PyCSVError(e.into())),
+        }
+    }
+}
+
+#[pyclass(name = "BaselineComparison", module = "csv_gp")]
+pub(crate) struct PyComparison(Comparison);
+
+#[pymethods]
+impl PyComparison {
+    #[getter]
+    fn deltas(&self) -> HashMap<String, (usize, usize)> {
+        self.0
+            .deltas
+            .iter()
+            .map(|delta| (delta.issue.clone(), (delta.baseline, delta.current)))
+            .collect()
+    }
+
+    #[getter]
+    fn regressions(&self) -> Vec<String> {
+        self.0
+            .regressions()
+            .map(|delta| delta.issue.clone())
+            .collect()
+    }
+
+    #[getter]
+    fn has_regressions(&self) -> bool {
+        self.0.has_regressions()
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (details, baseline_path, tolerance=ToleranceArg::Count(0), issue_tolerances=None))]
+pub(crate) fn compare_to_baseline(
+    details: &PyCSVDetails,
+    baseline_path: PathBuf,
+    tolerance: ToleranceArg,
+    issue_tolerances: Option<HashMap<String, ToleranceArg>>,
+) -> PyResult<PyComparison> {
+    let tolerances = Tolerances {
+        default: tolerance.try_into()?,
+        issues: issue_tolerances
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(issue, tolerance)| Ok((issue, tolerance.try_into()?)))
+            .collect::<Result<_, PyCSVError>>()?,
+    };
+    let baseline = Baseline::load(baseline_path).map_err(PyCSVError)?;
+
+    let comparison = baseline::compare_to_baseline(&details.0, &baseline, &tolerances)
+        .map_err(|e| PyCSVError(e.into()))?;
+
+    Ok(PyComparison(comparison))
+}
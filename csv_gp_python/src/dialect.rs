@@ -0,0 +1,183 @@
+// the code pyo3 0.19 generates for `#[new]` trips this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use ::csv_gp::{
+    dialect::{Dialect, PRESETS},
+    fixed_width::ColumnSpec,
+    parser::ParseOptions,
+};
+use pyo3::{prelude::*, pyclass::CompareOp};
+
+use crate::PyCSVError;
+
+/// The settings needed to read files written in a particular way, usable in place of a delimiter
+#[pyclass(name = "Dialect", module = "csv_gp")]
+#[derive(Clone)]
+pub(crate) struct PyDialect(pub(crate) Dialect);
+
+#[pymethods]
+impl PyDialect {
+    #[new]
+    #[pyo3(signature = (delimiter=",", encoding="utf-8", quoting=true, escape=None, trim_whitespace_outside_quotes=false))]
+    fn new(
+        delimiter: &str,
+        encoding: &str,
+        quoting: bool,
+        escape: Option<char>,
+        trim_whitespace_outside_quotes: bool,
+    ) -> Result<Self, PyCSVError> {
+        let dialect = Dialect {
+            delimiter: delimiter.into(),
+            encoding: encoding.into(),
+            quoting,
+            escape,
+            trim_whitespace_outside_quotes,
+        };
+        dialect.validate().map_err(|e| PyCSVError(e.into()))?;
+
+        Ok(Self(dialect))
+    }
+
+    /// The built-in dialect with this name
+    #[staticmethod]
+    fn preset(name: &str) -> Result<Self, PyCSVError> {
+        Ok(Self(
+            Dialect::preset(name).map_err(|e| PyCSVError(e.into()))?,
+        ))
+    }
+
+    /// Names of the built-in dialects
+    #[staticmethod]
+    fn presets() -> Vec<&'static str> {
+        PRESETS.to_vec()
+    }
+
+    /// Reads a dialect from a TOML dialect file
+    #[staticmethod]
+    fn from_file(path: &str) -> Result<Self, PyCSVError> {
+        Ok(Self(Dialect::from_file(path)?))
+    }
+
+    #[getter]
+    fn delimiter(&self) -> &str {
+        &self.0.delimiter
+    }
+
+    #[getter]
+    fn encoding(&self) -> &str {
+        &self.0.encoding
+    }
+
+    #[getter]
+    fn quoting(&self) -> bool {
+        self.0.quoting
+    }
+
+    #[getter]
+    fn escape(&self) -> Option<char> {
+        self.0.escape
+    }
+
+    #[getter]
+    fn trim_whitespace_outside_quotes(&self) -> bool {
+        self.0.trim_whitespace_outside_quotes
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python<'_>) -> PyObject {
+        match op {
+            CompareOp::Eq => (self.0 == other.0).into_py(py),
+            CompareOp::Ne => (self.0 != other.0).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Dialect(delimiter={:?}, encoding={:?}, quoting={}, escape={}, trim_whitespace_outside_quotes={})",
+            self.0.delimiter,
+            self.0.encoding,
+            if self.0.quoting { "True" } else { "False" },
+            self.0
+                .escape
+                .map_or("None".into(), |e| format!("{:?}", e.to_string())),
+            if self.0.trim_whitespace_outside_quotes {
+                "True"
+            } else {
+                "False"
+            },
+        )
+    }
+}
+
+/// Either a delimiter or a whole dialect, as accepted by the functions reading a file
+pub(crate) enum DialectArg {
+    Dialect(PyDialect),
+    Delimiter(String),
+}
+
+impl<'source> FromPyObject<'source> for DialectArg {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        // anything that isn't a dialect fails as a delimiter, which gives the clearer error
+        match ob.extract::<PyDialect>() {
+            Ok(dialect) => Ok(Self::Dialect(dialect)),
+            Err(_) => Ok(Self::Delimiter(ob.extract()?)),
+        }
+    }
+}
+
+impl DialectArg {
+    /// The dialect to read a file with, `encoding` overrides the dialect's and is UTF-8 for a plain delimiter if not
+    /// given. The whitespace outside quotes is trimmed if either the dialect or `trim_whitespace_outside_quotes` says so.
+    pub(crate) fn resolve(
+        self,
+        encoding: Option<String>,
+        trim_whitespace_outside_quotes: bool,
+    ) -> Dialect {
+        let mut dialect = match self {
+            Self::Dialect(dialect) => dialect.0,
+            Self::Delimiter(delimiter) => Dialect {
+                delimiter,
+                ..Default::default()
+            },
+        };
+        if let Some(encoding) = encoding {
+            dialect.encoding = encoding;
+        }
+        dialect.trim_whitespace_outside_quotes |= trim_whitespace_outside_quotes;
+
+        dialect
+    }
+}
+
+/// The columns of a fixed-width file, either a list of widths or a string as accepted by the CLI
+#[derive(FromPyObject)]
+pub(crate) enum FixedWidthArg {
+    Spec(String),
+    Widths(Vec<usize>),
+}
+
+/// Options to parse a file in `dialect`, skipping `skip_lines` lines before the header and the lines starting with
+/// `comment_prefix`. A fixed-width file is read with `fixed_width` columns rather than the dialect.
+pub(crate) fn parse_options(
+    dialect: &Dialect,
+    skip_lines: usize,
+    comment_prefix: Option<String>,
+    fixed_width: Option<FixedWidthArg>,
+) -> Result<ParseOptions, PyCSVError> {
+    let base = match fixed_width {
+        Some(FixedWidthArg::Spec(spec)) => ParseOptions::fixed_width(
+            spec.parse::<ColumnSpec>()
+                .map_err(|e| PyCSVError(e.into()))?,
+        ),
+        Some(FixedWidthArg::Widths(widths)) => ParseOptions::fixed_width(
+            ColumnSpec::from_widths(&widths).map_err(|e| PyCSVError(e.into()))?,
+        ),
+        None => dialect.parse_options(),
+    };
+
+    Ok(ParseOptions {
+        skip_lines,
+        comment_prefix,
+        ..base
+    })
+}
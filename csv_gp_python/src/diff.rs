@@ -0,0 +1,148 @@
+use std::{collections::HashMap, sync::Arc};
+
+use ::csv_gp::{
+    csv_details::CSVDetails,
+    diff::{self, DiffOptions, FileDiff, RowChange},
+};
+use pyo3::prelude::*;
+
+use crate::{
+    dialect::{parse_options, DialectArg, FixedWidthArg},
+    PyCSVDetails, PyCSVError,
+};
+
+#[pyclass(name = "FileDiff", module = "csv_gp")]
+pub(crate) struct PyFileDiff {
+    diff: FileDiff,
+    old_details: Arc<CSVDetails>,
+    new_details: Arc<CSVDetails>,
+}
+
+impl PyFileDiff {
+    fn new(diff: FileDiff) -> Self {
+        Self {
+            old_details: Arc::new(diff.old_details.clone()),
+            new_details: Arc::new(diff.new_details.clone()),
+            diff,
+        }
+    }
+}
+
+#[pymethods]
+impl PyFileDiff {
+    #[getter]
+    fn added_rows(&self) -> Vec<usize> {
+        self.diff.added_rows.iter().collect()
+    }
+
+    #[getter]
+    fn removed_rows(&self) -> Vec<usize> {
+        self.diff.removed_rows.iter().collect()
+    }
+
+    #[getter]
+    fn changed_rows(&self) -> Vec<PyRowChange> {
+        self.diff
+            .changed_rows
+            .iter()
+            .cloned()
+            .map(PyRowChange)
+            .collect()
+    }
+
+    #[getter]
+    fn unchanged_row_count(&self) -> usize {
+        self.diff.unchanged_row_count
+    }
+
+    #[getter]
+    fn issue_changes(&self) -> HashMap<&'static str, (usize, usize)> {
+        self.diff
+            .issue_changes
+            .iter()
+            .map(|change| (change.issue, (change.old, change.new)))
+            .collect()
+    }
+
+    #[getter]
+    fn old_details(&self) -> PyCSVDetails {
+        PyCSVDetails(self.old_details.clone())
+    }
+
+    #[getter]
+    fn new_details(&self) -> PyCSVDetails {
+        PyCSVDetails(self.new_details.clone())
+    }
+
+    #[pyo3(signature = (examples=10))]
+    fn report(&self, examples: usize) -> String {
+        self.diff.report(examples)
+    }
+
+    fn __bool__(&self) -> bool {
+        !self.diff.is_empty()
+    }
+}
+
+#[pyclass(name = "RowChange", module = "csv_gp")]
+pub(crate) struct PyRowChange(RowChange);
+
+#[pymethods]
+impl PyRowChange {
+    #[getter]
+    fn old_row(&self) -> usize {
+        self.0.old_row
+    }
+
+    #[getter]
+    fn new_row(&self) -> usize {
+        self.0.new_row
+    }
+
+    #[getter]
+    fn cells(&self) -> Vec<(usize, Option<String>, Option<String>)> {
+        self.0
+            .cells
+            .iter()
+            .map(|change| (change.column, change.old.clone(), change.new.clone()))
+            .collect()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("RowChange({})", self.0)
+    }
+}
+
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+#[pyo3(signature = (old_path, new_path, delimiter, encoding=None, key_columns=None, trim_whitespace_outside_quotes=false, skip_lines=0, comment_prefix=None, fixed_width=None))]
+pub(crate) fn diff_files(
+    py: Python<'_>,
+    old_path: String,
+    new_path: String,
+    delimiter: DialectArg,
+    encoding: Option<String>,
+    key_columns: Option<Vec<usize>>,
+    trim_whitespace_outside_quotes: bool,
+    skip_lines: usize,
+    comment_prefix: Option<String>,
+    fixed_width: Option<FixedWidthArg>,
+) -> PyResult<PyFileDiff> {
+    let dialect = delimiter.resolve(encoding, trim_whitespace_outside_quotes);
+    let options = DiffOptions {
+        parse: parse_options(&dialect, skip_lines, comment_prefix, fixed_width)?,
+        key_columns,
+    };
+
+    let result = py.allow_threads(|| {
+        diff::diff_files(
+            old_path,
+            new_path,
+            &dialect.delimiter,
+            &dialect.encoding,
+            &options,
+        )
+    });
+
+    Ok(PyFileDiff::new(result.map_err(PyCSVError)?))
+}
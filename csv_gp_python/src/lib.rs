@@ -1,18 +1,47 @@
-use std::collections::HashSet;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
-use ::csv_gp::{csv_details::CSVDetails, error::CSVError};
+use ::csv_gp::{
+    baseline::Baseline,
+    checker::{check_file_with_hooks, CheckOptions},
+    csv_details::CSVDetails,
+    error::{CSVError, InvalidOption},
+    footer::FooterOptions,
+    partial::{PartialCheck, Sample},
+    progress::{CancellationToken, CheckHooks, Progress},
+    strict::{Finding, StrictCheck},
+    valid_file::{Keep, OutputFormat, ValidFileOptions},
+};
+use dialect::{parse_options, DialectArg, FixedWidthArg, PyDialect};
 use error::until_err;
-use pyo3::{create_exception, exceptions::PyValueError, prelude::*};
+use pyo3::{
+    create_exception,
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyDict, PyTuple},
+};
 
+use regex::Regex;
+use rows::{cell_text, iter_rows, PyRowIterator};
+use views::{PyColumnCounts, PyRowSet, RowNumbers};
+
+#[cfg(feature = "arrow")]
+mod arrow;
+mod baseline;
+mod dialect;
+mod diff;
 mod error;
+mod rows;
+#[cfg(feature = "excel")]
+mod spreadsheet;
+mod views;
 
 // Results struct wrapper
 #[pyclass(name = "CSVDetails", module = "csv_gp")]
-struct PyCSVDetails(CSVDetails);
+struct PyCSVDetails(Arc<CSVDetails>);
 
 impl PyCSVDetails {
     fn new(csv_details: CSVDetails) -> Self {
-        Self(csv_details)
+        Self(Arc::new(csv_details))
     }
 }
 
@@ -34,64 +63,313 @@ impl PyCSVDetails {
     }
 
     #[getter]
-    fn column_count_per_line(&self) -> Vec<usize> {
-        self.0.column_count_per_line.clone()
+    fn invisible_character_counts(&self) -> HashMap<&'static str, usize> {
+        self.0
+            .invisible_characters
+            .iter()
+            .map(|(category, findings)| (category.name(), findings.count))
+            .collect()
+    }
+
+    #[getter]
+    fn invisible_character_locations(&self) -> HashMap<&'static str, Vec<(usize, usize)>> {
+        self.0
+            .invisible_characters
+            .iter()
+            .map(|(category, findings)| (category.name(), findings.locations.iter().collect()))
+            .collect()
+    }
+
+    #[getter]
+    fn mojibake(&self) -> HashMap<(&'static str, &'static str), PyRowSet> {
+        self.0
+            .mojibake
+            .keys()
+            .map(|&encodings| {
+                let rows = PyRowSet::new(&self.0, move |d| &d.mojibake[&encodings]);
+                ((encodings.encoded_as, encodings.decoded_as), rows)
+            })
+            .collect()
+    }
+
+    #[getter]
+    fn formula_injection(&self) -> Vec<(usize, usize)> {
+        self.0.formula_injection.iter().collect()
+    }
+
+    #[getter]
+    fn formula_injection_count(&self) -> usize {
+        self.0.formula_injection.len()
+    }
+
+    #[getter]
+    fn column_count_per_line(&self) -> PyColumnCounts {
+        PyColumnCounts::new(&self.0)
+    }
+
+    #[getter]
+    fn too_few_columns(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.too_few_columns)
+    }
+
+    #[getter]
+    fn too_many_columns(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.too_many_columns)
+    }
+
+    #[getter]
+    fn quoted_delimiter(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.quoted_delimiter)
+    }
+
+    #[getter]
+    fn quoted_newline(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.quoted_newline)
+    }
+
+    #[getter]
+    fn quoted_quote(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.quoted_quote)
+    }
+
+    #[getter]
+    fn quoted_quote_correctly(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.quoted_quote_correctly)
+    }
+
+    #[getter]
+    fn incorrect_cell_quote(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.incorrect_cell_quote)
+    }
+
+    #[getter]
+    fn whitespace_padding(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.whitespace_padding)
+    }
+
+    #[getter]
+    fn whitespace_outside_quotes(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.whitespace_outside_quotes)
     }
 
     #[getter]
-    fn too_few_columns(&self) -> Vec<usize> {
-        self.0.too_few_columns.clone()
+    fn all_empty_rows(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.all_empty_rows)
     }
 
     #[getter]
-    fn too_many_columns(&self) -> Vec<usize> {
-        self.0.too_many_columns.clone()
+    fn blank_rows(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.blank_rows)
     }
 
     #[getter]
-    fn quoted_delimiter(&self) -> Vec<usize> {
-        self.0.quoted_delimiter.clone()
+    fn valid_rows(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.valid_rows)
     }
 
     #[getter]
-    fn quoted_newline(&self) -> Vec<usize> {
-        self.0.quoted_newline.clone()
+    fn duplicate_rows(&self) -> Vec<Vec<usize>> {
+        self.0.duplicate_rows.clone()
     }
 
     #[getter]
-    fn quoted_quote(&self) -> Vec<usize> {
-        self.0.quoted_quote.clone()
+    fn duplicate_key_rows(&self) -> Option<Vec<Vec<usize>>> {
+        self.0.duplicate_key_rows.clone()
     }
 
     #[getter]
-    fn quoted_quote_correctly(&self) -> Vec<usize> {
-        self.0.quoted_quote_correctly.clone()
+    fn example_limit(&self) -> Option<usize> {
+        self.0.example_limit
     }
 
     #[getter]
-    fn incorrect_cell_quote(&self) -> Vec<usize> {
-        self.0.incorrect_cell_quote.clone()
+    fn partial(&self) -> Option<PyPartialCheck> {
+        self.0.partial.clone().map(PyPartialCheck)
     }
 
     #[getter]
-    fn all_empty_rows(&self) -> Vec<usize> {
-        self.0.all_empty_rows.clone()
+    fn strict(&self) -> Option<PyStrictCheck> {
+        self.0
+            .strict
+            .is_some()
+            .then(|| PyStrictCheck(self.0.clone()))
     }
 
     #[getter]
-    fn blank_rows(&self) -> Vec<usize> {
-        self.0.blank_rows.clone()
+    fn preamble(&self) -> Vec<String> {
+        self.0.skipped_lines.preamble.clone()
     }
 
     #[getter]
-    fn valid_rows(&self) -> HashSet<usize> {
-        self.0.valid_rows.clone()
+    fn comment_lines(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.skipped_lines.comments)
+    }
+
+    #[getter]
+    fn footer_rows(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| &d.footer.rows)
+    }
+
+    #[getter]
+    fn declared_record_count(&self) -> Option<usize> {
+        self.0.footer.declared_record_count
+    }
+
+    #[getter]
+    fn record_count_mismatch(&self) -> bool {
+        self.0.footer.record_count_mismatch()
     }
 
     #[getter]
     fn header_messed_up(&self) -> bool {
         self.0.header_messed_up()
     }
+
+    #[getter]
+    fn issue_counts(&self) -> HashMap<&'static str, usize> {
+        self.0.issue_counts().into_iter().collect()
+    }
+
+    fn save_baseline(&self, path: PathBuf) -> PyResult<()> {
+        Ok(Baseline::from_details(&self.0)
+            .save(path)
+            .map_err(PyCSVError)?)
+    }
+}
+
+#[pyclass(name = "PartialCheck", module = "csv_gp")]
+struct PyPartialCheck(PartialCheck);
+
+#[pymethods]
+impl PyPartialCheck {
+    #[getter]
+    fn reason(&self) -> &'static str {
+        self.0.reason.name()
+    }
+
+    #[getter]
+    fn rows_checked(&self) -> usize {
+        self.0.rows_checked
+    }
+
+    #[getter]
+    fn rows_read(&self) -> usize {
+        self.0.rows_read
+    }
+
+    fn extrapolate(&self, count: usize) -> Option<usize> {
+        self.0.extrapolate(count)
+    }
+}
+
+#[pyclass(name = "StrictCheck", module = "csv_gp")]
+struct PyStrictCheck(Arc<CSVDetails>);
+
+impl PyStrictCheck {
+    fn check(&self) -> &StrictCheck {
+        self.0
+            .strict
+            .as_ref()
+            .expect("only created for strict checks")
+    }
+}
+
+#[pymethods]
+impl PyStrictCheck {
+    #[getter]
+    fn findings(&self) -> Vec<PyStrictFinding> {
+        self.check()
+            .findings
+            .iter()
+            .copied()
+            .map(PyStrictFinding)
+            .collect()
+    }
+
+    #[getter]
+    fn counts(&self) -> HashMap<&'static str, usize> {
+        self.check()
+            .counts
+            .iter()
+            .map(|(violation, count)| (violation.name(), *count))
+            .collect()
+    }
+
+    #[getter]
+    fn finding_count(&self) -> usize {
+        self.check().finding_count()
+    }
+
+    #[getter]
+    fn record_count(&self) -> usize {
+        self.check().record_count
+    }
+
+    #[getter]
+    fn differing_rows(&self) -> PyRowSet {
+        PyRowSet::new(&self.0, |d| {
+            &d.strict
+                .as_ref()
+                .expect("only created for strict checks")
+                .differing_rows
+        })
+    }
+}
+
+#[pyclass(name = "StrictFinding", module = "csv_gp")]
+struct PyStrictFinding(Finding);
+
+#[pymethods]
+impl PyStrictFinding {
+    #[getter]
+    fn violation(&self) -> &'static str {
+        self.0.violation.name()
+    }
+
+    #[getter]
+    fn byte_offset(&self) -> u64 {
+        self.0.byte_offset
+    }
+
+    #[getter]
+    fn record(&self) -> usize {
+        self.0.record
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "StrictFinding({}, byte_offset={}, record={})",
+            self.0.violation.name(),
+            self.0.byte_offset,
+            self.0.record
+        )
+    }
+}
+
+#[pyclass(name = "Progress", module = "csv_gp")]
+struct PyProgress(Progress);
+
+#[pymethods]
+impl PyProgress {
+    #[getter]
+    fn bytes_read(&self) -> u64 {
+        self.0.bytes_read
+    }
+
+    #[getter]
+    fn total_bytes(&self) -> u64 {
+        self.0.total_bytes
+    }
+
+    #[getter]
+    fn rows_processed(&self) -> usize {
+        self.0.rows_processed
+    }
+
+    #[getter]
+    fn issues_found(&self) -> usize {
+        self.0.issues_found
+    }
 }
 
 // Error wrapper
@@ -115,44 +393,311 @@ impl From<CSVError> for PyCSVError {
     }
 }
 
+fn sample(
+    sample_every: Option<usize>,
+    sample_fraction: Option<f64>,
+    sample_seed: Option<u64>,
+) -> Result<Option<Sample>, InvalidOption> {
+    match (sample_every, sample_fraction) {
+        (Some(_), Some(_)) => Err(InvalidOption::Conflict {
+            option: "sample_every",
+            other: "sample_fraction",
+        }),
+        (Some(stride), None) => Ok(Some(Sample::Stride(stride))),
+        (None, Some(fraction)) => Ok(Some(Sample::Random {
+            fraction,
+            seed: sample_seed,
+        })),
+        (None, None) => Ok(None),
+    }
+}
+
+/// The format of the valid rows output, with the schema of a Parquet output
+fn output_format(format: &str, schema: Option<&PyAny>) -> PyResult<OutputFormat> {
+    let format = format
+        .parse::<OutputFormat>()
+        .map_err(|e| PyCSVError(e.into()))?;
+
+    match (format, schema) {
+        #[cfg(feature = "parquet")]
+        (OutputFormat::Parquet(mut arrow_options), Some(schema)) => {
+            arrow_options.schema = Some(Arc::new(arrow::import_schema(schema)?));
+            Ok(OutputFormat::Parquet(arrow_options))
+        }
+        (OutputFormat::Csv, Some(_)) => Err(PyCSVError(
+            InvalidOption::Conflict {
+                option: "valid_rows_output_schema",
+                other: "CSV output",
+            }
+            .into(),
+        )
+        .into()),
+        (format, _) => Ok(format),
+    }
+}
+
+fn footer_options(
+    last_rows: usize,
+    patterns: Option<Vec<&str>>,
+    short_rows: bool,
+    record_count_pattern: Option<&str>,
+) -> Result<FooterOptions, CSVError> {
+    Ok(FooterOptions {
+        last_rows,
+        patterns: patterns
+            .unwrap_or_default()
+            .into_iter()
+            .map(Regex::new)
+            .collect::<Result<_, _>>()?,
+        short_rows,
+        record_count_pattern: record_count_pattern.map(Regex::new).transpose()?,
+    })
+}
+
 #[pyfunction]
+#[allow(clippy::too_many_arguments)]
+#[pyo3(signature = (path, delimiter, encoding=None, valid_rows_output_path=None, duplicate_key_columns=None, deduplicate=None, trim_whitespace_outside_quotes=false, replace_invisible_characters=None, repair_mojibake=false, neutralise_formulas=false, formula_allowlist=None, summary_examples=None, max_issues=None, max_rows=None, sample_every=None, sample_fraction=None, sample_seed=None, progress_callback=None, valid_rows_output_format="csv", valid_rows_output_schema=None, rejected_rows_output_path=None, strict=false, skip_lines=0, comment_prefix=None, detect_preamble=false, footer_rows=None, footer_patterns=None, footer_record_count=None, footer_short_rows=false, fixed_width=None))]
 fn check_file(
+    py: Python<'_>,
     path: String,
-    delimiter: char,
-    encoding: &str,
+    delimiter: DialectArg,
+    encoding: Option<String>,
     valid_rows_output_path: Option<&str>,
-) -> Result<PyCSVDetails, PyCSVError> {
-    let result = ::csv_gp::checker::check_file(path, delimiter, encoding, valid_rows_output_path)?;
-    Ok(PyCSVDetails::new(result))
+    duplicate_key_columns: Option<Vec<usize>>,
+    deduplicate: Option<&str>,
+    trim_whitespace_outside_quotes: bool,
+    replace_invisible_characters: Option<String>,
+    repair_mojibake: bool,
+    neutralise_formulas: bool,
+    formula_allowlist: Option<&str>,
+    summary_examples: Option<usize>,
+    max_issues: Option<usize>,
+    max_rows: Option<usize>,
+    sample_every: Option<usize>,
+    sample_fraction: Option<f64>,
+    sample_seed: Option<u64>,
+    progress_callback: Option<PyObject>,
+    valid_rows_output_format: &str,
+    valid_rows_output_schema: Option<&PyAny>,
+    rejected_rows_output_path: Option<PathBuf>,
+    strict: bool,
+    skip_lines: usize,
+    comment_prefix: Option<String>,
+    detect_preamble: bool,
+    footer_rows: Option<usize>,
+    footer_patterns: Option<Vec<&str>>,
+    footer_record_count: Option<&str>,
+    footer_short_rows: bool,
+    fixed_width: Option<FixedWidthArg>,
+) -> PyResult<PyCSVDetails> {
+    let dialect = delimiter.resolve(encoding, trim_whitespace_outside_quotes);
+    let options = CheckOptions {
+        parse: parse_options(&dialect, skip_lines, comment_prefix, fixed_width)?,
+        duplicate_key_columns,
+        valid_file: ValidFileOptions {
+            deduplicate: deduplicate
+                .map(str::parse::<Keep>)
+                .transpose()
+                .map_err(|e| PyCSVError(e.into()))?,
+            replace_invisible_characters,
+            repair_mojibake,
+            neutralise_formulas,
+            formula_allowlist: formula_allowlist
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| PyCSVError(e.into()))?,
+            format: output_format(valid_rows_output_format, valid_rows_output_schema)?,
+            rejected_rows_path: rejected_rows_output_path,
+        },
+        summary_examples,
+        max_issues,
+        max_rows,
+        sample: sample(sample_every, sample_fraction, sample_seed)
+            .map_err(|e| PyCSVError(e.into()))?,
+        strict,
+        detect_preamble,
+        footer: footer_rows
+            .map(|last_rows| {
+                footer_options(
+                    last_rows,
+                    footer_patterns,
+                    footer_short_rows,
+                    footer_record_count,
+                )
+            })
+            .transpose()
+            .map_err(PyCSVError)?,
+    };
+
+    // The GIL is only taken back to report the progress, which is also when pending signals are handled, so that
+    // KeyboardInterrupt can stop the check
+    let cancellation = CancellationToken::new();
+    let mut callback_error = None;
+
+    let result = py.allow_threads(|| {
+        let hooks = CheckHooks {
+            on_progress: Some(Box::new(|progress: &Progress| {
+                Python::with_gil(|py| {
+                    let result = py.check_signals().and_then(|_| match &progress_callback {
+                        Some(callback) => callback.call1(py, (PyProgress(*progress),)).map(drop),
+                        None => Ok(()),
+                    });
+
+                    if let Err(e) = result {
+                        callback_error.get_or_insert(e);
+                        cancellation.cancel();
+                    }
+                })
+            })),
+            cancellation: Some(cancellation.clone()),
+            ..Default::default()
+        };
+
+        check_file_with_hooks(
+            path,
+            &dialect.delimiter,
+            &dialect.encoding,
+            valid_rows_output_path,
+            &options,
+            hooks,
+        )
+    });
+
+    if let Some(e) = callback_error {
+        return Err(e);
+    }
+
+    Ok(PyCSVDetails::new(result.map_err(PyCSVError)?))
 }
 
 #[pyfunction]
+#[pyo3(signature = (path, delimiter, encoding=None, row_numbers=None, trim_whitespace_outside_quotes=false, decode=false, skip_lines=0, comment_prefix=None, fixed_width=None))]
+#[allow(clippy::too_many_arguments)]
 fn get_rows(
+    py: Python<'_>,
     path: String,
-    delimiter: char,
-    encoding: &str,
-    row_numbers: HashSet<usize>,
-) -> Result<Vec<(usize, Vec<String>)>, PyCSVError> {
-    let lines = ::csv_gp::parser::parse_file(path, delimiter, encoding)?;
-
-    let mut err = Ok(());
-
-    let filtered = lines
-        .scan(&mut err, until_err)
-        .enumerate()
-        .filter(|(i, _)| row_numbers.contains(i))
-        .map(|(i, x)| (i, x.into_iter().map(|y| y.to_string()).collect()))
-        .collect();
-    err.map_err(Into::<CSVError>::into)?;
+    delimiter: DialectArg,
+    encoding: Option<String>,
+    row_numbers: Option<RowNumbers>,
+    trim_whitespace_outside_quotes: bool,
+    decode: bool,
+    skip_lines: usize,
+    comment_prefix: Option<String>,
+    fixed_width: Option<FixedWidthArg>,
+) -> PyResult<Vec<(usize, Vec<String>)>> {
+    // a default so that `encoding` can be left out as in the other functions, but the rows have to be chosen
+    let row_numbers = row_numbers.ok_or_else(|| {
+        PyTypeError::new_err("get_rows() missing required argument 'row_numbers'")
+    })?;
+    let dialect = delimiter.resolve(encoding, trim_whitespace_outside_quotes);
+    let options = parse_options(&dialect, skip_lines, comment_prefix, fixed_width)?;
+
+    let filtered = py
+        .allow_threads(|| -> Result<_, CSVError> {
+            let lines = ::csv_gp::parser::parse_file_with_options(
+                path,
+                &dialect.delimiter,
+                &dialect.encoding,
+                &options,
+            )?;
+
+            let mut err = Ok(());
+
+            let filtered = lines
+                .scan(&mut err, until_err)
+                .enumerate()
+                .filter(|(i, _)| row_numbers.0.contains(*i))
+                .map(|(i, x)| {
+                    let cells = x.iter().map(|y| cell_text(y, decode, &options)).collect();
+                    (i, cells)
+                })
+                .collect();
+            err?;
+
+            Ok(filtered)
+        })
+        .map_err(PyCSVError)?;
 
     Ok(filtered)
 }
 
+/// Calls `function` of this module with the arguments on the default executor of the running event loop,
+/// returning an awaitable of the result. The functions release the GIL, so they run alongside the event loop.
+fn run_in_executor<'py>(
+    module: &'py PyModule,
+    function: &str,
+    args: &PyTuple,
+    kwargs: Option<&PyDict>,
+) -> PyResult<&'py PyAny> {
+    let py = module.py();
+
+    let partial_args = PyTuple::new(
+        py,
+        [module.getattr(function)?]
+            .into_iter()
+            .chain(args.iter())
+            .collect::<Vec<_>>(),
+    );
+    let call = py
+        .import("functools")?
+        .getattr("partial")?
+        .call(partial_args, kwargs)?;
+
+    py.import("asyncio")?
+        .call_method0("get_running_loop")?
+        .call_method1("run_in_executor", (py.None(), call))
+}
+
+#[pyfunction]
+#[pyo3(pass_module, signature = (*args, **kwargs))]
+fn check_file_async<'py>(
+    module: &'py PyModule,
+    args: &PyTuple,
+    kwargs: Option<&PyDict>,
+) -> PyResult<&'py PyAny> {
+    run_in_executor(module, "check_file", args, kwargs)
+}
+
+#[pyfunction]
+#[pyo3(pass_module, signature = (*args, **kwargs))]
+fn get_rows_async<'py>(
+    module: &'py PyModule,
+    args: &PyTuple,
+    kwargs: Option<&PyDict>,
+) -> PyResult<&'py PyAny> {
+    run_in_executor(module, "get_rows", args, kwargs)
+}
+
 #[pymodule]
 fn csv_gp(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(check_file, m)?)?;
     m.add_function(wrap_pyfunction!(get_rows, m)?)?;
+    m.add_function(wrap_pyfunction!(iter_rows, m)?)?;
+    #[cfg(feature = "arrow")]
+    m.add_function(wrap_pyfunction!(arrow::read_valid_rows, m)?)?;
+    #[cfg(feature = "excel")]
+    m.add_function(wrap_pyfunction!(spreadsheet::check_sheet, m)?)?;
+    m.add_function(wrap_pyfunction!(diff::diff_files, m)?)?;
+    m.add_function(wrap_pyfunction!(baseline::compare_to_baseline, m)?)?;
+    m.add_function(wrap_pyfunction!(check_file_async, m)?)?;
+    m.add_function(wrap_pyfunction!(get_rows_async, m)?)?;
     m.add_class::<PyCSVDetails>()?;
+    m.add_class::<PyRowSet>()?;
+    py.import("collections.abc")?
+        .getattr("Set")?
+        .call_method1("register", (m.getattr("RowSet")?,))?;
+    m.add_class::<PyColumnCounts>()?;
+    m.add_class::<PyRowIterator>()?;
+    #[cfg(feature = "arrow")]
+    m.add_class::<arrow::PyValidRowsStream>()?;
+    m.add_class::<PyPartialCheck>()?;
+    m.add_class::<PyProgress>()?;
+    m.add_class::<PyStrictCheck>()?;
+    m.add_class::<PyStrictFinding>()?;
+    m.add_class::<PyDialect>()?;
+    m.add_class::<baseline::PyComparison>()?;
+    m.add_class::<diff::PyFileDiff>()?;
+    m.add_class::<diff::PyRowChange>()?;
     m.add("UnknownEncoding", py.get_type::<PyUnknownEncoding>())?;
     Ok(())
 }
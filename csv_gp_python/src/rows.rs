@@ -0,0 +1,191 @@
+use std::io;
+
+use ::csv_gp::{cell::Cell, error::CSVError, parser::ParseOptions, row_set::RowSet};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::{
+    dialect::{parse_options, DialectArg, FixedWidthArg},
+    views::RowNumbers,
+    PyCSVError,
+};
+
+type Rows = Box<dyn Iterator<Item = io::Result<Vec<String>>> + Send>;
+
+/// The cell's text as it is in the file, or its value as read with `options` if `decode` is set
+pub(crate) fn cell_text(cell: &Cell, decode: bool, options: &ParseOptions) -> String {
+    if decode {
+        cell.value(options).into_owned()
+    } else {
+        cell.raw().to_string()
+    }
+}
+
+/// A Python `range` of line numbers
+pub(crate) struct RowRange {
+    start: usize,
+    stop: usize,
+    step: usize,
+}
+
+impl<'source> FromPyObject<'source> for RowRange {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let step: isize = ob.getattr("step")?.extract()?;
+        if step <= 0 {
+            return Err(PyValueError::new_err("row ranges must be ascending"));
+        }
+
+        Ok(Self {
+            start: ob.getattr("start")?.extract()?,
+            stop: ob.getattr("stop")?.extract()?,
+            step: step as usize,
+        })
+    }
+}
+
+impl RowRange {
+    fn contains(&self, row: usize) -> bool {
+        (self.start..self.stop).contains(&row) && (row - self.start).is_multiple_of(self.step)
+    }
+}
+
+/// Which rows to yield, all of them if neither line numbers nor ranges are given
+struct RowFilter {
+    row_numbers: Option<RowSet>,
+    row_ranges: Option<Vec<RowRange>>,
+    /// Line number after which no more rows are included, if any rows are filtered
+    end: Option<usize>,
+}
+
+impl RowFilter {
+    fn new(row_numbers: Option<RowSet>, row_ranges: Option<Vec<RowRange>>) -> Self {
+        let end = (row_numbers.is_some() || row_ranges.is_some()).then(|| {
+            let numbers_end = row_numbers
+                .iter()
+                .filter_map(|r| r.iter().next_back())
+                .map(|n| n + 1);
+            let ranges_end = row_ranges.iter().flatten().map(|range| range.stop);
+
+            numbers_end.chain(ranges_end).max().unwrap_or(0)
+        });
+
+        Self {
+            row_numbers,
+            row_ranges,
+            end,
+        }
+    }
+
+    fn includes(&self, row: usize) -> bool {
+        match (&self.row_numbers, &self.row_ranges) {
+            (None, None) => true,
+            (row_numbers, row_ranges) => {
+                row_numbers.as_ref().is_some_and(|r| r.contains(row))
+                    || row_ranges
+                        .as_ref()
+                        .is_some_and(|r| r.iter().any(|range| range.contains(row)))
+            }
+        }
+    }
+
+    /// Whether none of the rows from `row` onwards are included, so the rest of the file doesn't need to be read
+    fn exhausted(&self, row: usize) -> bool {
+        self.end.is_some_and(|end| row >= end)
+    }
+}
+
+/// Lazy iterator over the rows of a file, the file is closed when the iterator is exhausted, closed or dropped
+#[pyclass(name = "RowIterator", module = "csv_gp")]
+pub(crate) struct PyRowIterator {
+    rows: Option<Rows>,
+    filter: RowFilter,
+    next_row: usize,
+}
+
+impl PyRowIterator {
+    fn next_row(&mut self) -> Result<Option<(usize, Vec<String>)>, CSVError> {
+        while let Some(rows) = &mut self.rows {
+            if self.filter.exhausted(self.next_row) {
+                break;
+            }
+
+            let Some(cells) = rows.next().transpose()? else {
+                break;
+            };
+            let row = self.next_row;
+            self.next_row += 1;
+
+            if self.filter.includes(row) {
+                return Ok(Some((row, cells)));
+            }
+        }
+
+        self.close();
+        Ok(None)
+    }
+}
+
+#[pymethods]
+impl PyRowIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> PyResult<Option<(usize, Vec<String>)>> {
+        self.next_row().map_err(|e| {
+            self.close();
+            PyCSVError(e).into()
+        })
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(&mut self, _exc_type: &PyAny, _exc_value: &PyAny, _traceback: &PyAny) {
+        self.close()
+    }
+
+    /// Closes the file, the iterator doesn't yield any more rows afterwards
+    fn close(&mut self) {
+        self.rows = None;
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (path, delimiter, encoding=None, row_numbers=None, row_ranges=None, trim_whitespace_outside_quotes=false, decode=false, skip_lines=0, comment_prefix=None, fixed_width=None))]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn iter_rows(
+    path: String,
+    delimiter: DialectArg,
+    encoding: Option<String>,
+    row_numbers: Option<RowNumbers>,
+    row_ranges: Option<Vec<RowRange>>,
+    trim_whitespace_outside_quotes: bool,
+    decode: bool,
+    skip_lines: usize,
+    comment_prefix: Option<String>,
+    fixed_width: Option<FixedWidthArg>,
+) -> Result<PyRowIterator, PyCSVError> {
+    let dialect = delimiter.resolve(encoding, trim_whitespace_outside_quotes);
+    let options = parse_options(&dialect, skip_lines, comment_prefix, fixed_width)?;
+    let rows = ::csv_gp::parser::parse_file_with_options(
+        path,
+        dialect.delimiter.clone(),
+        &dialect.encoding,
+        &options,
+    )?
+    .map(move |row| {
+        row.map(|cells| {
+            cells
+                .iter()
+                .map(|c| cell_text(c, decode, &options))
+                .collect()
+        })
+    });
+
+    Ok(PyRowIterator {
+        rows: Some(Box::new(rows)),
+        filter: RowFilter::new(row_numbers.map(|r| r.0), row_ranges),
+        next_row: 0,
+    })
+}
@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use ::csv_gp::{
+    checker::CheckOptions,
+    spreadsheet,
+    valid_file::{Keep, ValidFileOptions},
+};
+use pyo3::prelude::*;
+use regex::Regex;
+
+use crate::{footer_options, output_format, PyCSVDetails, PyCSVError};
+
+#[pyfunction]
+#[allow(clippy::too_many_arguments)]
+#[pyo3(signature = (path, sheet=None, valid_rows_output_path=None, duplicate_key_columns=None, deduplicate=None, replace_invisible_characters=None, repair_mojibake=false, neutralise_formulas=false, formula_allowlist=None, summary_examples=None, max_issues=None, max_rows=None, valid_rows_output_format="csv", rejected_rows_output_path=None, footer_rows=None, footer_patterns=None, footer_record_count=None, footer_short_rows=false))]
+pub(crate) fn check_sheet(
+    py: Python<'_>,
+    path: String,
+    sheet: Option<String>,
+    valid_rows_output_path: Option<&str>,
+    duplicate_key_columns: Option<Vec<usize>>,
+    deduplicate: Option<&str>,
+    replace_invisible_characters: Option<String>,
+    repair_mojibake: bool,
+    neutralise_formulas: bool,
+    formula_allowlist: Option<&str>,
+    summary_examples: Option<usize>,
+    max_issues: Option<usize>,
+    max_rows: Option<usize>,
+    valid_rows_output_format: &str,
+    rejected_rows_output_path: Option<PathBuf>,
+    footer_rows: Option<usize>,
+    footer_patterns: Option<Vec<&str>>,
+    footer_record_count: Option<&str>,
+    footer_short_rows: bool,
+) -> PyResult<PyCSVDetails> {
+    let options = CheckOptions {
+        duplicate_key_columns,
+        valid_file: ValidFileOptions {
+            deduplicate: deduplicate
+                .map(str::parse::<Keep>)
+                .transpose()
+                .map_err(|e| PyCSVError(e.into()))?,
+            replace_invisible_characters,
+            repair_mojibake,
+            neutralise_formulas,
+            formula_allowlist: formula_allowlist
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| PyCSVError(e.into()))?,
+            format: output_format(valid_rows_output_format, None)?,
+            rejected_rows_path: rejected_rows_output_path,
+        },
+        summary_examples,
+        max_issues,
+        max_rows,
+        footer: footer_rows
+            .map(|last_rows| {
+                footer_options(
+                    last_rows,
+                    footer_patterns,
+                    footer_short_rows,
+                    footer_record_count,
+                )
+            })
+            .transpose()
+            .map_err(PyCSVError)?,
+        ..Default::default()
+    };
+
+    let result = py.allow_threads(|| {
+        spreadsheet::check_sheet(path, sheet.as_deref(), valid_rows_output_path, &options)
+    });
+
+    Ok(PyCSVDetails::new(result.map_err(PyCSVError)?))
+}
@@ -0,0 +1,364 @@
+// the code pyo3 0.19 generates for the set operators returning a `RowSet` trips this lint on newer compilers
+#![allow(non_local_definitions)]
+
+use std::sync::Arc;
+
+use ::csv_gp::{csv_details::CSVDetails, row_set::RowSet};
+use pyo3::{
+    basic::CompareOp,
+    exceptions::{PyIndexError, PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyFrozenSet, PySet},
+};
+
+/// Number of line numbers fetched at a time when iterating over a row set
+const ITER_CHUNK_SIZE: usize = 1024;
+
+type RowSetField = Arc<dyn Fn(&CSVDetails) -> &RowSet + Send + Sync>;
+
+fn resolve_index(index: isize, len: usize) -> PyResult<usize> {
+    let resolved = if index < 0 {
+        index + len as isize
+    } else {
+        index
+    };
+
+    if resolved < 0 || resolved as usize >= len {
+        Err(PyIndexError::new_err("index out of range"))
+    } else {
+        Ok(resolved as usize)
+    }
+}
+
+fn repr_of(name: &str, values: impl Iterator<Item = usize>, len: usize) -> String {
+    const SHOWN: usize = 10;
+
+    let shown = values
+        .take(SHOWN)
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    if len > SHOWN {
+        format!("{name}([{shown}, ...])")
+    } else {
+        format!("{name}([{shown}])")
+    }
+}
+
+/// Where the line numbers of a row set come from
+#[derive(Clone)]
+enum RowSetSource {
+    /// A field of a CSVDetails
+    View {
+        details: Arc<CSVDetails>,
+        field: RowSetField,
+    },
+    /// The result of a set operation
+    Owned(Arc<RowSet>),
+}
+
+impl RowSetSource {
+    fn rows(&self) -> &RowSet {
+        match self {
+            Self::View { details, field } => field(details),
+            Self::Owned(rows) => rows,
+        }
+    }
+}
+
+/// Lazy, read-only view of a set of line numbers in a CSVDetails, which avoids copying the set into Python
+#[pyclass(name = "RowSet", module = "csv_gp")]
+pub(crate) struct PyRowSet {
+    source: RowSetSource,
+}
+
+impl PyRowSet {
+    pub(crate) fn new(
+        details: &Arc<CSVDetails>,
+        field: impl Fn(&CSVDetails) -> &RowSet + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            source: RowSetSource::View {
+                details: details.clone(),
+                field: Arc::new(field),
+            },
+        }
+    }
+
+    fn owned(rows: RowSet) -> Self {
+        Self {
+            source: RowSetSource::Owned(Arc::new(rows)),
+        }
+    }
+
+    pub(crate) fn rows(&self) -> &RowSet {
+        self.source.rows()
+    }
+}
+
+/// Line numbers passed as a RowSet or any iterable of ints
+pub(crate) struct RowNumbers(pub(crate) RowSet);
+
+impl<'source> FromPyObject<'source> for RowNumbers {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        if let Ok(rows) = ob.extract::<PyRef<PyRowSet>>() {
+            let rows = rows.rows();
+            if rows.is_truncated() {
+                return Err(PyValueError::new_err(
+                    "only the first line numbers of the row set were kept in summary mode",
+                ));
+            }
+            return Ok(Self(rows.clone()));
+        }
+
+        ob.iter()?
+            .map(|row| row?.extract::<usize>())
+            .collect::<PyResult<_>>()
+            .map(Self)
+    }
+}
+
+/// Line numbers of the other operand of a set operation or comparison, which must be a set
+fn other_set(other: &PyAny) -> Option<RowSet> {
+    if let Ok(other) = other.extract::<PyRef<PyRowSet>>() {
+        Some(other.rows().clone())
+    } else if other.downcast::<PySet>().is_ok() || other.downcast::<PyFrozenSet>().is_ok() {
+        RowNumbers::extract(other).ok().map(|rows| rows.0)
+    } else {
+        None
+    }
+}
+
+/// The other operand of a set operation, anything other than a set making the operation return `NotImplemented`
+struct SetOperand(RowSet);
+
+impl<'source> FromPyObject<'source> for SetOperand {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        other_set(ob)
+            .map(Self)
+            .ok_or_else(|| PyTypeError::new_err("expected a set of line numbers"))
+    }
+}
+
+#[pymethods]
+impl PyRowSet {
+    fn __len__(&self) -> usize {
+        self.rows().len()
+    }
+
+    fn __contains__(&self, row: usize) -> bool {
+        self.rows().contains(row)
+    }
+
+    /// Indexes into the kept line numbers, as only the first examples are kept in summary mode
+    fn __getitem__(&self, index: isize) -> PyResult<usize> {
+        let rows = self.rows();
+        let index = resolve_index(index, rows.kept_len())?;
+
+        Ok(rows.select(index).expect("index is within bounds"))
+    }
+
+    fn __iter__(&self) -> PyRowSetIter {
+        PyRowSetIter {
+            source: self.source.clone(),
+            buffer: Vec::new(),
+            next_row: 0,
+        }
+    }
+
+    /// Compares equal to a list of the same line numbers in ascending order, or a set of the same line numbers, and
+    /// compares to sets as subsets or supersets. Only the kept line numbers are compared in summary mode.
+    fn __richcmp__(&self, other: &PyAny, op: CompareOp) -> PyObject {
+        let py = other.py();
+        let rows = self.rows();
+
+        let Some(other) = other_set(other) else {
+            return match (op, other.extract::<Vec<usize>>()) {
+                (CompareOp::Eq, Ok(other)) => rows.iter().eq(other).into_py(py),
+                (CompareOp::Ne, Ok(other)) => (!rows.iter().eq(other)).into_py(py),
+                _ => py.NotImplemented(),
+            };
+        };
+
+        let subset = rows.iter().all(|row| other.contains(row));
+        let superset = other.iter().all(|row| rows.contains(row));
+
+        let result = match op {
+            CompareOp::Eq => subset && superset,
+            CompareOp::Ne => !(subset && superset),
+            CompareOp::Le => subset,
+            CompareOp::Lt => subset && !superset,
+            CompareOp::Ge => superset,
+            CompareOp::Gt => superset && !subset,
+        };
+        result.into_py(py)
+    }
+
+    fn __or__(&self, other: SetOperand) -> Self {
+        Self::owned(self.rows().iter().chain(other.0.iter()).collect())
+    }
+
+    fn __ror__(&self, other: SetOperand) -> Self {
+        self.__or__(other)
+    }
+
+    fn __and__(&self, other: SetOperand) -> Self {
+        Self::owned(
+            self.rows()
+                .iter()
+                .filter(|&row| other.0.contains(row))
+                .collect(),
+        )
+    }
+
+    fn __rand__(&self, other: SetOperand) -> Self {
+        self.__and__(other)
+    }
+
+    fn __sub__(&self, other: SetOperand) -> Self {
+        Self::owned(
+            self.rows()
+                .iter()
+                .filter(|&row| !other.0.contains(row))
+                .collect(),
+        )
+    }
+
+    fn __rsub__(&self, other: SetOperand) -> Self {
+        let rows = self.rows();
+        Self::owned(other.0.iter().filter(|&row| !rows.contains(row)).collect())
+    }
+
+    fn __xor__(&self, other: SetOperand) -> Self {
+        let rows = self.rows();
+        Self::owned(
+            rows.iter()
+                .filter(|&row| !other.0.contains(row))
+                .chain(other.0.iter().filter(|&row| !rows.contains(row)))
+                .collect(),
+        )
+    }
+
+    fn __rxor__(&self, other: SetOperand) -> Self {
+        self.__xor__(other)
+    }
+
+    fn isdisjoint(&self, other: RowNumbers) -> bool {
+        !other.0.iter().any(|row| self.rows().contains(row))
+    }
+
+    fn __repr__(&self) -> String {
+        let rows = self.rows();
+        repr_of("RowSet", rows.iter(), rows.len())
+    }
+}
+
+#[pyclass(module = "csv_gp")]
+pub(crate) struct PyRowSetIter {
+    source: RowSetSource,
+    /// Line numbers already fetched, in reverse order so they can be popped off the end
+    buffer: Vec<usize>,
+    next_row: usize,
+}
+
+#[pymethods]
+impl PyRowSetIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<usize> {
+        if self.buffer.is_empty() {
+            self.buffer = self
+                .source
+                .rows()
+                .iter_from(self.next_row)
+                .take(ITER_CHUNK_SIZE)
+                .collect();
+            self.buffer.reverse();
+        }
+
+        let row = self.buffer.pop()?;
+        self.next_row = row + 1;
+        Some(row)
+    }
+}
+
+/// Lazy, read-only view of the number of columns per line in a CSVDetails
+#[pyclass(name = "ColumnCounts", module = "csv_gp")]
+pub(crate) struct PyColumnCounts {
+    details: Arc<CSVDetails>,
+}
+
+impl PyColumnCounts {
+    pub(crate) fn new(details: &Arc<CSVDetails>) -> Self {
+        Self {
+            details: details.clone(),
+        }
+    }
+}
+
+#[pymethods]
+impl PyColumnCounts {
+    fn __len__(&self) -> usize {
+        self.details.column_count_per_line.len()
+    }
+
+    fn __getitem__(&self, index: isize) -> PyResult<usize> {
+        let counts = &self.details.column_count_per_line;
+        let index = resolve_index(index, counts.len())?;
+
+        Ok(counts.get(index).expect("index is within bounds"))
+    }
+
+    fn __iter__(&self) -> PyColumnCountsIter {
+        PyColumnCountsIter {
+            details: self.details.clone(),
+            next_line: 0,
+        }
+    }
+
+    /// Compares equal to a list of the same column counts
+    fn __richcmp__(&self, other: &PyAny, op: CompareOp) -> PyObject {
+        let py = other.py();
+
+        let equal = if let Ok(other) = other.extract::<PyRef<PyColumnCounts>>() {
+            self.details.column_count_per_line == other.details.column_count_per_line
+        } else if let Ok(other) = other.extract::<Vec<usize>>() {
+            self.details.column_count_per_line.iter().eq(other)
+        } else {
+            return py.NotImplemented();
+        };
+
+        match op {
+            CompareOp::Eq => equal.into_py(py),
+            CompareOp::Ne => (!equal).into_py(py),
+            _ => py.NotImplemented(),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        let counts = &self.details.column_count_per_line;
+        repr_of("ColumnCounts", counts.iter(), counts.len())
+    }
+}
+
+#[pyclass(module = "csv_gp")]
+pub(crate) struct PyColumnCountsIter {
+    details: Arc<CSVDetails>,
+    next_line: usize,
+}
+
+#[pymethods]
+impl PyColumnCountsIter {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> Option<usize> {
+        let count = self.details.column_count_per_line.get(self.next_line)?;
+        self.next_line += 1;
+        Some(count)
+    }
+}
@@ -1,3 +1,8 @@
+import _thread
+import asyncio
+import collections.abc
+import ctypes
+import json
 from pathlib import Path
 from tempfile import NamedTemporaryFile
 
@@ -28,8 +33,8 @@ def test_kitchen_sink():
 
 
 def test_invalid_delimiter():
-    with pytest.raises(ValueError, match="expected a string of length 1"):
-        csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",+", encoding="utf-8")
+    with pytest.raises(ValueError, match="delimiter can't be empty"):
+        csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), "", encoding="utf-8")
 
 
 def test_different_encoding():
@@ -88,6 +93,24 @@ def test_output_file():
         assert temp_file.read() == (FIXTURES / "kitchen_sink_valid.csv").read_bytes()
 
 
+def test_strict():
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", strict=True)
+
+    # `""test""` is an empty quoted cell followed by text
+    assert result.strict.counts == {"text_after_closing_quote": 1}
+    assert [(f.violation, f.byte_offset, f.record) for f in result.strict.findings] == [
+        ("text_after_closing_quote", 82, 7),
+    ]
+    assert result.strict.record_count == 10
+    assert list(result.strict.differing_rows) == [7]
+
+
+def test_not_strict():
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8")
+
+    assert result.strict is None
+
+
 def test_get_rows():
     result = csv_gp.get_rows(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", row_numbers={0, 1, 3})
 
@@ -95,6 +118,22 @@ def test_get_rows():
     assert result == [(0, ["a", "b"]), (1, ["", ""]), (3, [])]
 
 
+def test_get_rows_decode():
+    path = str(FIXTURES / "kitchen_sink.csv")
+
+    raw = csv_gp.get_rows(path, ",", encoding="utf-8", row_numbers={2, 4, 6})
+    decoded = csv_gp.get_rows(path, ",", encoding="utf-8", row_numbers={2, 4, 6}, decode=True)
+
+    assert raw == [(2, ['""', ""]), (4, ['"test,"', "with delim"]), (6, ['"""test"""', "with quoted quote"])]
+    assert decoded == [(2, ["", ""]), (4, ["test,", "with delim"]), (6, ['"test"', "with quoted quote"])]
+
+
+def test_iter_rows_decode():
+    rows = list(csv_gp.iter_rows(str(FIXTURES / "kitchen_sink.csv"), ",", "utf-8", row_numbers={5}, decode=True))
+
+    assert rows == [(5, ["test\n", "with newline"])]
+
+
 def test_unknown_encoding_ibm852():
     with pytest.raises(csv_gp.UnknownEncoding, match="unknown encoding ibm852"):
         csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="ibm852")
@@ -158,3 +197,855 @@ def test_incorrect_quote():
     assert result.column_count_per_line == [3, 3, 2, 1]
     assert result.valid_rows == {0}
     assert not result.header_messed_up
+
+
+def test_duplicates():
+    result = csv_gp.check_file(str(FIXTURES / "duplicates.csv"), ",", encoding="utf-8", duplicate_key_columns=[0, 1])
+
+    assert result
+    assert result.duplicate_rows == [[1, 3, 6]]
+    assert result.duplicate_key_rows == [[1, 3, 6], [2, 5]]
+
+
+def test_duplicates_no_key_columns():
+    result = csv_gp.check_file(str(FIXTURES / "duplicates.csv"), ",", encoding="utf-8")
+
+    assert result
+    assert result.duplicate_key_rows is None
+
+
+def test_output_file_deduplicate():
+    with NamedTemporaryFile() as temp_file:
+        csv_gp.check_file(
+            str(FIXTURES / "duplicates.csv"),
+            ",",
+            encoding="utf-8",
+            valid_rows_output_path=temp_file.name,
+            deduplicate="last",
+        )
+
+        assert temp_file.read() == b"id,name,amount\n2,Bob,20\n3,Carol,30\n2,Bob,25\n1,Alice,10\n"
+
+
+def test_invalid_deduplicate():
+    with pytest.raises(ValueError, match="invalid value middle for keep"):
+        csv_gp.check_file(str(FIXTURES / "duplicates.csv"), ",", encoding="utf-8", deduplicate="middle")
+
+
+def test_whitespace():
+    result = csv_gp.check_file(str(FIXTURES / "whitespace.csv"), ",", encoding="utf-8")
+
+    assert result
+    assert result.whitespace_padding == [2]
+    assert result.whitespace_outside_quotes == [1]
+    assert result.incorrect_cell_quote == [1]
+    assert result.valid_rows == {0, 2, 3}
+
+
+def test_whitespace_trimmed():
+    result = csv_gp.check_file(
+        str(FIXTURES / "whitespace.csv"), ",", encoding="utf-8", trim_whitespace_outside_quotes=True
+    )
+
+    assert result
+    assert result.whitespace_padding == [2]
+    assert result.whitespace_outside_quotes == []
+    assert result.incorrect_cell_quote == []
+    assert result.valid_rows == {0, 1, 2, 3}
+
+
+def test_get_rows_whitespace_trimmed():
+    result = csv_gp.get_rows(
+        str(FIXTURES / "whitespace.csv"), ",", encoding="utf-8", row_numbers={1}, trim_whitespace_outside_quotes=True
+    )
+
+    assert result == [(1, ["1", '"two"', "3"])]
+
+
+def test_invisible_characters():
+    result = csv_gp.check_file(str(FIXTURES / "invisible_characters.csv"), ",", encoding="utf-8")
+
+    assert result
+    assert result.invisible_character_counts == {"null": 1, "zero_width": 1, "non_breaking_space": 1}
+    assert result.invisible_character_locations == {
+        "null": [(1, 0)],
+        "zero_width": [(2, 1)],
+        "non_breaking_space": [(2, 1)],
+    }
+
+
+def test_output_file_replace_invisible_characters():
+    with NamedTemporaryFile() as temp_file:
+        csv_gp.check_file(
+            str(FIXTURES / "invisible_characters.csv"),
+            ",",
+            encoding="utf-8",
+            valid_rows_output_path=temp_file.name,
+            replace_invisible_characters="",
+        )
+
+        assert temp_file.read() == b"a,b\nxy,ok\nfine,zerospace\n"
+
+
+def test_mojibake():
+    result = csv_gp.check_file(str(FIXTURES / "mojibake.csv"), ",", encoding="utf-8")
+
+    assert result
+    assert result.mojibake == {("UTF-8", "windows-1252"): [1]}
+
+
+def test_mojibake_wrong_encoding():
+    result = csv_gp.check_file(str(FIXTURES / "utf8.csv"), ",", encoding="windows-1252")
+
+    assert result
+    assert result.mojibake == {("UTF-8", "windows-1252"): [1]}
+
+    result = csv_gp.check_file(str(FIXTURES / "utf8.csv"), ",", encoding="utf-8")
+
+    assert result
+    assert result.mojibake == {}
+
+
+def test_output_file_repair_mojibake():
+    with NamedTemporaryFile() as temp_file:
+        csv_gp.check_file(
+            str(FIXTURES / "mojibake.csv"),
+            ",",
+            encoding="utf-8",
+            valid_rows_output_path=temp_file.name,
+            repair_mojibake=True,
+        )
+
+        assert temp_file.read().decode() == "name,city\nMüller,Zürich\nSmith,London\n"
+
+
+def test_formula_injection():
+    result = csv_gp.check_file(str(FIXTURES / "formula_injection.csv"), ",", encoding="utf-8")
+
+    assert result
+    assert result.formula_injection == [(1, 0), (2, 0)]
+
+
+def test_output_file_neutralise_formulas():
+    with NamedTemporaryFile() as temp_file:
+        csv_gp.check_file(
+            str(FIXTURES / "formula_injection.csv"),
+            ",",
+            encoding="utf-8",
+            valid_rows_output_path=temp_file.name,
+            neutralise_formulas=True,
+        )
+
+        assert temp_file.read() == b'description,amount\n\'=1+2,-10.50\n"\'@SUM(A1)",+5\nrefund,-3\n'
+
+
+def test_output_file_formula_allowlist():
+    with NamedTemporaryFile() as temp_file:
+        result = csv_gp.check_file(
+            str(FIXTURES / "formula_injection.csv"),
+            ",",
+            encoding="utf-8",
+            valid_rows_output_path=temp_file.name,
+            neutralise_formulas=True,
+            formula_allowlist="^@SUM",
+        )
+
+        assert result.formula_injection == [(1, 0)]
+        assert temp_file.read() == b'description,amount\n\'=1+2,-10.50\n"@SUM(A1)",+5\nrefund,-3\n'
+
+
+def test_row_set_view():
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8")
+
+    assert isinstance(result.valid_rows, csv_gp.RowSet)
+    assert len(result.valid_rows) == 6
+    assert 4 in result.valid_rows
+    assert 3 not in result.valid_rows
+    assert list(result.valid_rows) == [0, 1, 2, 4, 5, 6]
+    assert result.valid_rows[0] == 0
+    assert result.valid_rows[-1] == 6
+    assert result.valid_rows != [0, 1]
+    assert not result.quoted_quote_correctly != [6]
+    assert repr(result.too_few_columns) == "RowSet([8])"
+    with pytest.raises(IndexError):
+        result.too_few_columns[1]
+
+
+def test_row_set_operators():
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8")
+    valid_rows = result.valid_rows
+
+    assert isinstance(valid_rows, collections.abc.Set)
+    assert isinstance(valid_rows | {8}, csv_gp.RowSet)
+    assert valid_rows | {8} == [0, 1, 2, 4, 5, 6, 8]
+    assert {8} | valid_rows == [0, 1, 2, 4, 5, 6, 8]
+    assert valid_rows & {1, 3} == [1]
+    assert valid_rows - result.quoted_quote == [0, 1, 2, 4, 5]
+    assert {1, 3} - valid_rows == [3]
+    assert valid_rows ^ {0, 3} == [1, 2, 3, 4, 5, 6]
+    assert result.quoted_quote_correctly <= valid_rows
+    assert result.quoted_quote_correctly < valid_rows
+    assert not result.quoted_quote <= valid_rows
+    assert not valid_rows <= {0, 1}
+    assert valid_rows >= frozenset({0, 1})
+    assert valid_rows.isdisjoint([3, 8])
+    with pytest.raises(TypeError):
+        valid_rows | [8]
+
+
+def test_get_rows_of_row_set():
+    path = str(FIXTURES / "kitchen_sink.csv")
+    r = csv_gp.check_file(path, ",", "utf-8")
+
+    rows = csv_gp.get_rows(path, ",", "utf-8", r.valid_rows)
+
+    assert [i for i, _ in rows] == list(r.valid_rows)
+    assert csv_gp.get_rows(path, ",", encoding="utf-8", row_numbers=r.valid_rows - {0}) == rows[1:]
+    assert csv_gp.get_rows(path, ",", encoding="utf-8", row_numbers=iter([1, 2])) == rows[1:3]
+    with csv_gp.iter_rows(path, ",", encoding="utf-8", row_numbers=r.too_few_columns) as it:
+        assert [i for i, _ in it] == [8]
+
+
+def test_row_set_view_large(tmp_path):
+    path = tmp_path / "large.csv"
+    path.write_text("a,b\n" + "x,y\n" * 2500 + "z\n" + "x,y\n" * 2500)
+
+    result = csv_gp.check_file(str(path), ",", encoding="utf-8")
+
+    assert list(result.too_few_columns) == [2501]
+    assert list(result.valid_rows) == [i for i in range(5002) if i != 2501]
+    assert repr(result.valid_rows) == "RowSet([0, 1, 2, 3, 4, 5, 6, 7, 8, 9, ...])"
+
+
+def test_column_counts_view():
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8")
+
+    assert isinstance(result.column_count_per_line, csv_gp.ColumnCounts)
+    assert len(result.column_count_per_line) == 10
+    assert result.column_count_per_line[3] == 0
+    assert result.column_count_per_line[-1] == 3
+    assert list(result.column_count_per_line) == [2, 2, 2, 0, 2, 2, 2, 2, 1, 3]
+
+
+def test_summary_examples():
+    result = csv_gp.check_file(str(FIXTURES / "incorrect_quote.csv"), ",", encoding="utf-8", summary_examples=1)
+
+    assert result
+    assert result.example_limit == 1
+    assert len(result.incorrect_cell_quote) == 3
+    assert list(result.incorrect_cell_quote) == [1]
+    assert result.incorrect_cell_quote == {1}
+    assert result.incorrect_cell_quote[-1] == 1
+    assert len(result.too_few_columns) == 2
+    assert list(result.too_few_columns) == [2]
+    assert list(result.column_count_per_line) == []
+    assert result.duplicate_rows == []
+    assert not result.header_messed_up
+
+
+def test_summary_examples_output_file():
+    with NamedTemporaryFile() as temp_file:
+        result = csv_gp.check_file(
+            str(FIXTURES / "kitchen_sink.csv"),
+            ",",
+            encoding="utf-8",
+            valid_rows_output_path=temp_file.name,
+            summary_examples=1,
+        )
+
+        assert result.valid_rows == {0, 1, 2, 4, 5, 6}
+        assert temp_file.read() == (FIXTURES / "kitchen_sink_valid.csv").read_bytes()
+
+
+def test_summary_examples_conflict():
+    with pytest.raises(ValueError, match="deduplicate can't be used together with summary mode"):
+        csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", summary_examples=1, deduplicate="first")
+
+
+def test_max_issues():
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", max_issues=2)
+
+    assert result.partial.reason == "issue_limit"
+    assert result.partial.rows_checked == 8
+    assert result.partial.extrapolate(1) is None
+    assert result.blank_rows == [3]
+    assert result.incorrect_cell_quote == [7]
+    assert result.too_few_columns == []
+
+
+def test_max_rows():
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", max_rows=5)
+
+    assert result.partial.reason == "row_limit"
+    assert result.partial.rows_read == 5
+    assert result.quoted_delimiter == [4]
+    assert result.quoted_newline == []
+
+
+def test_sample_every():
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", sample_every=2)
+
+    assert result.partial.reason == "sample"
+    assert result.partial.rows_checked == 5
+    assert result.partial.rows_read == 10
+    assert result.partial.extrapolate(1) == 2
+    assert result.valid_rows == [0, 2, 4, 6]
+    assert result.quoted_quote == [6]
+    assert result.too_few_columns == [8]
+    assert len(result.column_count_per_line) == 10
+
+
+def test_sample_fraction_seeded():
+    first = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", sample_fraction=0.5, sample_seed=1)
+    second = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", sample_fraction=0.5, sample_seed=1)
+
+    assert first.partial.reason == "sample"
+    assert first.valid_rows == list(second.valid_rows)
+    assert first.partial.rows_checked == second.partial.rows_checked
+
+
+def test_sample_with_output_file():
+    with NamedTemporaryFile() as temp_file:
+        with pytest.raises(ValueError, match="sampling can't be used together with valid rows output"):
+            csv_gp.check_file(
+                str(FIXTURES / "kitchen_sink.csv"),
+                ",",
+                encoding="utf-8",
+                valid_rows_output_path=temp_file.name,
+                sample_every=2,
+            )
+
+
+def test_sample_conflict():
+    with pytest.raises(ValueError, match="sample_every can't be used together with sample_fraction"):
+        csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", sample_every=2, sample_fraction=0.5)
+
+
+def test_whole_file_not_partial():
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", max_rows=100)
+
+    assert result.partial is None
+
+
+def test_progress_callback():
+    progress = []
+
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", progress_callback=progress.append)
+
+    assert result
+    assert progress
+    last = progress[-1]
+    assert isinstance(last, csv_gp.Progress)
+    assert last.rows_processed == 10
+    assert last.issues_found == 4
+    assert last.bytes_read == last.total_bytes == (FIXTURES / "kitchen_sink.csv").stat().st_size
+
+
+def test_progress_callback_exception():
+    def callback(progress):
+        raise RuntimeError("stop")
+
+    with pytest.raises(RuntimeError, match="stop"):
+        csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", progress_callback=callback)
+
+
+def test_keyboard_interrupt():
+    # signals are handled when the check reports its progress, which it always does at least once
+    with pytest.raises(KeyboardInterrupt):
+        csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", progress_callback=lambda _: _thread.interrupt_main())
+
+
+def test_check_file_async():
+    async def check():
+        return await csv_gp.check_file_async(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8", max_rows=5)
+
+    result = asyncio.run(check())
+
+    assert isinstance(result, csv_gp.CSVDetails)
+    assert result.quoted_delimiter == [4]
+    assert result.partial.reason == "row_limit"
+
+
+def test_check_file_async_error():
+    async def check():
+        return await csv_gp.check_file_async(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="foo")
+
+    with pytest.raises(csv_gp.UnknownEncoding):
+        asyncio.run(check())
+
+
+def test_get_rows_async():
+    async def get_rows():
+        return await asyncio.gather(
+            csv_gp.get_rows_async(str(FIXTURES / "kitchen_sink.csv"), ",", "utf-8", {4}),
+            csv_gp.get_rows_async(str(FIXTURES / "kitchen_sink.csv"), ",", "utf-8", row_numbers={8}),
+        )
+
+    assert asyncio.run(get_rows()) == [[(4, ['"test,"', "with delim"])], [(8, ["too few"])]]
+
+
+
+def test_iter_rows():
+    rows = csv_gp.iter_rows(str(FIXTURES / "kitchen_sink.csv"), ",", "utf-8")
+
+    assert isinstance(rows, csv_gp.RowIterator)
+    assert next(rows) == (0, ["a", "b"])
+    assert [row for row, _ in rows] == [1, 2, 3, 4, 5, 6, 7, 8, 9]
+    assert list(rows) == []
+
+
+def test_iter_rows_filter():
+    rows = csv_gp.iter_rows(
+        str(FIXTURES / "kitchen_sink.csv"), ",", "utf-8", row_numbers={8}, row_ranges=[range(1, 6, 2)]
+    )
+
+    assert list(rows) == [(1, ["", ""]), (3, []), (5, ['"test\n"', "with newline"]), (8, ["too few"])]
+
+
+def test_iter_rows_descending_range():
+    with pytest.raises(ValueError, match="row ranges must be ascending"):
+        csv_gp.iter_rows(str(FIXTURES / "kitchen_sink.csv"), ",", "utf-8", row_ranges=[range(5, 1, -1)])
+
+
+def test_iter_rows_context_manager():
+    with csv_gp.iter_rows(str(FIXTURES / "kitchen_sink.csv"), ",", "utf-8") as rows:
+        assert next(rows) == (0, ["a", "b"])
+
+    assert list(rows) == []
+
+
+def test_iter_rows_close():
+    rows = csv_gp.iter_rows(str(FIXTURES / "kitchen_sink.csv"), ",", "utf-8")
+    rows.close()
+
+    assert list(rows) == []
+
+
+def test_read_valid_rows_capsule():
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8")
+    stream = csv_gp.read_valid_rows(result, str(FIXTURES / "kitchen_sink.csv"), ",", "utf-8")
+
+    capsule = stream.__arrow_c_stream__()
+
+    get_name = ctypes.pythonapi.PyCapsule_GetName
+    get_name.restype = ctypes.c_char_p
+    get_name.argtypes = [ctypes.py_object]
+    assert get_name(capsule) == b"arrow_array_stream"
+    with pytest.raises(ValueError, match="the stream was already read"):
+        stream.__arrow_c_stream__()
+
+
+def test_read_valid_rows_pyarrow():
+    pa = pytest.importorskip("pyarrow")
+    result = csv_gp.check_file(str(FIXTURES / "kitchen_sink.csv"), ",", encoding="utf-8")
+
+    table = pa.RecordBatchReader.from_stream(
+        csv_gp.read_valid_rows(result, str(FIXTURES / "kitchen_sink.csv"), ",", "utf-8")
+    ).read_all()
+
+    assert table.column_names == ["a", "b"]
+    assert table.to_pylist() == [
+        {"a": "", "b": ""},
+        {"a": "", "b": ""},
+        {"a": "test,", "b": "with delim"},
+        {"a": "test\n", "b": "with newline"},
+        {"a": '"test"', "b": "with quoted quote"},
+    ]
+
+
+def test_read_valid_rows_pyarrow_schema():
+    pa = pytest.importorskip("pyarrow")
+    result = csv_gp.check_file(str(FIXTURES / "duplicates.csv"), ",", encoding="utf-8")
+    schema = pa.schema([("id", pa.int64()), ("name", pa.string()), ("amount", pa.float64())])
+
+    table = pa.RecordBatchReader.from_stream(
+        csv_gp.read_valid_rows(result, str(FIXTURES / "duplicates.csv"), ",", "utf-8", schema=schema)
+    ).read_all()
+
+    assert table.schema == schema
+
+
+def test_rejected_rows_output_file():
+    with NamedTemporaryFile() as temp_file:
+        csv_gp.check_file(
+            str(FIXTURES / "kitchen_sink.csv"),
+            ",",
+            encoding="utf-8",
+            rejected_rows_output_path=temp_file.name,
+        )
+
+        assert temp_file.read() == b'\n""test"",with incorrect quoted quote\ntoo few\ntoo, many, columns\n'
+
+
+def test_invalid_output_format():
+    with NamedTemporaryFile() as temp_file:
+        with pytest.raises(ValueError, match="invalid value xlsx for output format"):
+            csv_gp.check_file(
+                str(FIXTURES / "kitchen_sink.csv"),
+                ",",
+                encoding="utf-8",
+                valid_rows_output_path=temp_file.name,
+                valid_rows_output_format="xlsx",
+            )
+
+
+def test_normalised_csv_output_file():
+    with NamedTemporaryFile() as temp_file:
+        csv_gp.check_file(
+            str(FIXTURES / "kitchen_sink.csv"),
+            ",",
+            encoding="utf-8",
+            valid_rows_output_path=temp_file.name,
+            valid_rows_output_format="normalised-csv",
+        )
+
+        assert temp_file.read() == (
+            b'a,b\r\n,\r\n,\r\n"test,",with delim\r\n"test\n",with newline\r\n"""test""",with quoted quote\r\n'
+        )
+
+
+def test_json_lines_output_file():
+    with NamedTemporaryFile() as temp_file:
+        csv_gp.check_file(
+            str(FIXTURES / "kitchen_sink.csv"),
+            ",",
+            encoding="utf-8",
+            valid_rows_output_path=temp_file.name,
+            valid_rows_output_format="jsonl",
+        )
+
+        rows = [json.loads(line) for line in temp_file.read().splitlines()]
+
+    assert rows == [
+        {"a": "", "b": ""},
+        {"a": "", "b": ""},
+        {"a": "test,", "b": "with delim"},
+        {"a": "test\n", "b": "with newline"},
+        {"a": '"test"', "b": "with quoted quote"},
+    ]
+
+
+def test_parquet_output_file():
+    with NamedTemporaryFile() as temp_file:
+        csv_gp.check_file(
+            str(FIXTURES / "kitchen_sink.csv"),
+            ",",
+            encoding="utf-8",
+            valid_rows_output_path=temp_file.name,
+            valid_rows_output_format="parquet",
+        )
+
+        assert temp_file.read(4) == b"PAR1"
+
+
+def test_parquet_output_file_schema():
+    pa = pytest.importorskip("pyarrow")
+    pq = pytest.importorskip("pyarrow.parquet")
+    schema = pa.schema([("id", pa.int64()), ("name", pa.string()), ("amount", pa.float64())])
+
+    with NamedTemporaryFile() as temp_file:
+        csv_gp.check_file(
+            str(FIXTURES / "duplicates.csv"),
+            ",",
+            encoding="utf-8",
+            valid_rows_output_path=temp_file.name,
+            valid_rows_output_format="parquet",
+            valid_rows_output_schema=schema,
+        )
+
+        assert pq.read_table(temp_file.name).schema == schema
+
+
+def test_dialect_preset():
+    path = str(FIXTURES / "mysql.tsv")
+    dialect = csv_gp.Dialect.preset("mysql")
+
+    result = csv_gp.check_file(path, dialect)
+    rows = csv_gp.get_rows(path, dialect, row_numbers=range(3), decode=True)
+
+    assert result.valid_rows == [0, 1, 2]
+    assert result.incorrect_cell_quote == []
+    assert rows == [(0, ["name", "note"]), (1, ["x\ty", '5" screen']), (2, ['"quoted"', "line\nbreak"])]
+
+
+def test_get_rows_requires_row_numbers():
+    with pytest.raises(TypeError):
+        csv_gp.get_rows(str(FIXTURES / "kitchen_sink.csv"), ",", "utf-8")
+    with pytest.raises(TypeError, match="row_numbers"):
+        csv_gp.get_rows(str(FIXTURES / "kitchen_sink.csv"), ",")
+
+
+def test_dialect_presets():
+    assert csv_gp.Dialect.presets() == ["rfc4180", "excel", "excel-tab", "unix", "mysql", "postgres-csv"]
+    assert csv_gp.Dialect.preset("excel-tab") == csv_gp.Dialect("\t", "windows-1252")
+    assert csv_gp.Dialect.preset("unix") == csv_gp.Dialect()
+    assert csv_gp.Dialect.preset("postgres-csv") == csv_gp.Dialect()
+
+    with pytest.raises(ValueError, match="invalid value nope for dialect"):
+        csv_gp.Dialect.preset("nope")
+
+
+def test_dialect_from_file():
+    dialect = csv_gp.Dialect.from_file(str(FIXTURES / "semicolon.toml"))
+
+    assert dialect.delimiter == ";"
+    assert dialect.encoding == "windows-1252"
+    assert csv_gp.get_rows(str(FIXTURES / "semicolon.csv"), dialect, row_numbers={0, 1}) == [(0, ["a", "b"]), (1, ["1", "2"])]
+
+
+def test_dialect_encoding_override():
+    dialect = csv_gp.Dialect(",", "utf-8")
+
+    result = csv_gp.check_file(str(FIXTURES / "mac_roman.csv"), dialect, encoding="macintosh")
+
+    assert result.invalid_character_count == 0
+
+
+def test_invalid_dialect():
+    with pytest.raises(ValueError, match="invalid value , for escape"):
+        csv_gp.Dialect(",", escape=",")
+
+    assert repr(csv_gp.Dialect(escape="\\")) == (
+        "Dialect(delimiter=\",\", encoding=\"utf-8\", quoting=True, escape=\"\\\\\", "
+        "trim_whitespace_outside_quotes=False)"
+    )
+
+
+def test_string_delimiter():
+    path = str(FIXTURES / "string_delimiter.csv")
+
+    result = csv_gp.check_file(path, "~|~")
+
+    assert result.column_count == 3
+    assert result.valid_rows == [0, 1, 2]
+    assert result.quoted_delimiter == [2]
+    assert csv_gp.get_rows(path, "~|~", "utf-8", {2}, decode=True) == [(2, ["x~|~y", "§", ""])]
+
+
+def test_string_delimiter_output_file():
+    path = FIXTURES / "string_delimiter.csv"
+
+    with NamedTemporaryFile() as temp_file:
+        csv_gp.check_file(str(path), "~|~", valid_rows_output_path=temp_file.name)
+
+        assert temp_file.read() == path.read_bytes()
+
+
+def test_string_delimiter_normalised_csv():
+    with NamedTemporaryFile() as temp_file, pytest.raises(
+        ValueError, match="output delimiter must be a single ASCII character"
+    ):
+        csv_gp.check_file(
+            str(FIXTURES / "string_delimiter.csv"),
+            "~|~",
+            valid_rows_output_path=temp_file.name,
+            valid_rows_output_format="normalised-csv",
+        )
+
+
+def test_skip_lines():
+    path = str(FIXTURES / "preamble.csv")
+
+    result = csv_gp.check_file(path, ",", skip_lines=3, comment_prefix="#")
+    rows = csv_gp.get_rows(path, ",", "utf-8", range(3), skip_lines=3, comment_prefix="#")
+
+    assert result.preamble == ["Sales report", "Generated 1 Jan, 2024", ""]
+    assert result.comment_lines == [5]
+    assert result.valid_rows == [0, 1, 2]
+    assert not result.header_messed_up
+    assert rows == [(0, ["a", "b", "c"]), (1, ["1", "2", "3"]), (2, ["4", "5", "6"])]
+
+
+def test_detect_preamble():
+    result = csv_gp.check_file(str(FIXTURES / "preamble.csv"), ",", detect_preamble=True)
+
+    assert result.preamble == ["Sales report", "Generated 1 Jan, 2024", ""]
+    assert result.comment_lines == []
+    assert result.too_few_columns == [2]
+
+
+def test_no_preamble():
+    result = csv_gp.check_file(str(FIXTURES / "preamble.csv"), ",")
+
+    assert result.preamble == []
+    assert result.column_count == 1
+    assert result.too_many_columns == [1, 3, 4, 6]
+
+
+def test_footer():
+    result = csv_gp.check_file(
+        str(FIXTURES / "footer.csv"),
+        ",",
+        footer_rows=2,
+        footer_patterns=["^Total$"],
+        footer_record_count=r"^Record count: (\d+)$",
+    )
+
+    assert result.footer_rows == [4, 5]
+    assert result.too_few_columns == [3]
+    assert result.declared_record_count == 3
+    assert result.record_count_mismatch
+
+
+def test_footer_short_rows():
+    result = csv_gp.check_file(str(FIXTURES / "footer.csv"), ",", footer_rows=1, footer_short_rows=True)
+
+    assert result.footer_rows == [5]
+    assert result.too_few_columns == [3]
+    assert result.declared_record_count is None
+
+
+def test_no_footer():
+    result = csv_gp.check_file(str(FIXTURES / "footer.csv"), ",")
+
+    assert result.footer_rows == []
+    assert result.too_few_columns == [3, 5]
+    assert result.declared_record_count is None
+    assert not result.record_count_mismatch
+
+
+def test_invalid_footer_pattern():
+    with pytest.raises(ValueError, match="invalid pattern"):
+        csv_gp.check_file(str(FIXTURES / "footer.csv"), ",", footer_rows=3, footer_patterns=["("])
+
+
+def test_fixed_width():
+    path = str(FIXTURES / "fixed_width.txt")
+
+    result = csv_gp.check_file(path, ",", fixed_width=[5, 3, 7])
+
+    assert result.column_count == 3
+    assert result.too_few_columns == [2]
+    assert result.too_many_columns == [3]
+    assert result.valid_rows == [0, 1, 4]
+    assert csv_gp.get_rows(path, ",", "utf-8", {4}, fixed_width="0:5,5:3,8:7") == [(4, ["Dan", "2", "1,000"])]
+
+
+def test_fixed_width_to_csv():
+    with NamedTemporaryFile() as temp_file:
+        csv_gp.check_file(
+            str(FIXTURES / "fixed_width.txt"), ",", valid_rows_output_path=temp_file.name, fixed_width="5,3,7"
+        )
+
+        with open(temp_file.name, newline="") as f:
+            assert f.read() == 'NAME,QTY,AMOUNT\r\nAlice,42,12.50\r\nDan,2,"1,000"\r\n'
+
+
+def test_invalid_fixed_width():
+    with pytest.raises(ValueError, match="invalid value 5:0 for fixed width columns"):
+        csv_gp.check_file(str(FIXTURES / "fixed_width.txt"), ",", fixed_width=[5, 0])
+
+
+def test_check_sheet():
+    with NamedTemporaryFile() as temp_file:
+        result = csv_gp.check_sheet(str(FIXTURES / "invoices.xlsx"), "Invoices", valid_rows_output_path=temp_file.name)
+
+        assert result.column_count == 4
+        assert result.valid_rows == [0, 1, 2, 4]
+        assert result.too_few_columns == [5]
+        assert result.too_many_columns == [6]
+        assert result.blank_rows == [3]
+        assert result.quoted_delimiter == []
+
+        with open(temp_file.name, newline="", encoding="utf-8") as f:
+            assert f.read() == (
+                "invoice,supplier,amount,date\r\n"
+                'INV-1,"Acme, Inc.",12.5,2024-01-01\r\n'
+                'INV-2,"Say ""hi""",7,2024-01-02\r\n'
+                "INV-3,Müller GmbH,3,2024-01-03\r\n"
+            )
+
+
+def test_check_first_sheet():
+    result = csv_gp.check_sheet(str(FIXTURES / "invoices.xlsx"))
+
+    assert result.row_count == 1
+    assert result.valid_rows == [0]
+
+
+def test_unknown_sheet():
+    with pytest.raises(ValueError, match="invalid value Nope for sheet"):
+        csv_gp.check_sheet(str(FIXTURES / "invoices.xlsx"), "Nope")
+
+
+def test_diff_files_by_key():
+    result = csv_gp.diff_files(
+        str(FIXTURES / "invoices_old.csv"), str(FIXTURES / "invoices_new.csv"), ",", key_columns=[0]
+    )
+
+    assert result
+    assert result.added_rows == [3]
+    assert result.removed_rows == [2]
+    assert result.unchanged_row_count == 2
+    assert [(r.old_row, r.new_row, r.cells) for r in result.changed_rows] == [(1, 2, [(1, "Acme", "Acme, Inc.")])]
+    assert result.issue_changes == {"too_many_columns": (0, 1), "quoted_delimiter": (0, 1)}
+    assert result.old_details.row_count == 4
+    assert result.new_details.too_many_columns == [3]
+    assert "1 rows were added, 1 rows were removed and 1 rows were changed" in result.report()
+
+
+def test_diff_files_by_position():
+    result = csv_gp.diff_files(str(FIXTURES / "invoices_old.csv"), str(FIXTURES / "invoices_new.csv"), ",")
+
+    assert result.added_rows == []
+    assert result.removed_rows == []
+    assert [r.old_row for r in result.changed_rows] == [1, 2, 3]
+    assert result.changed_rows[2].cells[-1] == (3, None, "late")
+
+
+def test_diff_same_file():
+    path = str(FIXTURES / "invoices_old.csv")
+
+    assert not csv_gp.diff_files(path, path, ",")
+
+
+def test_baseline():
+    baseline = csv_gp.check_file(str(FIXTURES / "invoices_old.csv"), ",")
+    current = csv_gp.check_file(str(FIXTURES / "invoices_new.csv"), ",")
+
+    with NamedTemporaryFile() as temp_file:
+        baseline.save_baseline(temp_file.name)
+
+        comparison = csv_gp.compare_to_baseline(current, temp_file.name)
+        assert comparison.has_regressions
+        assert comparison.regressions == ["quoted_delimiter", "too_many_columns"]
+        assert comparison.deltas == {"quoted_delimiter": (0, 1), "too_many_columns": (0, 1)}
+        assert "There are 2 regressions compared to the baseline." in str(comparison)
+
+        comparison = csv_gp.compare_to_baseline(current, temp_file.name, tolerance=1)
+        assert not comparison.has_regressions
+
+        comparison = csv_gp.compare_to_baseline(current, temp_file.name, issue_tolerances={"quoted_delimiter": "100%"})
+        assert comparison.regressions == ["quoted_delimiter", "too_many_columns"]
+
+        assert not csv_gp.compare_to_baseline(baseline, temp_file.name).has_regressions
+
+
+def test_baseline_of_partial_check():
+    full = csv_gp.check_file(str(FIXTURES / "invoices_old.csv"), ",")
+    partial = csv_gp.check_file(str(FIXTURES / "invoices_new.csv"), ",", max_rows=2)
+
+    with NamedTemporaryFile() as temp_file:
+        full.save_baseline(temp_file.name)
+
+        with pytest.raises(ValueError, match="a partial check can't be used together with the baseline of a full check"):
+            csv_gp.compare_to_baseline(partial, temp_file.name)
+
+
+def test_baseline_issue_counts():
+    result = csv_gp.check_file(str(FIXTURES / "invoices_new.csv"), ",")
+
+    assert result.issue_counts["too_many_columns"] == 1
+    assert result.issue_counts["blank_rows"] == 0
+
+
+def test_invalid_tolerance():
+    result = csv_gp.check_file(str(FIXTURES / "invoices_old.csv"), ",")
+
+    with NamedTemporaryFile() as temp_file:
+        result.save_baseline(temp_file.name)
+
+        with pytest.raises(ValueError, match="invalid value bad_quotes for issue"):
+            csv_gp.compare_to_baseline(result, temp_file.name, issue_tolerances={"bad_quotes": 1})
+        with pytest.raises(ValueError, match="invalid value x for tolerance"):
+            csv_gp.compare_to_baseline(result, temp_file.name, tolerance="x")